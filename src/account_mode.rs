@@ -0,0 +1,257 @@
+use crate::error::WooError;
+use crate::woo::Woo;
+use crate::woo_data_structs::{AccountInfo, AccountInfoRes, WooEnvelope, WooOrder};
+use reqwest::Method;
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Mutex;
+
+/// What an account may trade, set in WOO's web UI.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AccountMode {
+    PureSpot,
+    Margin,
+    Futures,
+    #[serde(other)]
+    Unknown,
+}
+
+impl fmt::Display for AccountMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AccountMode::PureSpot => "PURE_SPOT",
+            AccountMode::Margin => "MARGIN",
+            AccountMode::Futures => "FUTURES",
+            AccountMode::Unknown => "UNKNOWN",
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct AccountModeParams {
+    account_mode: AccountMode,
+}
+
+/// The last account mode seen, shared by clones of a client.
+#[derive(Debug, Default)]
+pub(crate) struct AccountModeCache {
+    mode: Mutex<Option<AccountMode>>,
+}
+
+impl AccountModeCache {
+    fn get(&self) -> Option<AccountMode> {
+        *self.mode.lock().unwrap()
+    }
+
+    fn set(&self, mode: Option<AccountMode>) {
+        *self.mode.lock().unwrap() = mode;
+    }
+}
+
+impl Woo {
+    /// The account's mode, fetched with the account info on first use and
+    /// cached after that.
+    pub async fn account_mode(&self) -> anyhow::Result<Option<AccountMode>> {
+        Ok(self.cached_account_mode().await?)
+    }
+
+    /// Refetches the account mode, for when it was changed in the web UI
+    /// since it was cached.
+    pub async fn refresh_account_mode(&self) -> anyhow::Result<Option<AccountMode>> {
+        Ok(self.account_info().await?.account_mode)
+    }
+
+    /// Switches the account to `mode`. WOO refuses the switch while the
+    /// account holds positions or orders the new mode can't; its refusal
+    /// comes back unchanged as [`WooError::Api`] and the cached mode stays
+    /// what it was.
+    pub async fn set_account_mode(&self, mode: AccountMode) -> Result<(), WooError> {
+        if mode == AccountMode::Unknown {
+            return Err(WooError::Config(
+                "can't switch to an unknown account mode".to_string(),
+            ));
+        }
+
+        let params = AccountModeParams { account_mode: mode };
+        self.signed_request::<_, WooEnvelope<IgnoredAny>>(
+            Method::POST,
+            "v1/client/account_mode",
+            &params,
+        )
+        .await?
+        .into_data()?;
+
+        self.account_mode.set(Some(mode));
+        Ok(())
+    }
+
+    pub(crate) async fn account_info(&self) -> Result<AccountInfo, WooError> {
+        let res = self
+            .signed_request::<_, WooEnvelope<AccountInfoRes>>(
+                Method::GET,
+                "v1/client/info",
+                BTreeMap::<String, String>::new(),
+            )
+            .await?
+            .into_data()?;
+
+        self.account_mode.set(res.application.account_mode);
+        Ok(res.application)
+    }
+
+    async fn cached_account_mode(&self) -> Result<Option<AccountMode>, WooError> {
+        match self.account_mode.get() {
+            Some(mode) => Ok(Some(mode)),
+            None => Ok(self.account_info().await?.account_mode),
+        }
+    }
+
+    /// Fails orders the account's mode can't place: perpetuals need
+    /// `FUTURES`. If the mode can't be fetched the exchange gets to decide.
+    pub(crate) async fn check_account_mode(&self, order: &WooOrder) -> Result<(), WooError> {
+        if !order.symbol.starts_with("PERP_") {
+            return Ok(());
+        }
+
+        let actual = match self.cached_account_mode().await {
+            Ok(Some(mode)) => mode,
+            Ok(None) => return Ok(()),
+            Err(err) => {
+                tracing::warn!(%err, "could not fetch the account mode, sending the order unchecked");
+                return Ok(());
+            }
+        };
+
+        match actual {
+            AccountMode::Futures | AccountMode::Unknown => Ok(()),
+            actual => Err(WooError::WrongAccountMode {
+                required: AccountMode::Futures,
+                actual,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mock_woo;
+    use crate::woo_data_structs::OrderSize;
+    use rust_decimal_macros::dec;
+    use wiremock::matchers::{body_string, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn info(mode: &str) -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "success": true,
+            "application": { "application_id": "app", "account": "main", "account_mode": mode },
+        }))
+    }
+
+    #[tokio::test]
+    async fn perp_orders_need_futures_mode() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/client/info"))
+            .respond_with(info("MARGIN"))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/order"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{"success":true,"timestamp":"1578565539.808","order_id":9,"order_type":"MARKET","client_order_id":0}"#,
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let woo = mock_woo(&server);
+        let perp = WooOrder::builder("PERP_BTC_USDT", "BUY", "MARKET")
+            .size(OrderSize::Quantity(dec!(1)))
+            .build();
+
+        let err = woo.create_order(perp.clone()).await.unwrap_err();
+        assert!(matches!(
+            err,
+            WooError::WrongAccountMode {
+                required: AccountMode::Futures,
+                actual: AccountMode::Margin,
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "request needs a FUTURES account, this one is in MARGIN mode"
+        );
+
+        // the mode was switched in the web UI
+        Mock::given(method("GET"))
+            .and(path("/v1/client/info"))
+            .respond_with(info("FUTURES"))
+            .mount(&server)
+            .await;
+        assert_eq!(
+            woo.refresh_account_mode().await.unwrap(),
+            Some(AccountMode::Futures)
+        );
+        woo.create_order(perp).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn switching_mode_updates_the_cached_one_unless_woo_refuses() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/client/info"))
+            .respond_with(info("PURE_SPOT"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/client/account_mode"))
+            .and(body_string("account_mode=FUTURES"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true, "timestamp": 1673323685109_u64,
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/client/account_mode"))
+            .and(body_string("account_mode=PURE_SPOT"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                "success": false, "code": -1000,
+                "message": "Account mode can not be changed with open positions.",
+            })))
+            .mount(&server)
+            .await;
+        let woo = mock_woo(&server);
+
+        assert_eq!(
+            woo.account_mode().await.unwrap(),
+            Some(AccountMode::PureSpot)
+        );
+        woo.set_account_mode(AccountMode::Futures).await.unwrap();
+        assert_eq!(
+            woo.account_mode().await.unwrap(),
+            Some(AccountMode::Futures)
+        );
+
+        let err = woo
+            .set_account_mode(AccountMode::PureSpot)
+            .await
+            .unwrap_err();
+        match err {
+            WooError::Api(err) => assert!(err.message.contains("open positions")),
+            other => panic!("expected WOO's refusal, got {other:?}"),
+        }
+        assert_eq!(
+            woo.account_mode().await.unwrap(),
+            Some(AccountMode::Futures)
+        );
+        assert!(woo.set_account_mode(AccountMode::Unknown).await.is_err());
+    }
+}
@@ -0,0 +1,209 @@
+use crate::woo::Woo;
+use crate::woo_data_structs::{GetTrades, Trade};
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use rust_decimal::Decimal;
+use std::collections::{HashMap, VecDeque};
+
+/// Which earlier buys (or sells) a closing trade is matched against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CostBasis {
+    /// Oldest open lot first.
+    Fifo,
+    /// A single lot at the volume-weighted entry price.
+    AverageCost,
+}
+
+/// Position carried into the window: signed quantity (negative for short) at
+/// its entry price.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InitialPosition {
+    pub quantity: Decimal,
+    pub average_price: Decimal,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PnlReport {
+    pub realized_pnl: Decimal,
+    pub fees: HashMap<String, Decimal>,
+    /// Traded notional in quote currency.
+    pub volume: Decimal,
+    pub trade_count: usize,
+    /// Signed position left open at the end of the window.
+    pub closing_position: Decimal,
+}
+
+/// Folds trades, in execution order, into realized PnL.
+#[derive(Debug, Clone)]
+pub struct PnlCalculator {
+    basis: CostBasis,
+    // signed lots, all of the same sign
+    lots: VecDeque<(Decimal, Decimal)>,
+    report: PnlReport,
+}
+
+impl PnlCalculator {
+    pub fn new(basis: CostBasis, initial: InitialPosition) -> Self {
+        let mut lots = VecDeque::new();
+        if !initial.quantity.is_zero() {
+            lots.push_back((initial.quantity, initial.average_price));
+        }
+
+        Self {
+            basis,
+            lots,
+            report: PnlReport::default(),
+        }
+    }
+
+    pub fn add(&mut self, trade: &Trade) {
+        let price = trade.executed_price;
+        let mut quantity = match trade.side.as_str() {
+            "SELL" => -trade.executed_quantity,
+            _ => trade.executed_quantity,
+        };
+
+        self.report.volume += trade.executed_quantity * price;
+        self.report.trade_count += 1;
+        *self.report.fees.entry(trade.fee_asset.clone()).or_default() += trade.fee;
+
+        // close against open lots of the opposite sign
+        while !quantity.is_zero() {
+            let Some((lot_quantity, lot_price)) = self.lots.front_mut() else {
+                break;
+            };
+            if lot_quantity.is_sign_positive() == quantity.is_sign_positive() {
+                break;
+            }
+
+            let matched = quantity.abs().min(lot_quantity.abs());
+            let direction = if lot_quantity.is_sign_positive() {
+                Decimal::ONE
+            } else {
+                Decimal::NEGATIVE_ONE
+            };
+            self.report.realized_pnl += matched * (price - *lot_price) * direction;
+
+            *lot_quantity -= matched * direction;
+            quantity += matched * direction;
+            if lot_quantity.is_zero() {
+                self.lots.pop_front();
+            }
+        }
+
+        if quantity.is_zero() {
+            return;
+        }
+
+        match (self.basis, self.lots.front_mut()) {
+            (CostBasis::AverageCost, Some((lot_quantity, lot_price))) => {
+                let total = *lot_quantity + quantity;
+                *lot_price = (*lot_quantity * *lot_price + quantity * price) / total;
+                *lot_quantity = total;
+            }
+            _ => self.lots.push_back((quantity, price)),
+        }
+    }
+
+    pub fn finish(mut self) -> PnlReport {
+        self.report.closing_position = self.lots.iter().map(|(quantity, _)| quantity).sum();
+        self.report
+    }
+}
+
+impl Woo {
+    /// Realized PnL, fees and volume of `symbol` trades executed in
+    /// `[from, to]`, starting from `initial` so that positions opened before
+    /// `from` are closed at their real entry price.
+    pub async fn compute_realized_pnl(
+        &self,
+        symbol: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        basis: CostBasis,
+        initial: InitialPosition,
+    ) -> anyhow::Result<PnlReport> {
+        let filter = GetTrades {
+            symbol: Some(symbol.to_string()),
+            start_t: Some(from.timestamp_millis()),
+            end_t: Some(to.timestamp_millis()),
+            ..Default::default()
+        };
+
+        let mut trades: Vec<Trade> = self.get_trades_stream(filter).try_collect().await?;
+        // trade ids increase with execution time, whatever order pages list them in
+        trades.sort_by_key(|trade| trade.id);
+
+        let mut calculator = PnlCalculator::new(basis, initial);
+        for trade in &trades {
+            calculator.add(trade);
+        }
+
+        Ok(calculator.finish())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::woo_data_structs::OrderId;
+    use rust_decimal_macros::dec;
+
+    fn trade(side: &str, quantity: Decimal, price: Decimal) -> Trade {
+        Trade {
+            id: 1,
+            symbol: "SPOT_BTC_USDT".to_string(),
+            order_id: OrderId(1),
+            side: side.to_string(),
+            executed_price: price,
+            executed_quantity: quantity,
+            fee: dec!(0.1),
+            fee_asset: "USDT".to_string(),
+            executed_timestamp: "1578565539.808".to_string(),
+            is_maker: true,
+        }
+    }
+
+    fn report(basis: CostBasis, initial: InitialPosition) -> PnlReport {
+        let mut calculator = PnlCalculator::new(basis, initial);
+        for trade in [
+            trade("BUY", dec!(1), dec!(100)),
+            trade("BUY", dec!(1), dec!(200)),
+            trade("SELL", dec!(1), dec!(300)),
+        ] {
+            calculator.add(&trade);
+        }
+        calculator.finish()
+    }
+
+    #[test]
+    fn fifo_closes_oldest_lot_first() {
+        let report = report(CostBasis::Fifo, InitialPosition::default());
+
+        assert_eq!(report.realized_pnl, dec!(200));
+        assert_eq!(report.closing_position, dec!(1));
+        assert_eq!(report.volume, dec!(600));
+        assert_eq!(report.fees["USDT"], dec!(0.3));
+    }
+
+    #[test]
+    fn average_cost_closes_at_mean_entry() {
+        let report = report(CostBasis::AverageCost, InitialPosition::default());
+
+        assert_eq!(report.realized_pnl, dec!(150));
+        assert_eq!(report.closing_position, dec!(1));
+    }
+
+    #[test]
+    fn initial_short_is_covered_first() {
+        let initial = InitialPosition {
+            quantity: dec!(-1),
+            average_price: dec!(250),
+        };
+        let report = report(CostBasis::Fifo, initial);
+
+        // the first buy covers the short at 250, the sell closes the second buy
+        assert_eq!(report.realized_pnl, dec!(250));
+        assert_eq!(report.closing_position, dec!(0));
+    }
+}
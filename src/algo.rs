@@ -0,0 +1,402 @@
+use crate::error::WooError;
+use crate::serde_helpers::decimal_string;
+use crate::woo::{order_rejection, RequestBody, Woo};
+use crate::woo_data_structs::{ClientOrderId, OrderId, WooEnvelope, WooOrder};
+use reqwest::Method;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// The algo order kinds WOO takes, see <https://docs.woo.org/#send-algo-order>.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AlgoType {
+    Stop,
+    Oco,
+    TrailingStop,
+    /// An entry order with a `POSITIONAL_TP_SL` child protecting the position
+    /// it opens.
+    Bracket,
+    /// Take profit and stop loss legs on a whole position.
+    PositionalTpSl,
+    TakeProfit,
+    StopLoss,
+}
+
+/// An algo order and its child legs, nested any number of levels deep.
+///
+/// Sent as a JSON body signed with the `v3` scheme: fields go out in
+/// declaration order, so the bytes signed are the bytes sent and the same
+/// order always encodes the same way.
+// https://docs.woo.org/#send-algo-order
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AlgoOrder {
+    pub symbol: String,
+    pub algo_type: AlgoType,
+    /// `LIMIT`, `MARKET` or, for legs closing a position, `CLOSE_POSITION`.
+    pub r#type: Option<String>,
+    pub side: Option<String>,
+    #[serde(serialize_with = "decimal_string::serialize_option")]
+    pub quantity: Option<Decimal>,
+    #[serde(serialize_with = "decimal_string::serialize_option")]
+    pub price: Option<Decimal>,
+    #[serde(serialize_with = "decimal_string::serialize_option")]
+    pub trigger_price: Option<Decimal>,
+    pub reduce_only: Option<bool>,
+    pub client_order_id: Option<ClientOrderId>,
+    pub order_tag: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub child_orders: Vec<AlgoOrder>,
+}
+
+impl AlgoOrder {
+    /// An algo order of `algo_type` on `symbol` with every other field unset.
+    pub fn new(symbol: impl Into<String>, algo_type: AlgoType) -> Self {
+        Self {
+            symbol: symbol.into(),
+            algo_type,
+            r#type: None,
+            side: None,
+            quantity: None,
+            price: None,
+            trigger_price: None,
+            reduce_only: None,
+            client_order_id: None,
+            order_tag: None,
+            child_orders: Vec::new(),
+        }
+    }
+
+    /// A limit entry on `symbol` whose position is closed at `take_profit`
+    /// or `stop_loss`, whichever triggers first.
+    pub fn bracket(
+        symbol: impl Into<String>,
+        side: impl Into<String>,
+        price: Decimal,
+        quantity: Decimal,
+        take_profit: Decimal,
+        stop_loss: Decimal,
+    ) -> Self {
+        let symbol = symbol.into();
+        let side = side.into();
+        let closing_side = if side == "BUY" { "SELL" } else { "BUY" };
+        let leg = |algo_type, trigger_price| AlgoOrder {
+            r#type: Some("CLOSE_POSITION".to_string()),
+            side: Some(closing_side.to_string()),
+            trigger_price: Some(trigger_price),
+            reduce_only: Some(true),
+            ..AlgoOrder::new(symbol.clone(), algo_type)
+        };
+
+        AlgoOrder {
+            r#type: Some("LIMIT".to_string()),
+            side: Some(side.clone()),
+            quantity: Some(quantity),
+            price: Some(price),
+            child_orders: vec![AlgoOrder {
+                child_orders: vec![
+                    leg(AlgoType::TakeProfit, take_profit),
+                    leg(AlgoType::StopLoss, stop_loss),
+                ],
+                ..AlgoOrder::new(symbol.clone(), AlgoType::PositionalTpSl)
+            }],
+            ..AlgoOrder::new(symbol, AlgoType::Bracket)
+        }
+    }
+
+    /// The top level order as a plain one, priced at its trigger if it has
+    /// no limit price, for the risk and account mode checks.
+    fn entry(&self) -> WooOrder {
+        WooOrder {
+            order_price: self.price.or(self.trigger_price),
+            order_quantity: self.quantity,
+            reduce_only: self.reduce_only,
+            ..WooOrder::builder(
+                &self.symbol,
+                self.side.as_deref().unwrap_or_default(),
+                self.r#type.as_deref().unwrap_or("MARKET"),
+            )
+            .build()
+        }
+    }
+
+    fn validate(&self) -> Result<(), WooError> {
+        if let Some(client_order_id) = &self.client_order_id {
+            client_order_id.validate()?;
+        }
+        if let Some(child) = self.child_orders.iter().find(|c| c.symbol != self.symbol) {
+            return Err(WooError::InvalidOrder(format!(
+                "{:?} leg on {} under an algo order on {}",
+                child.algo_type, child.symbol, self.symbol
+            )));
+        }
+
+        self.child_orders.iter().try_for_each(AlgoOrder::validate)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AlgoOrderRow {
+    pub order_id: OrderId,
+    #[serde(default)]
+    pub client_order_id: ClientOrderId,
+    pub algo_type: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AlgoOrderRes {
+    /// The order sent and, for some algo types, the legs it created.
+    pub rows: Vec<AlgoOrderRow>,
+}
+
+/// A leg of an algo order as WOO reports it, with the legs nested under it.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ChildOrder {
+    pub algo_order_id: OrderId,
+    pub algo_type: AlgoType,
+    #[serde(default)]
+    pub side: String,
+    /// `None` for legs only grouping others, like `POSITIONAL_TP_SL`.
+    #[serde(default)]
+    pub trigger_price: Option<Decimal>,
+    /// Zero for legs closing the whole position.
+    #[serde(default)]
+    pub quantity: Decimal,
+    #[serde(rename = "algoStatus")]
+    pub status: String,
+    #[serde(default)]
+    pub child_orders: Vec<ChildOrder>,
+}
+
+/// An algo order read back with [`Woo::get_algo_order`].
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AlgoOrderDetail {
+    pub algo_order_id: OrderId,
+    #[serde(default)]
+    pub client_order_id: ClientOrderId,
+    pub symbol: String,
+    pub algo_type: AlgoType,
+    #[serde(default)]
+    pub side: String,
+    #[serde(default)]
+    pub quantity: Decimal,
+    #[serde(default)]
+    pub price: Option<Decimal>,
+    #[serde(rename = "algoStatus")]
+    pub status: String,
+    #[serde(default)]
+    pub child_orders: Vec<ChildOrder>,
+}
+
+impl AlgoOrderDetail {
+    /// The take profit leg, e.g. of a bracket, however deep it is nested.
+    pub fn take_profit(&self) -> Option<&ChildOrder> {
+        self.leg(AlgoType::TakeProfit)
+    }
+
+    /// The stop loss leg, however deep it is nested.
+    pub fn stop_loss(&self) -> Option<&ChildOrder> {
+        self.leg(AlgoType::StopLoss)
+    }
+
+    /// The first leg of `algo_type`, depth first.
+    pub fn leg(&self, algo_type: AlgoType) -> Option<&ChildOrder> {
+        find_leg(&self.child_orders, algo_type)
+    }
+}
+
+fn find_leg(legs: &[ChildOrder], algo_type: AlgoType) -> Option<&ChildOrder> {
+    legs.iter().find_map(|leg| {
+        if leg.algo_type == algo_type {
+            Some(leg)
+        } else {
+            find_leg(&leg.child_orders, algo_type)
+        }
+    })
+}
+
+impl Woo {
+    /// Sends `order` and its child legs to `v3/algo/order`, once the entry
+    /// leg passed the checks of plain orders. The legs under it only close
+    /// what it opens, so they aren't checked on their own.
+    pub async fn create_algo_order(&self, order: &AlgoOrder) -> Result<AlgoOrderRes, WooError> {
+        order.validate()?;
+        let _in_flight = self.guard_order(&order.entry()).await?;
+
+        self.signed_v3_request::<_, WooEnvelope<AlgoOrderRes>>(Method::POST, "v3/algo/order", order)
+            .await
+            .map_err(order_rejection)?
+            .into_data()
+    }
+
+    /// Reads back the algo order `algo_order_id` and its legs.
+    pub async fn get_algo_order(
+        &self,
+        algo_order_id: OrderId,
+    ) -> Result<AlgoOrderDetail, WooError> {
+        let path = format!("v3/algo/order/{algo_order_id}");
+
+        self.signed_body_request::<WooEnvelope<AlgoOrderDetail>>(
+            Method::GET,
+            &path,
+            RequestBody::Json(String::new()),
+        )
+        .await?
+        .into_data()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::Credentials;
+    use crate::risk::{RiskLimits, RiskRule};
+    use crate::testing::mock_woo;
+    use crate::woo::Environment;
+    use rust_decimal_macros::dec;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn bracket() -> AlgoOrder {
+        AlgoOrder::bracket(
+            "PERP_BTC_USDT",
+            "BUY",
+            dec!(60000),
+            dec!(0.1),
+            dec!(66000),
+            dec!(57000),
+        )
+    }
+
+    #[test]
+    fn nested_legs_encode_and_sign_the_same_every_time() {
+        let body = serde_json::to_string(&bracket()).unwrap();
+
+        assert_eq!(
+            body,
+            concat!(
+                r#"{"symbol":"PERP_BTC_USDT","algoType":"BRACKET","type":"LIMIT","side":"BUY","#,
+                r#""quantity":"0.1","price":"60000","childOrders":[{"symbol":"PERP_BTC_USDT","#,
+                r#""algoType":"POSITIONAL_TP_SL","childOrders":[{"symbol":"PERP_BTC_USDT","#,
+                r#""algoType":"TAKE_PROFIT","type":"CLOSE_POSITION","side":"SELL","#,
+                r#""triggerPrice":"66000","reduceOnly":true},{"symbol":"PERP_BTC_USDT","#,
+                r#""algoType":"STOP_LOSS","type":"CLOSE_POSITION","side":"SELL","#,
+                r#""triggerPrice":"57000","reduceOnly":true}]}]}"#,
+            )
+        );
+        assert_eq!(serde_json::to_string(&bracket()).unwrap(), body);
+
+        let tagged = AlgoOrder {
+            client_order_id: Some(ClientOrderId::text("desk-1").unwrap()),
+            ..AlgoOrder::new("PERP_BTC_USDT", AlgoType::Stop)
+        };
+        assert!(serde_json::to_string(&tagged)
+            .unwrap()
+            .contains(r#""clientOrderId":"desk-1""#));
+
+        let payload = format!("1700000000000POST/v3/algo/order{body}");
+        assert_eq!(
+            Woo::generate_v3_signature(&payload, "secret"),
+            "f8c26de907338ff08d4ba6e31f7ae08336a6860961a4f2fcaf5dfa9cd6c5132f"
+        );
+    }
+
+    #[tokio::test]
+    async fn signs_exactly_the_body_it_sends() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v3/algo/order"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "rows": [{ "orderId": 13065, "clientOrderId": 0, "algoType": "BRACKET" }] },
+                "timestamp": 1676277825917_u64,
+            })))
+            .mount(&server)
+            .await;
+        let woo = mock_woo(&server);
+
+        let res = woo.create_algo_order(&bracket()).await.unwrap();
+        assert_eq!(res.rows[0].order_id, OrderId(13065));
+
+        let requests = server.received_requests().await.unwrap();
+        // after the account mode lookup of the entry leg
+        let request = requests.iter().find(|r| r.method == "POST").unwrap();
+        let body = String::from_utf8(request.body.clone()).unwrap();
+        assert_eq!(body, serde_json::to_string(&bracket()).unwrap());
+        let timestamp = request.headers["x-api-timestamp"].to_str().unwrap();
+        let payload = format!("{timestamp}POST/v3/algo/order{body}");
+        assert_eq!(
+            request.headers["x-api-signature"].to_str().unwrap(),
+            Woo::generate_v3_signature(&payload, "secret")
+        );
+    }
+
+    #[tokio::test]
+    async fn entry_leg_is_held_to_the_risk_limits() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(0)
+            .mount(&server)
+            .await;
+        let woo = mock_woo(&server).with_risk_limits(RiskLimits {
+            max_order_notional: Some(dec!(5000)),
+            ..RiskLimits::default()
+        });
+
+        let err = woo.create_algo_order(&bracket()).await.unwrap_err();
+
+        assert!(matches!(
+            err,
+            WooError::RiskRejected {
+                rule: RiskRule::MaxOrderNotional,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn bracket_legs_are_told_apart() {
+        let res: WooEnvelope<AlgoOrderDetail> = serde_json::from_str(concat!(
+            r#"{"success":true,"data":{"algoOrderId":13065,"clientOrderId":0,"#,
+            r#""symbol":"PERP_BTC_USDT","algoType":"BRACKET","side":"BUY","quantity":0.1,"#,
+            r#""price":60000,"algoStatus":"NEW","childOrders":[{"algoOrderId":13066,"#,
+            r#""algoType":"POSITIONAL_TP_SL","side":"SELL","algoStatus":"NEW","#,
+            r#""triggerPrice":null,"childOrders":[{"algoOrderId":13067,"#,
+            r#""algoType":"TAKE_PROFIT","side":"SELL","triggerPrice":66000,"quantity":0,"#,
+            r#""algoStatus":"NEW","childOrders":[]},{"algoOrderId":13068,"#,
+            r#""algoType":"STOP_LOSS","side":"SELL","triggerPrice":"57000","quantity":0,"#,
+            r#""algoStatus":"NEW","childOrders":[]}]}]},"timestamp":1676277825917}"#,
+        ))
+        .unwrap();
+        let bracket = res.into_data().unwrap();
+
+        let take_profit = bracket.take_profit().unwrap();
+        assert_eq!(take_profit.algo_order_id, OrderId(13067));
+        assert_eq!(take_profit.trigger_price, Some(dec!(66000)));
+        let stop_loss = bracket.stop_loss().unwrap();
+        assert_eq!(stop_loss.algo_order_id, OrderId(13068));
+        assert_eq!(stop_loss.trigger_price, Some(dec!(57000)));
+        assert_eq!(stop_loss.side, "SELL");
+        assert_eq!(stop_loss.status, "NEW");
+        assert_eq!(bracket.child_orders[0].trigger_price, None);
+        assert!(bracket.leg(AlgoType::TrailingStop).is_none());
+    }
+
+    #[tokio::test]
+    async fn legs_must_stay_on_the_symbol() {
+        let mut order = bracket();
+        order.child_orders[0].child_orders[1].symbol = "PERP_ETH_USDT".to_string();
+        let woo = Woo::builder(Environment::Staging, Credentials::new("key", "secret"))
+            .build()
+            .unwrap();
+
+        let err = woo.create_algo_order(&order).await.unwrap_err();
+
+        assert!(err.to_string().contains("StopLoss leg on PERP_ETH_USDT"));
+    }
+}
@@ -0,0 +1,250 @@
+use crate::woo::Woo;
+use reqwest::Method;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Receives an [`AuditRecord`] for every signed instruction sent to WOO, see
+/// [`Woo::with_audit_sink`].
+///
+/// Runs on a dedicated thread, so it may block, e.g. on an fsync or a Kafka
+/// produce, without holding up requests; records arriving while it is busy
+/// queue up to the channel capacity and are dropped beyond that.
+pub trait AuditSink: Send + 'static {
+    fn record(&mut self, record: AuditRecord);
+}
+
+/// One signed request exactly as it was sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditRecord {
+    /// The `x-api-timestamp` the payload was signed with, in milliseconds.
+    pub timestamp: u64,
+    pub request_id: Uuid,
+    pub method: Method,
+    /// Path without the query, e.g. `/v1/order`.
+    pub endpoint: String,
+    /// The params that were signed and sent: the canonical query string, or
+    /// the JSON body of `v3` endpoints.
+    pub payload: String,
+    /// The `x-api-signature` sent with the request.
+    pub signature: String,
+    pub outcome: AuditOutcome,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditOutcome {
+    /// WOO answered with HTTP `status`, and `api_code` if it rejected the
+    /// request.
+    Responded { status: u16, api_code: Option<i64> },
+    /// Sent but unacknowledged: no response came back, e.g. after a timeout
+    /// or a dropped connection, so the exchange may or may not have acted on
+    /// it.
+    Unacknowledged,
+}
+
+/// A signed request on its way out, waiting for its outcome.
+#[derive(Debug)]
+pub(crate) struct PendingAudit {
+    pub(crate) timestamp: u64,
+    pub(crate) method: Method,
+    pub(crate) endpoint: String,
+    pub(crate) payload: String,
+    pub(crate) signature: String,
+}
+
+impl PendingAudit {
+    fn into_record(self, request_id: Uuid, outcome: AuditOutcome) -> AuditRecord {
+        AuditRecord {
+            timestamp: self.timestamp,
+            request_id,
+            method: self.method,
+            endpoint: self.endpoint,
+            payload: self.payload,
+            signature: self.signature,
+            outcome,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct AuditLog {
+    records: SyncSender<AuditRecord>,
+    dropped: AtomicU64,
+}
+
+impl AuditLog {
+    fn spawn(mut sink: impl AuditSink, capacity: usize) -> Self {
+        let (records, received) = mpsc::sync_channel(capacity);
+        std::thread::Builder::new()
+            .name("woo-audit".to_string())
+            // ends once every client clone, and with them the sender, is gone
+            .spawn(move || {
+                for record in received {
+                    sink.record(record);
+                }
+            })
+            .expect("failed to spawn the audit thread");
+
+        Self {
+            records,
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// Never blocks: drops the record if the sink is behind.
+    pub(crate) fn submit(&self, pending: PendingAudit, request_id: Uuid, outcome: AuditOutcome) {
+        let record = pending.into_record(request_id, outcome);
+        if let Err(TrySendError::Full(record) | TrySendError::Disconnected(record)) =
+            self.records.try_send(record)
+        {
+            let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+            tracing::warn!(%record.request_id, dropped, "audit sink behind, record dropped");
+        }
+    }
+
+    fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl Woo {
+    /// Hands every signed request that can change state (anything but `GET`)
+    /// to `sink`, including ones that failed in transit. Up to `capacity`
+    /// records wait while the sink is busy; see [`Woo::audit_records_dropped`]
+    /// for the ones that didn't fit. Shared by clones made after this call.
+    pub fn with_audit_sink(mut self, sink: impl AuditSink, capacity: usize) -> Self {
+        self.audit = Some(Arc::new(AuditLog::spawn(sink, capacity)));
+        self
+    }
+
+    /// How many audit records were dropped because the sink fell behind.
+    pub fn audit_records_dropped(&self) -> u64 {
+        self.audit.as_ref().map(|log| log.dropped()).unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::Credentials;
+    use crate::woo::Environment;
+    use crate::woo_data_structs::WooOrder;
+    use rust_decimal_macros::dec;
+    use std::sync::mpsc::{Receiver, Sender};
+    use std::time::Duration;
+    use url::Url;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    struct ChannelSink(Sender<AuditRecord>);
+
+    impl AuditSink for ChannelSink {
+        fn record(&mut self, record: AuditRecord) {
+            self.0.send(record).unwrap();
+        }
+    }
+
+    fn audited_woo(base_url: &str) -> (Woo, Receiver<AuditRecord>) {
+        let (sender, records) = mpsc::channel();
+        let woo = Woo::builder(Environment::Staging, Credentials::new("key", "secret"))
+            .base_url(Url::parse(base_url).unwrap())
+            .build()
+            .unwrap()
+            .with_audit_sink(ChannelSink(sender), 16);
+        (woo, records)
+    }
+
+    fn order() -> WooOrder {
+        WooOrder::post_only("SPOT_BTC_USDT", "BUY", dec!(9000), dec!(0.1))
+    }
+
+    #[tokio::test]
+    async fn records_the_signed_payload_and_response() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/order"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                "success": false, "code": -1102, "message": "order value too small",
+            })))
+            .mount(&server)
+            .await;
+        let (woo, records) = audited_woo(&server.uri());
+
+        woo.create_order(order()).await.unwrap_err();
+        woo.get_holding().await.unwrap_err();
+
+        let record = records.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(record.method, Method::POST);
+        assert_eq!(record.endpoint, "/v1/order");
+        assert_eq!(
+            record.payload,
+            "order_price=9000&order_quantity=0.1&order_type=POST_ONLY&side=BUY&symbol=SPOT_BTC_USDT"
+        );
+        assert_eq!(
+            record.signature,
+            Woo::generate_hmac_sha256_signature(record.payload.clone(), record.timestamp, "secret")
+        );
+        assert_eq!(
+            record.outcome,
+            AuditOutcome::Responded {
+                status: 400,
+                api_code: Some(-1102)
+            }
+        );
+        // the GET isn't an instruction
+        assert!(records.recv_timeout(Duration::from_millis(100)).is_err());
+    }
+
+    #[tokio::test]
+    async fn records_requests_that_got_no_response() {
+        // bind and release a port so nothing listens on it
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let (woo, records) = audited_woo(&base_url);
+
+        woo.create_order(order()).await.unwrap_err();
+
+        let record = records.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(record.outcome, AuditOutcome::Unacknowledged);
+    }
+
+    #[test]
+    fn drops_and_counts_records_while_the_sink_is_busy() {
+        struct BlockedSink {
+            entered: Sender<()>,
+            release: Receiver<()>,
+        }
+
+        impl AuditSink for BlockedSink {
+            fn record(&mut self, _: AuditRecord) {
+                self.entered.send(()).unwrap();
+                self.release.recv().unwrap();
+            }
+        }
+
+        let (entered, entered_rx) = mpsc::channel();
+        let (release_tx, release) = mpsc::channel();
+        let log = AuditLog::spawn(BlockedSink { entered, release }, 1);
+        let pending = || PendingAudit {
+            timestamp: 0,
+            method: Method::POST,
+            endpoint: "/v1/order".to_string(),
+            payload: String::new(),
+            signature: String::new(),
+        };
+        let submit = || log.submit(pending(), Uuid::nil(), AuditOutcome::Unacknowledged);
+
+        submit();
+        entered_rx.recv().unwrap();
+        // one record fits the channel, the rest are dropped
+        submit();
+        submit();
+        submit();
+
+        assert_eq!(log.dropped(), 2);
+        release_tx.send(()).unwrap();
+        release_tx.send(()).unwrap();
+    }
+}
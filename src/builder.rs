@@ -0,0 +1,531 @@
+use crate::constants::{WOO_API_BASE_URL, WOO_API_BASE_URL_STAGING, WOO_PUBLIC_DATA_BASE_URL};
+use crate::error::WooError;
+use crate::proxy::{ProxyConfig, ProxyPool, ProxyPoolConfig, RotationStrategy};
+use crate::signer::{HmacSigner, Signer, SigningKey};
+use crate::tls::{ClientIdentity, TlsConfig};
+use crate::woo::{Environment, Woo};
+use reqwest::header::{self, HeaderName, HeaderValue};
+use secrecy::SecretString;
+use std::sync::Arc;
+use std::time::Duration;
+use url::Url;
+
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Pings idle HTTP/2 connections so proxies and load balancers keep them.
+const HTTP2_KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(30);
+const DEFAULT_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+#[derive(Debug, Clone)]
+pub struct Credentials {
+    pub api_key: String,
+    pub api_secret: SecretString,
+}
+
+impl Credentials {
+    pub fn new(api_key: impl Into<String>, api_secret: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            api_secret: SecretString::from(api_secret.into()),
+        }
+    }
+}
+
+pub struct WooBuilder {
+    environment: Environment,
+    credentials: Credentials,
+    base_url: Option<Url>,
+    public_data_url: Option<Url>,
+    proxies: Option<ProxyPoolConfig>,
+    connect_timeout: Duration,
+    tls: TlsConfig,
+    user_agent: Option<String>,
+    default_headers: Vec<(String, String)>,
+    http_client: Option<reqwest::Client>,
+    prefer_http2: bool,
+    signer: Option<Arc<dyn Signer>>,
+}
+
+impl WooBuilder {
+    pub fn new(environment: Environment, credentials: Credentials) -> Self {
+        Self {
+            environment,
+            credentials,
+            base_url: None,
+            public_data_url: None,
+            proxies: None,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            tls: TlsConfig::default(),
+            user_agent: None,
+            default_headers: Vec::new(),
+            http_client: None,
+            prefer_http2: false,
+            signer: None,
+        }
+    }
+
+    /// Overrides the base url implied by the environment.
+    pub fn base_url(mut self, base_url: Url) -> Self {
+        self.base_url = Some(base_url);
+        self
+    }
+
+    /// Overrides [`WOO_PUBLIC_DATA_BASE_URL`], where historical market data
+    /// is fetched from.
+    pub fn public_data_url(mut self, public_data_url: Url) -> Self {
+        self.public_data_url = Some(public_data_url);
+        self
+    }
+
+    pub fn proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.proxies = Some(ProxyPoolConfig::new(
+            vec![proxy],
+            RotationStrategy::Failover,
+        ));
+        self
+    }
+
+    /// Spreads requests across several proxies, see [`ProxyPoolConfig`].
+    pub fn proxy_pool(mut self, proxies: ProxyPoolConfig) -> Self {
+        self.proxies = Some(proxies);
+        self
+    }
+
+    /// How long to wait for a tcp connection (to the proxy, if any) before
+    /// failing the request with a transport error.
+    pub fn connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = connect_timeout;
+        self
+    }
+
+    /// Replaces the default `woo-http-client/<version>` user agent, which some
+    /// proxies and WAFs treat better than reqwest's empty one.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Adds a header sent with every request. Name and value are validated by
+    /// [`WooBuilder::build`].
+    pub fn default_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.default_headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Talks HTTP/2 from the first byte instead of HTTP/1.1, multiplexing
+    /// bursts of orders over one kept alive connection rather than opening
+    /// more, with flow control windows sized to the measured bandwidth.
+    ///
+    /// Through an HTTP proxy, `https` requests go through a CONNECT tunnel,
+    /// so HTTP/2 runs end to end with WOO and the proxy only relays bytes;
+    /// SOCKS proxies always just relay. A plain `http` base url is forwarded
+    /// by an HTTP proxy request by request though, which needs the proxy
+    /// itself to speak HTTP/2, so [`WooBuilder::build`] refuses that
+    /// combination. Off by default: nothing falls back to HTTP/1.1 if the
+    /// other end turns out not to support it.
+    pub fn prefer_http2(mut self, prefer_http2: bool) -> Self {
+        self.prefer_http2 = prefer_http2;
+        self
+    }
+
+    /// Signs requests with `signer` instead of an HMAC over the api secret of
+    /// the credentials, which then goes unused and may be empty.
+    pub fn signer(mut self, signer: Arc<dyn Signer>) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    /// Uses an existing client instead of building one, so several `Woo`s
+    /// (e.g. one per sub-account) share a connection pool and DNS cache.
+    ///
+    /// Everything transport related then comes from `http_client`: its proxy,
+    /// TLS settings, user agent, default headers and timeouts. Configuring a
+    /// proxy on this builder as well is rejected by [`WooBuilder::build`]
+    /// rather than silently ignored, and [`Woo::proxy_status`] stays empty.
+    pub fn http_client(mut self, http_client: reqwest::Client) -> Self {
+        self.http_client = Some(http_client);
+        self
+    }
+
+    /// Trusts an extra PEM encoded root, e.g. the CA of a TLS intercepting
+    /// corporate proxy. Parsed, and rejected if malformed, by [`WooBuilder::build`].
+    pub fn add_root_certificate(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.tls.root_certificates.push(pem.into());
+        self
+    }
+
+    /// Presents a client certificate during the TLS handshake.
+    pub fn client_identity(mut self, identity: ClientIdentity) -> Self {
+        self.tls.identity = Some(identity);
+        self
+    }
+
+    /// Only completes handshakes whose certificate chain contains a public key
+    /// with this base64 encoded SPKI sha256 hash. May be called several times
+    /// to allow a backup key. See [`crate::tls::spki_sha256`] to compute a pin.
+    #[cfg(feature = "tls-pinning")]
+    pub fn pin_spki_sha256(mut self, pin: impl Into<String>) -> Self {
+        self.tls.spki_pins.push(pin.into());
+        self
+    }
+
+    /// Disables certificate validation entirely. Anyone on the path can then
+    /// read and replay signed requests; only meant for debugging against a
+    /// gateway you control, e.g. a self-signed one in front of staging.
+    /// [`WooBuilder::build`] refuses it for [`Environment::Production`], even
+    /// with an overridden base url.
+    #[cfg(feature = "dangerous-tls")]
+    pub fn danger_accept_invalid_certs(mut self, accept_invalid_certs: bool) -> Self {
+        self.tls.accept_invalid_certs = accept_invalid_certs;
+        self
+    }
+
+    pub fn build(self) -> Result<Woo, WooError> {
+        #[cfg(feature = "dangerous-tls")]
+        if self.tls.accept_invalid_certs && self.environment == Environment::Production {
+            return Err(WooError::Config(
+                "invalid certificates can't be accepted with production credentials".to_string(),
+            ));
+        }
+
+        let base_url = match self.base_url {
+            Some(base_url) => base_url,
+            None => match self.environment {
+                Environment::Production => Url::parse(WOO_API_BASE_URL).unwrap(),
+                Environment::Staging => Url::parse(WOO_API_BASE_URL_STAGING).unwrap(),
+            },
+        };
+
+        let public_data_url = self
+            .public_data_url
+            .unwrap_or_else(|| Url::parse(WOO_PUBLIC_DATA_BASE_URL).unwrap());
+
+        let signer = self
+            .signer
+            .unwrap_or_else(|| Arc::new(HmacSigner::new(self.credentials.api_secret)));
+        let key = SigningKey::new(&self.credentials.api_key, signer)?;
+
+        if let Some(http_client) = self.http_client {
+            if self.proxies.is_some() {
+                return Err(WooError::Config(
+                    "a proxy can't be configured on top of a shared http client, \
+                     configure it on the shared client instead"
+                        .to_string(),
+                ));
+            }
+            if self.prefer_http2 {
+                return Err(WooError::Config(
+                    "http2 can't be configured on top of a shared http client, \
+                     configure it on the shared client instead"
+                        .to_string(),
+                ));
+            }
+
+            return Ok(Woo::from_parts(
+                http_client,
+                None,
+                base_url,
+                public_data_url,
+                key,
+            ));
+        }
+
+        let forwarded_by_proxy = base_url.scheme() == "http"
+            && self.proxies.as_ref().is_some_and(|proxies| {
+                proxies
+                    .proxies
+                    .iter()
+                    .any(|proxy| matches!(proxy, ProxyConfig::Http { .. }))
+            });
+        if self.prefer_http2 && forwarded_by_proxy {
+            return Err(WooError::Config(
+                "http2 needs an https base url to tunnel through an http proxy".to_string(),
+            ));
+        }
+
+        let mut default_headers = header::HeaderMap::new();
+        for (name, value) in &self.default_headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| WooError::Config(format!("invalid header name {name:?}")))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|_| WooError::Config(format!("invalid value for header {name}")))?;
+            default_headers.insert(name, value);
+        }
+
+        let client_builder = || {
+            let mut client_builder = reqwest::Client::builder()
+                .default_headers(default_headers.clone())
+                .connect_timeout(self.connect_timeout)
+                .user_agent(self.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT));
+            if self.prefer_http2 {
+                client_builder = client_builder
+                    .http2_prior_knowledge()
+                    .http2_adaptive_window(true)
+                    .http2_keep_alive_interval(HTTP2_KEEP_ALIVE_INTERVAL)
+                    .http2_keep_alive_while_idle(true);
+            }
+
+            self.tls.apply(client_builder)
+        };
+
+        let (http_client, proxy_pool) = match &self.proxies {
+            Some(proxies) => {
+                let pool = ProxyPool::new(proxies, |proxy| {
+                    Ok(client_builder()?.proxy(proxy).build()?)
+                })?;
+                (pool.primary_client(), Some(Arc::new(pool)))
+            }
+            None => (client_builder()?.build()?, None),
+        };
+
+        Ok(Woo::from_parts(
+            http_client,
+            proxy_pool,
+            base_url,
+            public_data_url,
+            key,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::woo_data_structs::CancelOrderRes;
+    use reqwest::Method;
+
+    #[test]
+    fn http2_is_only_refused_where_the_proxy_would_see_it() {
+        let proxied = |base_url: &str| {
+            WooBuilder::new(Environment::Staging, Credentials::new("key", "secret"))
+                .base_url(Url::parse(base_url).unwrap())
+                .proxy(ProxyConfig::Http {
+                    url: Url::parse("http://127.0.0.1:3128").unwrap(),
+                    basic_auth: None,
+                })
+                .prefer_http2(true)
+                .build()
+        };
+
+        assert!(proxied("https://api.staging.woo.org").is_ok());
+        assert!(matches!(
+            proxied("http://127.0.0.1:8080"),
+            Err(WooError::Config(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn unreachable_proxy_fails_fast_with_transport_error() {
+        let woo = WooBuilder::new(Environment::Staging, Credentials::new("key", "secret"))
+            .proxy(ProxyConfig::Http {
+                // nothing listens on port 1
+                url: Url::parse("http://127.0.0.1:1").unwrap(),
+                basic_auth: None,
+            })
+            .connect_timeout(Duration::from_secs(2))
+            .build()
+            .unwrap();
+
+        let res = tokio::time::timeout(
+            Duration::from_secs(5),
+            woo.public_request::<_, CancelOrderRes>(Method::GET, "v1/public/system_info", ()),
+        )
+        .await
+        .expect("request through a dead proxy should not hang");
+
+        match res {
+            Err(err @ WooError::Transport { .. }) => assert!(err.request_id().is_some()),
+            other => panic!("expected a transport error, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn pool_fails_over_and_quarantines_dead_proxy() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        // the mock server doubles as a plain http proxy: it receives the
+        // absolute-form request for the (unresolvable) api host
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/public/system_info"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_string(r#"{"success":true,"status":"OK"}"#),
+            )
+            .mount(&server)
+            .await;
+
+        let dead = ProxyConfig::Http {
+            url: Url::parse("http://127.0.0.1:1").unwrap(),
+            basic_auth: None,
+        };
+        let live = ProxyConfig::Http {
+            url: Url::parse(&server.uri()).unwrap(),
+            basic_auth: None,
+        };
+
+        let woo = WooBuilder::new(Environment::Staging, Credentials::new("key", "secret"))
+            .base_url(Url::parse("http://woo.invalid").unwrap())
+            .proxy_pool(
+                ProxyPoolConfig::new(vec![dead, live], RotationStrategy::Failover)
+                    .quarantine_after(1)
+                    .cooldown(Duration::from_secs(60)),
+            )
+            .build()
+            .unwrap();
+
+        for _ in 0..2 {
+            let res: CancelOrderRes = woo
+                .public_request(Method::GET, "v1/public/system_info", ())
+                .await
+                .unwrap();
+            assert!(res.success);
+        }
+
+        let status = woo.proxy_status();
+        assert_eq!(status.len(), 2);
+
+        // the dead proxy was tried once, then skipped while quarantined
+        assert!(!status[0].healthy);
+        assert_eq!(status[0].total_requests, 1);
+        assert_eq!(status[0].total_failures, 1);
+        assert!(status[0].quarantined_for.is_some());
+        assert!(status[0].last_error.is_some());
+
+        assert!(status[1].healthy);
+        assert_eq!(status[1].total_requests, 2);
+    }
+
+    #[tokio::test]
+    async fn sends_user_agent_default_headers_and_request_id() {
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/public/system_info"))
+            .and(header("user-agent", "my-bot/1.2"))
+            .and(header("x-team", "market-making"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{"success":false,"code":-1000,"message":"An unknown error occurred."}"#,
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let woo = WooBuilder::new(Environment::Staging, Credentials::new("key", "secret"))
+            .base_url(Url::parse(&server.uri()).unwrap())
+            .user_agent("my-bot/1.2")
+            .default_header("x-team", "market-making")
+            .build()
+            .unwrap();
+
+        let err = woo
+            .public_request::<_, CancelOrderRes>(Method::GET, "v1/public/system_info", ())
+            .await
+            .unwrap_err();
+
+        let requests = server.received_requests().await.unwrap();
+        let sent_id = requests[0].headers["x-request-id"].to_str().unwrap();
+
+        assert!(matches!(err, WooError::Api(_)));
+        assert_eq!(err.request_id().unwrap().to_string(), sent_id);
+    }
+
+    #[tokio::test]
+    async fn failures_without_a_code_keep_the_request_id() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/public/system_info"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"success":false}"#))
+            .mount(&server)
+            .await;
+        let woo = WooBuilder::new(Environment::Staging, Credentials::new("key", "secret"))
+            .base_url(Url::parse(&server.uri()).unwrap())
+            .build()
+            .unwrap();
+
+        let err = woo
+            .public_request::<_, CancelOrderRes>(Method::GET, "v1/public/system_info", ())
+            .await
+            .unwrap_err();
+
+        let requests = server.received_requests().await.unwrap();
+        let sent_id = requests[0].headers["x-request-id"].to_str().unwrap();
+        assert!(err.to_string().contains("success: false"));
+        assert_eq!(err.request_id().unwrap().to_string(), sent_id);
+    }
+
+    #[tokio::test]
+    async fn identifies_the_crate_by_default() {
+        use wiremock::matchers::header;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(header("user-agent", DEFAULT_USER_AGENT))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(r#"{"success":true,"data":{"status":"CANCEL_ALL_SENT"}}"#),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let woo = WooBuilder::new(Environment::Staging, Credentials::new("key", "secret"))
+            .base_url(Url::parse(&server.uri()).unwrap())
+            .build()
+            .unwrap();
+
+        assert!(woo.cancel_all_orders_global().await.unwrap().success);
+        assert!(DEFAULT_USER_AGENT.starts_with("woo-http-client/"));
+    }
+
+    #[test]
+    fn rejects_invalid_default_header() {
+        let res = WooBuilder::new(Environment::Staging, Credentials::new("key", "secret"))
+            .default_header("bad header", "value")
+            .build();
+
+        assert!(matches!(res, Err(WooError::Config(_))));
+    }
+
+    #[test]
+    fn shared_client_rejects_builder_proxy() {
+        let res = WooBuilder::new(Environment::Staging, Credentials::new("key", "secret"))
+            .http_client(reqwest::Client::new())
+            .proxy(ProxyConfig::Http {
+                url: Url::parse("http://127.0.0.1:3128").unwrap(),
+                basic_auth: None,
+            })
+            .build();
+
+        assert!(matches!(res, Err(WooError::Config(_))));
+    }
+
+    #[cfg(feature = "dangerous-tls")]
+    #[test]
+    fn invalid_certs_are_only_accepted_outside_production() {
+        let build = |environment| {
+            WooBuilder::new(environment, Credentials::new("key", "secret"))
+                .base_url(Url::parse("https://gateway.internal").unwrap())
+                .danger_accept_invalid_certs(true)
+                .build()
+        };
+
+        assert!(matches!(
+            build(Environment::Production),
+            Err(WooError::Config(_))
+        ));
+        assert!(build(Environment::Staging).is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_api_key() {
+        let res =
+            WooBuilder::new(Environment::Staging, Credentials::new("bad\nkey", "secret")).build();
+
+        assert!(matches!(res, Err(WooError::Config(_))));
+    }
+}
@@ -0,0 +1,297 @@
+use crate::constants::{RateLimit, SEND_ORDER_RATE_LIMIT};
+use crate::error::{WooError, WooErrorCode};
+use crate::woo::Woo;
+use crate::woo_data_structs::{OrderId, WooOrder};
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+use tokio::time::{self, Instant};
+
+/// How [`Woo::place_orders_bulk`] paces and retries orders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BulkPolicy {
+    /// Budget per symbol, as WOO counts sent orders. Set it below
+    /// [`SEND_ORDER_RATE_LIMIT`] to leave room for other orders meanwhile.
+    pub rate_limit: RateLimit,
+    /// Further attempts at an order after a transient failure.
+    pub retries: u32,
+    /// Wait before the first retry of an order, doubled for each after it.
+    pub backoff: Duration,
+}
+
+impl Default for BulkPolicy {
+    fn default() -> Self {
+        Self {
+            rate_limit: SEND_ORDER_RATE_LIMIT,
+            retries: 3,
+            backoff: Duration::from_millis(500),
+        }
+    }
+}
+
+/// One order given to [`Woo::place_orders_bulk`] and what became of it.
+#[derive(Debug)]
+pub struct BulkEntry {
+    /// As sent, with the `client_order_id` it was given if it had none.
+    pub order: WooOrder,
+    pub outcome: Result<OrderId, WooError>,
+    pub attempts: u32,
+}
+
+/// Every order given to [`Woo::place_orders_bulk`], in the order given.
+#[derive(Debug, Default)]
+pub struct BulkReport {
+    pub entries: Vec<BulkEntry>,
+}
+
+impl BulkReport {
+    /// The orders on the book, with their ids.
+    pub fn placed(&self) -> impl Iterator<Item = (&WooOrder, OrderId)> {
+        self.entries
+            .iter()
+            .filter_map(|entry| entry.outcome.as_ref().ok().map(|id| (&entry.order, *id)))
+    }
+
+    /// The orders that couldn't be placed, e.g. to fill in the gaps of a grid.
+    pub fn failed(&self) -> impl Iterator<Item = (&WooOrder, &WooError)> {
+        self.entries
+            .iter()
+            .filter_map(|entry| entry.outcome.as_ref().err().map(|err| (&entry.order, err)))
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.entries.iter().all(|entry| entry.outcome.is_ok())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BulkProgress {
+    pub placed: usize,
+    pub failed: usize,
+    pub total: usize,
+}
+
+impl BulkProgress {
+    pub fn done(&self) -> usize {
+        self.placed + self.failed
+    }
+}
+
+/// Spaces out sends per symbol so none exceeds the rate limit.
+struct Pacer {
+    limit: RateLimit,
+    sent: HashMap<String, VecDeque<Instant>>,
+}
+
+impl Pacer {
+    async fn wait_turn(&mut self, symbol: &str) {
+        let sent = self.sent.entry(symbol.to_string()).or_default();
+        if sent.len() >= self.limit.requests.max(1) as usize {
+            if let Some(oldest) = sent.pop_front() {
+                time::sleep_until(oldest + self.limit.per).await;
+            }
+        }
+        sent.push_back(Instant::now());
+    }
+}
+
+/// Failures worth sending the same order again for: WOO asking to slow down
+/// or its order service being unreachable. Lost responses never get here,
+/// [`Woo::place_or_look_up`] resolves those by looking the order up.
+fn is_transient(err: &WooError) -> bool {
+    match err {
+        WooError::Api(err) | WooError::OrderRejected(err) => matches!(
+            err.error_code(),
+            WooErrorCode::TooManyRequests | WooErrorCode::RpcNotConnected
+        ),
+        _ => false,
+    }
+}
+
+impl Woo {
+    /// Places `orders` one after the other, e.g. the levels of a grid,
+    /// reporting to `on_progress` after each.
+    ///
+    /// WOO's REST API (<https://docs.woo.org/>, "Orders") takes one order per
+    /// send, there is no batch endpoint for placing them, so they go out
+    /// paced to `policy.rate_limit` per symbol, and transient failures are
+    /// retried with backoff. Each order is given a `client_order_id` if it
+    /// has none, so one whose response is lost is looked up rather than sent
+    /// twice; if the lookup fails too, it is reported as
+    /// [`WooError::OrderStateUnknown`], to look up before filling the gap.
+    /// A failed order doesn't stop the ones after it: the report says which
+    /// were placed and which weren't.
+    pub async fn place_orders_bulk(
+        &self,
+        orders: Vec<WooOrder>,
+        policy: BulkPolicy,
+        mut on_progress: Option<&mut (dyn FnMut(&BulkProgress) + Send)>,
+    ) -> BulkReport {
+        let mut pacer = Pacer {
+            limit: policy.rate_limit,
+            sent: HashMap::new(),
+        };
+        let mut progress = BulkProgress {
+            placed: 0,
+            failed: 0,
+            total: orders.len(),
+        };
+        let mut report = BulkReport::default();
+
+        for mut order in orders {
+            let client_order_id = order
+                .client_order_id
+                .get_or_insert_with(|| self.next_client_order_id())
+                .clone();
+
+            let mut attempts = 0;
+            let outcome = loop {
+                if attempts > 0 {
+                    time::sleep(policy.backoff * 2u32.saturating_pow(attempts - 1)).await;
+                }
+                pacer.wait_turn(&order.symbol).await;
+                attempts += 1;
+
+                match self.place_or_look_up(&order, &client_order_id).await {
+                    Ok(Some(order_id)) => break Ok(order_id),
+                    Ok(None) if attempts <= policy.retries => {}
+                    Ok(None) => {
+                        break Err(WooError::InvalidOrder(format!(
+                            "order {client_order_id} was never placed"
+                        )))
+                    }
+                    Err(err) if is_transient(&err) && attempts <= policy.retries => {}
+                    Err(err) => break Err(err),
+                }
+            };
+
+            match outcome {
+                Ok(_) => progress.placed += 1,
+                Err(ref err) => {
+                    tracing::warn!(%client_order_id, symbol = %order.symbol, %err, "bulk order not placed");
+                    progress.failed += 1;
+                }
+            }
+            report.entries.push(BulkEntry {
+                order,
+                outcome,
+                attempts,
+            });
+            if let Some(on_progress) = on_progress.as_mut() {
+                on_progress(&progress);
+            }
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mock_woo;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+    use wiremock::matchers::{body_string_contains, method, path, path_regex};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn level(price: Decimal) -> WooOrder {
+        WooOrder::limit("SPOT_BTC_USDT", "BUY", price, dec!(0.01))
+    }
+
+    async fn answer(server: &MockServer, price: &str, status: u16, body: &str, times: u64) {
+        Mock::given(method("POST"))
+            .and(path("/v1/order"))
+            .and(body_string_contains(format!("order_price={price}&")))
+            .respond_with(ResponseTemplate::new(status).set_body_string(body))
+            .up_to_n_times(times)
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn grid_levels_are_reported_placed_or_failed_in_order() {
+        let server = MockServer::start().await;
+        let placed = |order_id: u64| {
+            format!(
+                r#"{{"success":true,"timestamp":"1578565539.808","order_id":{order_id},"order_type":"LIMIT","client_order_id":0,"order_price":9000,"order_quantity":0.01}}"#
+            )
+        };
+        answer(&server, "9000", 200, &placed(1), u64::MAX).await;
+        // rate limited once, placed on the retry
+        answer(
+            &server,
+            "8900",
+            429,
+            r#"{"success":false,"code":-1003,"message":"Rate limit exceed."}"#,
+            1,
+        )
+        .await;
+        answer(&server, "8900", 200, &placed(2), u64::MAX).await;
+        answer(
+            &server,
+            "8800",
+            400,
+            r#"{"success":false,"code":-1102,"message":"The order value (price * size) is too small."}"#,
+            u64::MAX,
+        )
+        .await;
+        let woo = mock_woo(&server);
+        let policy = BulkPolicy {
+            backoff: Duration::from_millis(1),
+            ..BulkPolicy::default()
+        };
+
+        let mut seen = Vec::new();
+        let mut on_progress = |progress: &BulkProgress| seen.push(*progress);
+        let report = woo
+            .place_orders_bulk(
+                vec![level(dec!(9000)), level(dec!(8900)), level(dec!(8800))],
+                policy,
+                Some(&mut on_progress),
+            )
+            .await;
+
+        assert!(!report.is_complete());
+        let placed: Vec<_> = report.placed().map(|(_, order_id)| order_id).collect();
+        assert_eq!(placed, [OrderId(1), OrderId(2)]);
+        assert_eq!(report.entries[1].attempts, 2);
+        let (failed, err) = report.failed().next().unwrap();
+        assert_eq!(failed.order_price, Some(dec!(8800)));
+        assert!(failed.client_order_id.is_some());
+        assert!(matches!(err, WooError::OrderRejected(err) if err.code == -1102));
+        assert_eq!(report.entries[2].attempts, 1);
+
+        assert_eq!(seen.len(), 3);
+        assert_eq!(seen[2].done(), 3);
+        assert_eq!((seen[2].placed, seen[2].failed), (2, 1));
+    }
+
+    #[tokio::test]
+    async fn order_whose_fate_is_unknown_is_not_sent_again() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/order"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("<html>"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path_regex("^/v1/client/order/"))
+            .respond_with(ResponseTemplate::new(502).set_body_string("Bad Gateway"))
+            .mount(&server)
+            .await;
+        let woo = mock_woo(&server);
+        let policy = BulkPolicy {
+            backoff: Duration::from_millis(1),
+            ..BulkPolicy::default()
+        };
+
+        let report = woo
+            .place_orders_bulk(vec![level(dec!(9000))], policy, None)
+            .await;
+
+        let (_, err) = report.failed().next().unwrap();
+        assert!(matches!(err, WooError::OrderStateUnknown { .. }), "{err}");
+        assert_eq!(report.entries[0].attempts, 1);
+    }
+}
@@ -0,0 +1,125 @@
+use crate::error::{WooApiError, WooError};
+use crate::woo::Woo;
+use crate::woo_data_structs::{CancelOrder, CancelOrderRes, OrderId};
+use futures::stream::{self, StreamExt};
+use reqwest::Method;
+
+/// How many cancels are in flight at once, to stay clear of the rate limit.
+const CANCEL_CONCURRENCY: usize = 8;
+
+/// What happened to one order swept by [`Woo::cancel_orders_by_tag`].
+#[derive(Debug)]
+pub struct TaggedCancel {
+    pub order_id: OrderId,
+    pub symbol: String,
+    pub outcome: CancelOutcome,
+}
+
+#[derive(Debug)]
+pub enum CancelOutcome {
+    Cancelled,
+    /// The order filled or was cancelled between listing and cancelling it.
+    AlreadyClosed,
+    Failed(WooError),
+}
+
+impl Woo {
+    /// Cancels every open order tagged `order_tag`, leaving orders with other
+    /// tags (other strategies' quotes) alone.
+    ///
+    /// All pages of open orders are listed before the first cancel so that
+    /// cancels don't shift the pagination under the listing.
+    pub async fn cancel_orders_by_tag(&self, order_tag: &str) -> anyhow::Result<Vec<TaggedCancel>> {
+        let orders = self.open_orders(None, Some(order_tag)).await?;
+
+        let cancels = stream::iter(orders)
+            .map(|order| async move {
+                let cancel = CancelOrder {
+                    order_id: order.order_id,
+                    symbol: order.symbol,
+                };
+
+                let outcome = match self
+                    .signed_request::<_, CancelOrderRes>(Method::DELETE, "v1/order", &cancel)
+                    .await
+                {
+                    Ok(_) => CancelOutcome::Cancelled,
+                    Err(WooError::Api(err)) if err.code == WooApiError::RESOURCE_NOT_FOUND => {
+                        CancelOutcome::AlreadyClosed
+                    }
+                    Err(err) => CancelOutcome::Failed(err),
+                };
+
+                TaggedCancel {
+                    order_id: cancel.order_id,
+                    symbol: cancel.symbol,
+                    outcome,
+                }
+            })
+            .buffer_unordered(CANCEL_CONCURRENCY)
+            .collect()
+            .await;
+
+        Ok(cancels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{mock_woo, ok, order_row, page, with};
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn cancels_each_tagged_order_and_reports_closed_ones() {
+        let server = MockServer::start().await;
+
+        for (order_id, current_page) in [(1, 1), (2, 2)] {
+            Mock::given(method("GET"))
+                .and(path("/v1/orders"))
+                .and(query_param("order_tag", "twap"))
+                .and(query_param("status", "INCOMPLETE"))
+                .and(query_param("page", current_page.to_string()))
+                .respond_with(ok(page(
+                    vec![with(
+                        order_row(order_id, "NEW"),
+                        serde_json::json!({ "order_tag": "twap" }),
+                    )],
+                    2,
+                    1,
+                    current_page,
+                )))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+        Mock::given(method("DELETE"))
+            .and(path("/v1/order"))
+            .and(query_param("order_id", "1"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(r#"{"success":true,"status":"CANCEL_SENT"}"#),
+            )
+            .mount(&server)
+            .await;
+        // order 2 filled after it was listed
+        Mock::given(method("DELETE"))
+            .and(path("/v1/order"))
+            .and(query_param("order_id", "2"))
+            .respond_with(ResponseTemplate::new(400).set_body_string(
+                r#"{"success":false,"code":-1006,"message":"Your order and symbol are not valid or already canceled."}"#,
+            ))
+            .mount(&server)
+            .await;
+
+        let woo = mock_woo(&server);
+
+        let mut results = woo.cancel_orders_by_tag("twap").await.unwrap();
+        results.sort_by_key(|result| result.order_id);
+
+        assert_eq!(results.len(), 2);
+        assert!(matches!(results[0].outcome, CancelOutcome::Cancelled));
+        assert!(matches!(results[1].outcome, CancelOutcome::AlreadyClosed));
+    }
+}
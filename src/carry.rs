@@ -0,0 +1,394 @@
+use crate::error::WooError;
+use crate::serde_helpers::{deserialize_woo_timestamp, string_or_number};
+use crate::symbol::{Market, Symbol};
+use crate::woo::Woo;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use futures::TryStreamExt;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Hours between fundings when WOO doesn't say.
+const DEFAULT_FUNDING_INTERVAL_HOURS: i64 = 8;
+
+#[derive(Serialize, Clone)]
+struct FundingFeeQuery {
+    symbol: String,
+    start_t: i64,
+    end_t: i64,
+    page: u32,
+}
+
+#[derive(Serialize, Clone)]
+struct InterestQuery {
+    token: String,
+    start_t: i64,
+    end_t: i64,
+    page: u32,
+}
+
+// https://docs.woo.org/#get-funding-fee-history
+#[derive(Deserialize, Debug, Clone)]
+pub struct FundingFee {
+    pub symbol: String,
+    #[serde(deserialize_with = "string_or_number::deserialize")]
+    pub funding_rate: Decimal,
+    #[serde(deserialize_with = "string_or_number::deserialize")]
+    pub mark_price: Decimal,
+    /// Always positive, `payment_type` tells which way it went.
+    #[serde(deserialize_with = "string_or_number::deserialize")]
+    pub funding_fee: Decimal,
+    /// `Pay` or `Receive`.
+    pub payment_type: String,
+    #[serde(default)]
+    pub funding_interval_hours: Option<i64>,
+    #[serde(deserialize_with = "deserialize_woo_timestamp")]
+    pub created_time: DateTime<Utc>,
+}
+
+impl FundingFee {
+    /// Positive when received.
+    pub fn received(&self) -> Decimal {
+        if self.payment_type.eq_ignore_ascii_case("receive") {
+            self.funding_fee.abs()
+        } else {
+            -self.funding_fee.abs()
+        }
+    }
+
+    /// Whether the position was long when this was settled: longs pay a
+    /// positive rate and shorts a negative one. `None` at a zero rate.
+    pub fn held_long(&self) -> Option<bool> {
+        if self.funding_rate.is_zero() {
+            return None;
+        }
+        let paid = self.received().is_sign_negative();
+
+        Some(paid == self.funding_rate.is_sign_positive())
+    }
+}
+
+// https://docs.woo.org/#get-interest-history
+#[derive(Deserialize, Debug, Clone)]
+pub struct InterestCharge {
+    pub token: String,
+    /// `LOAN` for interest charged on a borrow, `REPAY` for repayments.
+    pub side: String,
+    #[serde(deserialize_with = "string_or_number::deserialize")]
+    pub amount: Decimal,
+    #[serde(deserialize_with = "deserialize_woo_timestamp")]
+    pub created_time: DateTime<Utc>,
+}
+
+/// Carry of one UTC day, amounts in the quote currency.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CarryDay {
+    pub date: NaiveDate,
+    /// Net funding, positive when received.
+    pub funding: Decimal,
+    /// The part of `funding` settled while long, and while short.
+    pub funding_long: Decimal,
+    pub funding_short: Decimal,
+    pub fundings: u32,
+    /// Borrow interest, base currency interest valued at the mark price of
+    /// the nearest funding.
+    pub interest: Decimal,
+    /// `funding` less `interest`.
+    pub net: Decimal,
+}
+
+/// A stretch longer than the funding interval with no funding in it, e.g.
+/// exchange downtime or the position being flat.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CarryGap {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+/// See [`Woo::carry_report`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CarryReport {
+    pub symbol: String,
+    /// Every day of the window, in order, including days with nothing.
+    pub days: Vec<CarryDay>,
+    pub funding: Decimal,
+    pub interest: Decimal,
+    pub net: Decimal,
+    /// Base currency interest that couldn't be valued, for lack of a single
+    /// funding to take a mark price from.
+    pub unpriced_interest: Decimal,
+    pub gaps: Vec<CarryGap>,
+}
+
+impl Woo {
+    /// Funding received or paid on the perpetual `symbol` over `[from, to]`,
+    /// netted against the interest charged on borrowing its base and quote
+    /// currencies and bucketed by UTC day, e.g. for a basis trade.
+    ///
+    /// Each funding is put down to the side held when it settled, so a
+    /// position flipping mid-window splits cleanly. Stretches without
+    /// funding are reported in [`CarryReport::gaps`] instead of passing as
+    /// zero carry.
+    pub async fn carry_report(
+        &self,
+        symbol: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> anyhow::Result<CarryReport> {
+        let parsed: Symbol = symbol.parse()?;
+        if parsed.market != Market::Perp {
+            return Err(WooError::InvalidSymbol(format!("{symbol} has no funding")).into());
+        }
+        let (start_t, end_t) = (from.timestamp_millis(), to.timestamp_millis());
+
+        let query = FundingFeeQuery {
+            symbol: symbol.to_string(),
+            start_t,
+            end_t,
+            page: 1,
+        };
+        let mut fundings: Vec<FundingFee> = self
+            .paginate("v1/funding_fee/history", 1, move |page| FundingFeeQuery {
+                page,
+                ..query.clone()
+            })
+            .try_collect()
+            .await?;
+        fundings.retain(|funding| (from..=to).contains(&funding.created_time));
+        fundings.sort_by_key(|funding| funding.created_time);
+
+        let mut charges = Vec::new();
+        for token in [&parsed.base, &parsed.quote] {
+            let query = InterestQuery {
+                token: token.clone(),
+                start_t,
+                end_t,
+                page: 1,
+            };
+            let rows: Vec<InterestCharge> = self
+                .paginate("v1/interest/history", 1, move |page| InterestQuery {
+                    page,
+                    ..query.clone()
+                })
+                .try_collect()
+                .await?;
+            charges.extend(rows.into_iter().filter(|charge| {
+                charge.side.eq_ignore_ascii_case("LOAN")
+                    && (from..=to).contains(&charge.created_time)
+            }));
+        }
+
+        Ok(CarryReport::build(
+            symbol, &parsed, from, to, &fundings, &charges,
+        ))
+    }
+}
+
+impl CarryReport {
+    fn build(
+        symbol: &str,
+        parsed: &Symbol,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        fundings: &[FundingFee],
+        charges: &[InterestCharge],
+    ) -> Self {
+        let mut days: BTreeMap<NaiveDate, CarryDay> = from
+            .date_naive()
+            .iter_days()
+            .take_while(|date| *date <= to.date_naive())
+            .map(|date| {
+                let day = CarryDay {
+                    date,
+                    ..Default::default()
+                };
+                (date, day)
+            })
+            .collect();
+        let mut report = CarryReport {
+            symbol: symbol.to_string(),
+            ..Default::default()
+        };
+
+        for funding in fundings {
+            let day = days
+                .get_mut(&funding.created_time.date_naive())
+                .expect("fundings are within the window");
+            let received = funding.received();
+            day.funding += received;
+            day.fundings += 1;
+            match funding.held_long() {
+                Some(true) => day.funding_long += received,
+                Some(false) => day.funding_short += received,
+                None => {}
+            }
+        }
+
+        for charge in charges {
+            let amount = if charge.token == parsed.quote {
+                charge.amount
+            } else {
+                match nearest_mark_price(fundings, charge.created_time) {
+                    Some(price) => charge.amount * price,
+                    None => {
+                        report.unpriced_interest += charge.amount;
+                        continue;
+                    }
+                }
+            };
+            let day = days
+                .get_mut(&charge.created_time.date_naive())
+                .expect("charges are within the window");
+            day.interest += amount;
+        }
+
+        for day in days.values_mut() {
+            day.net = day.funding - day.interest;
+            report.funding += day.funding;
+            report.interest += day.interest;
+        }
+        report.net = report.funding - report.interest;
+        report.days = days.into_values().collect();
+        report.gaps = funding_gaps(fundings, from, to);
+
+        report
+    }
+}
+
+fn nearest_mark_price(fundings: &[FundingFee], at: DateTime<Utc>) -> Option<Decimal> {
+    fundings
+        .iter()
+        .min_by_key(|funding| (funding.created_time - at).abs())
+        .map(|funding| funding.mark_price)
+}
+
+/// Stretches of `[from, to]` half again as long as the funding interval with
+/// no funding settled in them.
+fn funding_gaps(fundings: &[FundingFee], from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<CarryGap> {
+    let interval_hours = fundings
+        .iter()
+        .find_map(|funding| funding.funding_interval_hours)
+        .unwrap_or(DEFAULT_FUNDING_INTERVAL_HOURS);
+    let longest = Duration::hours(interval_hours) * 3 / 2;
+
+    let settled = fundings.iter().map(|funding| funding.created_time);
+    let edges: Vec<DateTime<Utc>> = std::iter::once(from)
+        .chain(settled)
+        .chain(std::iter::once(to))
+        .collect();
+
+    edges
+        .windows(2)
+        .filter(|pair| pair[1] - pair[0] > longest)
+        .map(|pair| CarryGap {
+            from: pair[0],
+            to: pair[1],
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mock_woo;
+    use rust_decimal_macros::dec;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn funding(hours: i64, rate: &str, fee: &str, payment_type: &str) -> serde_json::Value {
+        let at = 1704067200 + hours * 3600;
+        serde_json::json!({
+            "id": at, "symbol": "PERP_BTC_USDT", "funding_rate": rate,
+            "mark_price": "40000.00000000", "funding_fee": fee, "payment_type": payment_type,
+            "status": "COMPLETED", "funding_interval_hours": 8,
+            "created_time": format!("{at}.605"), "updated_time": format!("{at}.605"),
+        })
+    }
+
+    fn page(rows: Vec<serde_json::Value>) -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "success": true,
+            "meta": { "total": rows.len(), "records_per_page": 25, "current_page": 1 },
+            "rows": rows,
+        }))
+    }
+
+    fn interest(token: &str, hours: i64, amount: &str) -> serde_json::Value {
+        let at = 1704067200 + hours * 3600;
+        serde_json::json!({
+            "created_time": format!("{at}.041"), "updated_time": format!("{at}.041"),
+            "token": token, "side": "LOAN", "amount": amount,
+        })
+    }
+
+    #[tokio::test]
+    async fn nets_funding_against_interest_by_day_and_flags_gaps() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/funding_fee/history"))
+            .and(query_param("symbol", "PERP_BTC_USDT"))
+            .respond_with(page(vec![
+                // long, paying a positive rate
+                funding(0, "0.0001", "4", "Pay"),
+                funding(8, "0.0001", "4", "Pay"),
+                funding(16, "0.0002", "8", "Pay"),
+                // flipped short overnight, 08:00 on the 2nd never settled
+                funding(24, "0.0001", "4", "Receive"),
+                funding(40, "-0.0002", "8", "Pay"),
+            ]))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/interest/history"))
+            .and(query_param("token", "BTC"))
+            .respond_with(page(vec![interest("BTC", 30, "0.0001")]))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/interest/history"))
+            .and(query_param("token", "USDT"))
+            .respond_with(page(vec![
+                interest("USDT", 2, "1.5"),
+                serde_json::json!({
+                    "created_time": "1704070800.041", "updated_time": "1704070800.041",
+                    "token": "USDT", "side": "REPAY", "amount": "100",
+                }),
+            ]))
+            .mount(&server)
+            .await;
+        let woo = mock_woo(&server);
+
+        let from = DateTime::from_timestamp(1704067200, 0).unwrap();
+        let to = from + Duration::hours(48) - Duration::seconds(1);
+        let report = woo.carry_report("PERP_BTC_USDT", from, to).await.unwrap();
+
+        assert_eq!(report.days.len(), 2);
+        let first = &report.days[0];
+        assert_eq!(first.funding, dec!(-16));
+        assert_eq!(first.funding_long, dec!(-16));
+        assert_eq!(first.fundings, 3);
+        assert_eq!(first.interest, dec!(1.5));
+        assert_eq!(first.net, dec!(-17.5));
+
+        let second = &report.days[1];
+        // 4 received, then 8 paid once the rate turned negative, both short
+        assert_eq!(second.funding, dec!(-4));
+        assert_eq!(second.funding_short, dec!(-4));
+        assert_eq!(second.funding_long, dec!(0));
+        assert_eq!(second.fundings, 2);
+        assert_eq!(second.interest, dec!(4));
+
+        assert_eq!(report.funding, dec!(-20));
+        assert_eq!(report.net, dec!(-25.5));
+        assert_eq!(report.unpriced_interest, dec!(0));
+        assert_eq!(report.gaps.len(), 1);
+        assert_eq!(
+            report.gaps[0].from,
+            from + Duration::hours(24) + Duration::milliseconds(605)
+        );
+        assert_eq!(
+            report.gaps[0].to,
+            from + Duration::hours(40) + Duration::milliseconds(605)
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use crate::woo::Woo;
+use crate::woo_data_structs::{
+    CancelOrder, CancelOrderRes, GetOrder, GetOrderRes, HoldingRes, Meta, OrderDetail, OrderId,
+    PositionsRes, Row, SendOrderRes, WooOrder,
+};
+use std::cmp::Reverse;
+use std::future::Future;
+
+/// WOO's page size when a request doesn't set one.
+const DEFAULT_PAGE_SIZE: u32 = 25;
+
+/// The calls strategies make, so strategy code can run against [`Woo`] in
+/// production, against [`PaperWoo`](crate::paper::PaperWoo) in paper mode and
+/// against a mock in tests.
+pub trait WooClient: Send + Sync {
+    fn create_order(
+        &self,
+        order: WooOrder,
+    ) -> impl Future<Output = anyhow::Result<SendOrderRes>> + Send;
+
+    fn cancel_order(
+        &self,
+        cancel_order: CancelOrder,
+    ) -> impl Future<Output = anyhow::Result<CancelOrderRes>> + Send;
+
+    fn get_order_by_id(
+        &self,
+        order_id: OrderId,
+    ) -> impl Future<Output = anyhow::Result<OrderDetail>> + Send;
+
+    /// One page of the orders matching `filter`.
+    fn get_orders(
+        &self,
+        filter: GetOrder,
+    ) -> impl Future<Output = anyhow::Result<GetOrderRes>> + Send;
+
+    /// Balance of every token.
+    fn get_holding(&self) -> impl Future<Output = anyhow::Result<HoldingRes>> + Send;
+
+    fn get_positions(&self) -> impl Future<Output = anyhow::Result<PositionsRes>> + Send;
+}
+
+impl WooClient for Woo {
+    async fn create_order(&self, order: WooOrder) -> anyhow::Result<SendOrderRes> {
+        Ok(Woo::create_order(self, order).await?)
+    }
+
+    async fn cancel_order(&self, cancel_order: CancelOrder) -> anyhow::Result<CancelOrderRes> {
+        Ok(Woo::cancel_order(self, cancel_order).await?)
+    }
+
+    async fn get_order_by_id(&self, order_id: OrderId) -> anyhow::Result<OrderDetail> {
+        Woo::get_order(self, order_id).await
+    }
+
+    async fn get_orders(&self, filter: GetOrder) -> anyhow::Result<GetOrderRes> {
+        Woo::get_orders(self, filter).await
+    }
+
+    async fn get_holding(&self) -> anyhow::Result<HoldingRes> {
+        Woo::get_holding(self).await
+    }
+
+    async fn get_positions(&self) -> anyhow::Result<PositionsRes> {
+        Woo::get_positions(self).await
+    }
+}
+
+/// Answers `filter` from the orders an in-memory exchange keeps, newest
+/// first like WOO.
+pub(crate) fn page_of_orders(filter: &GetOrder, rows: impl Iterator<Item = Row>) -> GetOrderRes {
+    let matches = |row: &Row| {
+        let status = row.order_status();
+        filter
+            .symbol
+            .as_ref()
+            .is_none_or(|symbol| *symbol == row.symbol)
+            && filter.side.as_ref().is_none_or(|side| *side == row.side)
+            && filter
+                .order_type
+                .as_ref()
+                .is_none_or(|order_type| *order_type == row.r#type)
+            && filter
+                .order_tag
+                .as_ref()
+                .is_none_or(|tag| *tag == row.order_tag)
+            && filter.status.as_deref().is_none_or(|wanted| match wanted {
+                "INCOMPLETE" => !status.is_terminal(),
+                "COMPLETED" => status.is_terminal(),
+                wanted => wanted == row.status,
+            })
+    };
+    let mut rows: Vec<Row> = rows.filter(|row| matches(row)).collect();
+    rows.sort_by_key(|row| Reverse(row.order_id));
+
+    let records_per_page = filter.size.unwrap_or(DEFAULT_PAGE_SIZE).max(1);
+    let current_page = filter.page.unwrap_or(1).max(1);
+    let total = rows.len() as u32;
+    let rows = rows
+        .into_iter()
+        .skip(((current_page - 1) * records_per_page) as usize)
+        .take(records_per_page as usize)
+        .collect();
+
+    GetOrderRes {
+        success: true,
+        meta: Meta {
+            total,
+            records_per_page,
+            current_page,
+        },
+        rows,
+    }
+}
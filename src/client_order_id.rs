@@ -0,0 +1,93 @@
+use crate::woo::Woo;
+use crate::woo_data_structs::ClientOrderId;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Hands out increasing `client_order_id`s, unique across every clone of the
+/// [`Woo`] it belongs to.
+///
+/// Seeded with the current unix time in seconds, so a restarted process
+/// carries on above the ids of the previous run as long as that run used
+/// fewer ids than seconds went by. Past `u32::MAX` it wraps around to 1, as
+/// 0 means "no client order id" to WOO.
+#[derive(Debug)]
+pub struct ClientOrderIdGenerator {
+    next: AtomicU32,
+}
+
+impl ClientOrderIdGenerator {
+    pub fn new() -> Self {
+        let now = chrono::Utc::now().timestamp();
+        Self::starting_at(u32::try_from(now).unwrap_or(1))
+    }
+
+    pub fn starting_at(seed: u32) -> Self {
+        Self {
+            next: AtomicU32::new(seed.max(1)),
+        }
+    }
+
+    pub fn next_id(&self) -> ClientOrderId {
+        ClientOrderId::Numeric(
+            self.next
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |id| {
+                    Some(id.checked_add(1).unwrap_or(1))
+                })
+                .unwrap(),
+        )
+    }
+}
+
+impl Default for ClientOrderIdGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Woo {
+    pub fn next_client_order_id(&self) -> ClientOrderId {
+        self.client_order_ids.next_id()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::Credentials;
+    use crate::woo::Environment;
+    use std::collections::HashSet;
+
+    #[tokio::test]
+    async fn ids_are_unique_across_tasks_and_clones() {
+        let woo = Woo::builder(Environment::Staging, Credentials::new("key", "secret"))
+            .build()
+            .unwrap();
+
+        let tasks: Vec<_> = (0..16)
+            .map(|_| {
+                let woo = woo.clone();
+                tokio::spawn(async move {
+                    (0..1000)
+                        .map(|_| woo.next_client_order_id())
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        let mut ids = HashSet::new();
+        for task in tasks {
+            for id in task.await.unwrap() {
+                assert!(ids.insert(id.clone()), "duplicate id {id}");
+            }
+        }
+        assert_eq!(ids.len(), 16_000);
+    }
+
+    #[test]
+    fn wraps_to_one_past_the_ceiling() {
+        let generator = ClientOrderIdGenerator::starting_at(u32::MAX);
+
+        assert_eq!(generator.next_id(), ClientOrderId::Numeric(u32::MAX));
+        assert_eq!(generator.next_id(), ClientOrderId::Numeric(1));
+        assert_eq!(generator.next_id(), ClientOrderId::Numeric(2));
+    }
+}
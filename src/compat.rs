@@ -0,0 +1,284 @@
+use crate::diagnostics::{clock_skew_ms_of, MAX_CLOCK_SKEW};
+use crate::error::{WooApiError, WooError, WooErrorCode};
+use crate::woo::Woo;
+
+/// A worked signing example: the inputs of a request and the signature they
+/// must come out with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SigningExample {
+    pub api_key: &'static str,
+    pub secret: &'static str,
+    pub timestamp: u64,
+    pub method: &'static str,
+    pub path: &'static str,
+    /// The sorted form parameters of a `v1` request, the JSON body of a `v3`
+    /// one.
+    pub payload: &'static str,
+    pub signature: &'static str,
+}
+
+/// The `v1` order signed in <https://docs.woo.org/#authentication>.
+pub const DOCS_SEND_ORDER: SigningExample = SigningExample {
+    api_key: "AbmyVJGUpN064ks5ELjLfA==",
+    secret: "QHKRXHPAW1MC9YGZMAT8YDJG2HPR",
+    timestamp: 1578565539808,
+    method: "POST",
+    path: "/v1/order",
+    payload: "order_price=9000&order_quantity=0.11&order_type=LIMIT&side=BUY&symbol=SPOT_BTC_USDT",
+    signature: "20da0852f73b20da0208c7e627975a59ff072379883d8457d03104651032033d",
+};
+
+impl SigningExample {
+    fn is_v3(&self) -> bool {
+        self.path.starts_with("/v3/")
+    }
+
+    /// The signature this crate computes for the example, to compare with
+    /// the documented `signature`.
+    pub fn sign(&self) -> String {
+        if self.is_v3() {
+            v3_signature(
+                self.timestamp,
+                self.method,
+                self.path,
+                self.payload,
+                self.secret,
+            )
+        } else {
+            v1_signature(self.payload, self.timestamp, self.secret)
+        }
+    }
+
+    /// The example as this crate sends it, signed with
+    /// [`SigningExample::sign`]: the request line, the headers and, unless
+    /// the parameters go into the query string, the body.
+    pub fn request_lines(&self) -> String {
+        let in_query = !self.is_v3() && (self.method == "GET" || self.method == "DELETE");
+        let mut lines = vec![if in_query && !self.payload.is_empty() {
+            format!("{} {}?{}", self.method, self.path, self.payload)
+        } else {
+            format!("{} {}", self.method, self.path)
+        }];
+        lines.push(format!("x-api-key: {}", self.api_key));
+        lines.push(format!("x-api-timestamp: {}", self.timestamp));
+        lines.push(format!("x-api-signature: {}", self.sign()));
+        if !in_query {
+            let content_type = if self.is_v3() {
+                "application/json"
+            } else {
+                "application/x-www-form-urlencoded"
+            };
+            lines.push(format!("content-type: {content_type}"));
+            lines.push(String::new());
+            lines.push(self.payload.to_string());
+        }
+
+        lines.join("\n")
+    }
+}
+
+/// The `v1` signature of the sorted parameters `payload`, the hex HMAC of
+/// `{payload}|{timestamp}`.
+pub fn v1_signature(payload: &str, timestamp: u64, secret: &str) -> String {
+    Woo::generate_hmac_sha256_signature(payload.to_string(), timestamp, secret)
+}
+
+/// The `v3` signature of a request, the hex HMAC of
+/// `{timestamp}{method}{path}{body}`.
+pub fn v3_signature(timestamp: u64, method: &str, path: &str, body: &str, secret: &str) -> String {
+    Woo::generate_v3_signature(&format!("{timestamp}{method}{path}{body}"), secret)
+}
+
+/// What [`verify_my_credentials`] made of a signed read.
+#[derive(Debug)]
+pub enum CredentialCheck {
+    /// WOO accepted the signed request.
+    Valid,
+    /// The api key is unknown, revoked or lacks the permission.
+    BadKey(WooApiError),
+    /// The key is known but the signature didn't match, so the secret is
+    /// wrong.
+    BadSecret(WooApiError),
+    /// The key's IP whitelist doesn't allow the address the request came
+    /// from.
+    IpNotWhitelisted(WooApiError),
+    /// Refused for its timestamp, or with the local clock `skew_ms` off the
+    /// server, further than signed requests tolerate. `None` if the skew
+    /// couldn't be measured.
+    ClockSkew {
+        skew_ms: Option<i64>,
+        error: WooApiError,
+    },
+    /// Failed for another reason, e.g. WOO couldn't be reached.
+    Failed(WooError),
+}
+
+/// Performs a harmless signed read, the account info, and tells whether
+/// `woo`'s credentials work and if not, the likely reason.
+///
+/// WOO answers a wrong signature with -1001 and a bad key with -1002; the
+/// IP whitelist and the timestamp have no code of their own, so those are
+/// told apart by the message and by measuring the clock skew beforehand.
+pub async fn verify_my_credentials(woo: &Woo) -> CredentialCheck {
+    let skew_ms = clock_skew_ms_of(woo).await.ok();
+
+    let error = match woo.account_info().await {
+        Ok(_) => return CredentialCheck::Valid,
+        Err(WooError::Api(error)) => error,
+        Err(err) => return CredentialCheck::Failed(err),
+    };
+
+    let message = error.message.to_ascii_lowercase();
+    let mentions = |needles: &[&str]| {
+        message
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .any(|word| needles.contains(&word))
+    };
+    let skewed =
+        skew_ms.is_some_and(|skew| skew.unsigned_abs() > MAX_CLOCK_SKEW.as_millis() as u64);
+
+    if mentions(&["ip", "whitelist", "whitelisted"]) {
+        CredentialCheck::IpNotWhitelisted(error)
+    } else if skewed || mentions(&["timestamp"]) {
+        CredentialCheck::ClockSkew { skew_ms, error }
+    } else {
+        match error.error_code() {
+            WooErrorCode::InvalidSignature => CredentialCheck::BadSecret(error),
+            WooErrorCode::Unauthorized => CredentialCheck::BadKey(error),
+            _ => CredentialCheck::Failed(WooError::Api(error)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::Credentials;
+    use crate::woo::Environment;
+    use url::Url;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn documented_examples_sign_as_documented() {
+        assert_eq!(DOCS_SEND_ORDER.sign(), DOCS_SEND_ORDER.signature);
+        assert_eq!(
+            DOCS_SEND_ORDER.request_lines(),
+            concat!(
+                "POST /v1/order\n",
+                "x-api-key: AbmyVJGUpN064ks5ELjLfA==\n",
+                "x-api-timestamp: 1578565539808\n",
+                "x-api-signature: 20da0852f73b20da0208c7e627975a59ff072379883d8457d03104651032033d\n",
+                "content-type: application/x-www-form-urlencoded\n",
+                "\n",
+                "order_price=9000&order_quantity=0.11&order_type=LIMIT&side=BUY&symbol=SPOT_BTC_USDT",
+            )
+        );
+
+        let holding = SigningExample {
+            method: "GET",
+            path: "/v1/client/holding",
+            payload: "all=true",
+            signature: "",
+            ..DOCS_SEND_ORDER
+        };
+        assert!(holding
+            .request_lines()
+            .starts_with("GET /v1/client/holding?all=true\n"));
+        assert!(!holding.request_lines().contains("content-type"));
+
+        // cross-checked with `openssl dgst -sha256 -hmac secret`
+        assert_eq!(
+            v3_signature(1700000000000, "GET", "/v3/positions", "", "secret"),
+            "5d34e5debbabc1066208f554804efbae0a7b1c714e4ab7be4d15c01cc9b24ec4"
+        );
+    }
+
+    async fn rejecting(server: &MockServer, key: &str, code: i64, message: &str) {
+        Mock::given(method("GET"))
+            .and(path("/v1/client/info"))
+            .and(header("x-api-key", key))
+            .respond_with(ResponseTemplate::new(401).set_body_json(serde_json::json!({
+                "success": false, "code": code, "message": message,
+            })))
+            .mount(server)
+            .await;
+    }
+
+    async fn server_at(skew_ms: i64) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/public/system_info"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true, "data": { "status": 0, "msg": "System is functioning properly." },
+                "timestamp": chrono::Utc::now().timestamp_millis() + skew_ms,
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/client/info"))
+            .and(header("x-api-key", "good"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "application": {
+                    "application_id": "8935820a-6600-4c2c-9bc3-f017d89aa173",
+                    "account": "CLIENT_ACCOUNT_01", "alias": "CLIENT_ACCOUNT_01",
+                    "account_mode": "FUTURES", "leverage": 5, "taker_fee_rate": 0.0005,
+                    "maker_fee_rate": 0.0002, "futures_leverage": 5, "otpauth": false,
+                },
+            })))
+            .mount(&server)
+            .await;
+        rejecting(&server, "revoked", -1002, "API key or secret is invalid.").await;
+        rejecting(
+            &server,
+            "wrong-secret",
+            -1001,
+            "The api key or secret is in wrong format.",
+        )
+        .await;
+        rejecting(&server, "elsewhere", -1002, "IP address not in whitelist.").await;
+
+        server
+    }
+
+    async fn check(server: &MockServer, key: &str) -> CredentialCheck {
+        let woo = Woo::builder(Environment::Staging, Credentials::new(key, "secret"))
+            .base_url(Url::parse(&server.uri()).unwrap())
+            .build()
+            .unwrap();
+
+        verify_my_credentials(&woo).await
+    }
+
+    #[tokio::test]
+    async fn failed_auth_is_told_apart_by_code_message_and_clock() {
+        let server = server_at(0).await;
+
+        assert!(matches!(
+            check(&server, "good").await,
+            CredentialCheck::Valid
+        ));
+        assert!(matches!(
+            check(&server, "revoked").await,
+            CredentialCheck::BadKey(_)
+        ));
+        assert!(matches!(
+            check(&server, "wrong-secret").await,
+            CredentialCheck::BadSecret(_)
+        ));
+        assert!(matches!(
+            check(&server, "elsewhere").await,
+            CredentialCheck::IpNotWhitelisted(_)
+        ));
+
+        let skewed = server_at(60_000).await;
+        match check(&skewed, "wrong-secret").await {
+            CredentialCheck::ClockSkew { skew_ms, error } => {
+                assert!(skew_ms.unwrap() > 55_000, "{skew_ms:?}");
+                assert_eq!(error.code, -1001);
+            }
+            other => panic!("expected a clock skew, got {other:?}"),
+        }
+    }
+}
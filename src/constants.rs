@@ -1,4 +1,118 @@
-pub const PROXY_URL: &str = "https://brd.superproxy.io:22225";
-pub const PROXY_IP: &str = "194.34.130.36";
+use std::time::Duration;
+
+/// Default base urls, override them with [`WooBuilder::base_url`](crate::builder::WooBuilder::base_url).
 pub const WOO_API_BASE_URL: &str = "https://api.woo.org";
 pub const WOO_API_BASE_URL_STAGING: &str = "https://api.staging.woo.org";
+/// Historical market data such as `v1/hist/kline`, served from its own host
+/// whatever the environment. Override it with
+/// [`WooBuilder::public_data_url`](crate::builder::WooBuilder::public_data_url).
+pub const WOO_PUBLIC_DATA_BASE_URL: &str = "https://api-pub.woo.org";
+/// Public websocket streams, the application id is appended as the last
+/// path segment.
+#[cfg(feature = "websocket")]
+pub const WOO_WS_PUBLIC_URL: &str = "wss://wss.woo.org/ws/stream";
+#[cfg(feature = "websocket")]
+pub const WOO_WS_PUBLIC_URL_STAGING: &str = "wss://wss.staging.woo.org/ws/stream";
+
+/// Request budget for one endpoint, see <https://docs.woo.org/#rate-limit>.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub requests: u32,
+    pub per: Duration,
+}
+
+impl RateLimit {
+    const fn per_second(requests: u32) -> Self {
+        Self {
+            requests,
+            per: Duration::from_secs(1),
+        }
+    }
+}
+
+/// `POST v1/order`, counted per symbol.
+pub const SEND_ORDER_RATE_LIMIT: RateLimit = RateLimit::per_second(5);
+/// `DELETE v1/order` and `v1/client/order`, counted per symbol.
+pub const CANCEL_ORDER_RATE_LIMIT: RateLimit = RateLimit::per_second(10);
+/// `DELETE v1/orders`, counted per symbol.
+pub const CANCEL_ALL_ORDERS_RATE_LIMIT: RateLimit = RateLimit::per_second(10);
+/// Order and trade queries, `v1/order/{id}`, `v1/orders`, `v1/client/trades`.
+pub const ORDER_QUERY_RATE_LIMIT: RateLimit = RateLimit::per_second(10);
+/// `v1/client/holding` and `v1/client/info`.
+pub const ACCOUNT_RATE_LIMIT: RateLimit = RateLimit {
+    requests: 10,
+    per: Duration::from_secs(60),
+};
+/// `v1/positions`.
+pub const POSITIONS_RATE_LIMIT: RateLimit = RateLimit {
+    requests: 30,
+    per: Duration::from_secs(10),
+};
+/// Public market data, counted per ip.
+pub const PUBLIC_RATE_LIMIT: RateLimit = RateLimit::per_second(10);
+
+/// Largest `size` the paginated endpoints accept.
+pub const MAX_PAGE_SIZE: u32 = 500;
+/// Largest `limit` of `v1/public/kline`.
+pub const MAX_KLINE_LIMIT: u32 = 1000;
+/// Largest `size` of `v1/hist/trades`.
+pub const MAX_HIST_TRADES_SIZE: u32 = 1000;
+
+/// Longest `order_tag` WOO accepts.
+pub const ORDER_TAG_MAX_LEN: usize = 64;
+
+/// Whether WOO accepts `tag` as an `order_tag`: ascii letters, digits, `_`
+/// and `-`, at most [`ORDER_TAG_MAX_LEN`] long.
+pub fn is_valid_order_tag(tag: &str) -> bool {
+    !tag.is_empty()
+        && tag.len() <= ORDER_TAG_MAX_LEN
+        && tag
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+}
+
+/// Every documented error code with its message, see
+/// <https://docs.woo.org/#error-codes>.
+pub const ERROR_CODES: &[(i64, &str)] = &[
+    (-1000, "An unknown error occurred while processing the request."),
+    (-1001, "The api key or secret is in wrong format."),
+    (
+        -1002,
+        "API key or secret is invalid, it may because key have insufficient permission or the key is expired/revoked.",
+    ),
+    (-1003, "Rate limit exceed."),
+    (-1004, "An unknown parameter was sent."),
+    (-1005, "Some parameters are in wrong format for api."),
+    (-1006, "The data is not found in server."),
+    (-1007, "The data is already exists or your request is duplicated."),
+    (-1008, "The quantity of settlement is too high than you can request."),
+    (
+        -1009,
+        "Can not request withdrawal settlement, you need to deposit other arrears first.",
+    ),
+    (
+        -1011,
+        "Can not place/cancel orders, it may because internal network error. Please try again in a few seconds.",
+    ),
+    (
+        -1012,
+        "The place/cancel order request is rejected by internal module, it may because the account is in liquidation or other internal errors. Please try again in a few seconds.",
+    ),
+    (
+        -1101,
+        "The risk exposure for client is too high, it may cause by sending too big order or the leverage is too low.",
+    ),
+    (-1102, "The order value (price * size) is too small."),
+    (
+        -1103,
+        "The order price is not following the tick size rule for the symbol.",
+    ),
+    (
+        -1104,
+        "The order quantity is not following the step size rule for the symbol.",
+    ),
+    (
+        -1105,
+        "Price is X% too high or X% too low from the mid price.",
+    ),
+];
@@ -0,0 +1,278 @@
+use crate::error::WooError;
+use crate::woo::Woo;
+use crate::woo_data_structs::{GetOrder, Meta, OrderId, Row};
+use chrono::{DateTime, Utc};
+use futures::stream::{self, Stream, TryStreamExt};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+/// The last order a history pull handed out.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LastOrder {
+    pub order_id: OrderId,
+    pub created_time: DateTime<Utc>,
+}
+
+impl LastOrder {
+    fn of(row: &Row) -> Self {
+        Self {
+            order_id: row.order_id,
+            created_time: row.created_time,
+        }
+    }
+
+    /// Whether `row` comes after this one in `v1/orders`, which lists the
+    /// newest first.
+    fn precedes(&self, row: &Row) -> bool {
+        (row.created_time, row.order_id) < (self.created_time, self.order_id)
+    }
+}
+
+/// Where a pull of the order history got to, to checkpoint it, e.g. to disk,
+/// and carry on with [`Woo::get_orders_from`] after a restart.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OrdersCursor {
+    pub filter: GetOrder,
+    /// The page `last` is on.
+    pub page: u32,
+    /// `None` before the first row. Rows already processed up to and
+    /// including this one are never handed out again.
+    pub last: Option<LastOrder>,
+}
+
+impl OrdersCursor {
+    /// The start of the history matching `filter`, from `filter.page`.
+    pub fn new(filter: GetOrder) -> Self {
+        Self {
+            page: filter.page.unwrap_or(1),
+            filter,
+            last: None,
+        }
+    }
+}
+
+struct Resume {
+    cursor: OrdersCursor,
+    /// Set until the page of the checkpoint was found again.
+    skip_through: Option<LastOrder>,
+    /// Set once resumed by time, dropping rows the restart returns again.
+    after: Option<LastOrder>,
+}
+
+#[derive(Deserialize)]
+struct Page {
+    meta: Meta,
+    rows: Vec<Row>,
+}
+
+impl Page {
+    /// Whether this, fetched as page `number`, is the end of the history.
+    fn is_last(&self, number: u32) -> bool {
+        self.rows.is_empty() || number * self.meta.records_per_page >= self.meta.total
+    }
+}
+
+impl Woo {
+    /// Every order from `cursor` on, each with the cursor to resume after it.
+    ///
+    /// The page of the checkpoint is fetched again and read past its last
+    /// row. If that row isn't on the page any more, because orders placed
+    /// since shifted the pages or they were archived, the pull restarts by
+    /// time from the last row instead; either way no row is handed out twice.
+    pub fn get_orders_from(
+        &self,
+        cursor: OrdersCursor,
+    ) -> impl Stream<Item = Result<(Row, OrdersCursor), WooError>> + '_ {
+        let resume = Resume {
+            skip_through: cursor.last,
+            cursor,
+            after: None,
+        };
+
+        stream::try_unfold(Some(resume), move |resume| async move {
+            let Some(mut resume) = resume else {
+                return Ok(None);
+            };
+
+            let mut page = self.orders_page(&resume.cursor).await?;
+            // before reading past the checkpoint, which may be the last row
+            let mut exhausted = page.is_last(resume.cursor.page);
+            if let Some(last) = resume.skip_through.take() {
+                match page
+                    .rows
+                    .iter()
+                    .position(|row| row.order_id == last.order_id)
+                {
+                    Some(index) => {
+                        page.rows.drain(..=index);
+                    }
+                    None => {
+                        tracing::info!(
+                            page = resume.cursor.page,
+                            %last.order_id,
+                            "checkpoint not found on its page, resuming by time"
+                        );
+                        let end_t = Woo::checked_timestamp(last.created_time.timestamp_millis())?;
+                        resume.cursor.filter.end_t = Some(end_t);
+                        resume.cursor.page = 1;
+                        resume.after = Some(last);
+                        page = self.orders_page(&resume.cursor).await?;
+                        exhausted = page.is_last(resume.cursor.page);
+                    }
+                }
+            }
+
+            let current = resume.cursor.page;
+
+            let mut rows = Vec::with_capacity(page.rows.len());
+            for row in page.rows {
+                if resume.after.is_some_and(|after| !after.precedes(&row)) {
+                    continue;
+                }
+                let cursor = OrdersCursor {
+                    filter: resume.cursor.filter.clone(),
+                    page: current,
+                    last: Some(LastOrder::of(&row)),
+                };
+                rows.push(Ok((row, cursor)));
+            }
+
+            resume.cursor.page = current + 1;
+            let next = (!exhausted).then_some(resume);
+
+            Ok::<_, WooError>(Some((stream::iter(rows), next)))
+        })
+        .try_flatten()
+    }
+
+    async fn orders_page(&self, cursor: &OrdersCursor) -> Result<Page, WooError> {
+        let filter = GetOrder {
+            page: Some(cursor.page),
+            ..cursor.filter.clone()
+        };
+
+        self.signed_request(Method::GET, "v1/orders", filter).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{mock_woo, ok, order_row, page, with};
+    use wiremock::matchers::{method, path, query_param, query_param_is_missing};
+    use wiremock::{Mock, MockServer};
+
+    fn row(order_id: u64) -> serde_json::Value {
+        let time = format!("{}.000", 1_577_349_000 + order_id);
+        with(
+            order_row(order_id, "FILLED"),
+            serde_json::json!({
+                "executed": 0.1, "total_fee": 0.0001, "fee_asset": "BTC",
+                "created_time": time, "updated_time": time, "average_executed_price": 9000,
+            }),
+        )
+    }
+
+    async fn order_ids(
+        orders: impl Stream<Item = Result<(Row, OrdersCursor), WooError>>,
+    ) -> Vec<OrderId> {
+        orders
+            .map_ok(|(row, _)| row.order_id)
+            .try_collect()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn resumes_from_a_serialized_checkpoint() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/orders"))
+            .and(query_param("page", "1"))
+            .respond_with(ok(page(vec![row(5), row(4)], 3, 2, 1)))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/orders"))
+            .and(query_param("page", "2"))
+            .respond_with(ok(page(vec![row(3)], 3, 2, 1)))
+            .mount(&server)
+            .await;
+        let woo = mock_woo(&server);
+
+        let orders = woo.get_orders_from(OrdersCursor::new(GetOrder::default()));
+        futures::pin_mut!(orders);
+        let (first, checkpoint) = orders.try_next().await.unwrap().unwrap();
+        assert_eq!(first.order_id, OrderId(5));
+
+        let checkpoint: OrdersCursor =
+            serde_json::from_str(&serde_json::to_string(&checkpoint).unwrap()).unwrap();
+        assert_eq!(checkpoint.last.unwrap().order_id, OrderId(5));
+
+        let resumed = order_ids(woo.get_orders_from(checkpoint)).await;
+        assert_eq!(resumed, [OrderId(4), OrderId(3)]);
+    }
+
+    #[tokio::test]
+    async fn checkpoint_on_the_last_row_of_a_page_carries_on() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/orders"))
+            .and(query_param("page", "1"))
+            .respond_with(ok(page(vec![row(5), row(4)], 3, 2, 1)))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/orders"))
+            .and(query_param("page", "2"))
+            .respond_with(ok(page(vec![row(3)], 3, 2, 2)))
+            .mount(&server)
+            .await;
+        let woo = mock_woo(&server);
+
+        let checkpoint = OrdersCursor {
+            filter: GetOrder::default(),
+            page: 1,
+            last: Some(LastOrder {
+                order_id: OrderId(4),
+                created_time: DateTime::from_timestamp(1_577_349_004, 0).unwrap(),
+            }),
+        };
+
+        let resumed = order_ids(woo.get_orders_from(checkpoint)).await;
+        assert_eq!(resumed, [OrderId(3)]);
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_time_when_the_checkpoint_moved() {
+        let server = MockServer::start().await;
+        // order 6 arrived since, pushing order 4 off page 2
+        Mock::given(method("GET"))
+            .and(path("/v1/orders"))
+            .and(query_param("page", "2"))
+            .and(query_param_is_missing("end_t"))
+            .respond_with(ok(page(vec![row(3)], 4, 2, 1)))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/orders"))
+            .and(query_param("page", "1"))
+            .and(query_param("end_t", "1577349004000"))
+            .respond_with(ok(page(vec![row(4), row(3)], 2, 2, 1)))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let checkpoint = OrdersCursor {
+            filter: GetOrder::default(),
+            page: 2,
+            last: Some(LastOrder {
+                order_id: OrderId(4),
+                created_time: DateTime::from_timestamp(1_577_349_004, 0).unwrap(),
+            }),
+        };
+        let resumed = order_ids(mock_woo(&server).get_orders_from(checkpoint)).await;
+
+        assert_eq!(resumed, [OrderId(3)]);
+    }
+}
@@ -0,0 +1,335 @@
+use crate::error::WooError;
+use crate::serde_helpers::deserialize_woo_timestamp;
+use crate::woo::Woo;
+use chrono::{DateTime, Utc};
+use futures::stream::{self, Stream};
+use reqwest::Method;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::Duration;
+use tokio::time::Instant;
+
+#[derive(Serialize)]
+struct DepositAddressQuery<'a> {
+    token: &'a str,
+    network: &'a str,
+}
+
+// https://docs.woo.org/#get-token-deposit-address
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DepositAddress {
+    pub address: String,
+    /// Memo or tag, for the networks that need one.
+    #[serde(default)]
+    pub extra: Option<String>,
+}
+
+/// Where a deposit or withdrawal got to.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TransferStatus {
+    New,
+    /// Seen on chain, waiting for `confirming_threshold` confirmations.
+    Confirming,
+    Processing,
+    Completed,
+    Canceled,
+    Failed,
+    #[serde(other)]
+    Unknown,
+}
+
+impl TransferStatus {
+    pub fn is_terminal(self) -> bool {
+        matches!(
+            self,
+            TransferStatus::Completed | TransferStatus::Canceled | TransferStatus::Failed
+        )
+    }
+}
+
+#[derive(Serialize)]
+struct AssetHistoryQuery<'a> {
+    token: &'a str,
+    token_side: &'a str,
+}
+
+// https://docs.woo.org/#get-asset-history
+#[derive(Deserialize, Debug, Clone)]
+pub struct AssetTransfer {
+    pub id: String,
+    pub token: String,
+    pub token_side: String,
+    pub amount: Decimal,
+    pub status: TransferStatus,
+    #[serde(default)]
+    pub tx_id: Option<String>,
+    #[serde(default)]
+    pub confirming_threshold: Option<u32>,
+    #[serde(default)]
+    pub confirmed_number: Option<u32>,
+    #[serde(deserialize_with = "deserialize_woo_timestamp")]
+    pub created_time: DateTime<Utc>,
+    #[serde(deserialize_with = "deserialize_woo_timestamp")]
+    pub updated_time: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Debug)]
+struct AssetHistoryRes {
+    rows: Vec<AssetTransfer>,
+}
+
+struct Watch {
+    deadline: Instant,
+    /// Deposits already completed when the watch started, never the one
+    /// awaited.
+    settled: Option<HashSet<String>>,
+    last: Option<(TransferStatus, Option<u32>)>,
+}
+
+impl Woo {
+    pub async fn get_deposit_address(
+        &self,
+        token: &str,
+        network: &str,
+    ) -> anyhow::Result<DepositAddress> {
+        let query = DepositAddressQuery { token, network };
+        Ok(self
+            .signed_request(Method::GET, "v1/asset/deposit", &query)
+            .await?)
+    }
+
+    /// Follows the deposit of `expected_amount` of `token`, polling the asset
+    /// history every `poll_interval`. Yields the deposit each time its status
+    /// or confirmation count changes and ends once it settled; if it hasn't by
+    /// `deadline` the stream ends with [`WooError::NotFound`].
+    ///
+    /// Deposits of the same amount already completed, failed or cancelled
+    /// when the watch started are ignored, so start it before sending the
+    /// funds or while they confirm.
+    pub fn watch_deposit<'a>(
+        &'a self,
+        token: &'a str,
+        expected_amount: Decimal,
+        poll_interval: Duration,
+        deadline: Duration,
+    ) -> impl Stream<Item = Result<AssetTransfer, WooError>> + 'a {
+        let watch = Watch {
+            deadline: Instant::now() + deadline,
+            settled: None,
+            last: None,
+        };
+
+        stream::try_unfold(Some(watch), move |watch| async move {
+            let Some(mut watch) = watch else {
+                return Ok(None);
+            };
+
+            loop {
+                let deposits = self.deposit_history(token).await?;
+                let settled = watch.settled.get_or_insert_with(|| {
+                    deposits
+                        .iter()
+                        .filter(|deposit| deposit.status.is_terminal())
+                        .map(|deposit| deposit.id.clone())
+                        .collect()
+                });
+
+                // newest first, so the latest matching deposit
+                let deposit = deposits.into_iter().find(|deposit| {
+                    deposit.amount == expected_amount && !settled.contains(&deposit.id)
+                });
+                if let Some(deposit) = deposit {
+                    let seen = (deposit.status, deposit.confirmed_number);
+                    if watch.last != Some(seen) {
+                        watch.last = Some(seen);
+                        let next = (!deposit.status.is_terminal()).then_some(watch);
+                        return Ok(Some((deposit, next)));
+                    }
+                }
+
+                let now = Instant::now();
+                if now >= watch.deadline {
+                    return Err(WooError::NotFound(format!(
+                        "completed deposit of {expected_amount} {token} by the deadline"
+                    )));
+                }
+                tokio::time::sleep(poll_interval.min(watch.deadline - now)).await;
+            }
+        })
+    }
+
+    async fn deposit_history(&self, token: &str) -> Result<Vec<AssetTransfer>, WooError> {
+        let query = AssetHistoryQuery {
+            token,
+            token_side: "DEPOSIT",
+        };
+        let res: AssetHistoryRes = self
+            .signed_request(Method::GET, "v1/asset/history", &query)
+            .await?;
+
+        Ok(res.rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mock_woo;
+    use futures::TryStreamExt;
+    use rust_decimal_macros::dec;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn deposit(id: &str, amount: u32, status: &str, confirmed: u32) -> serde_json::Value {
+        serde_json::json!({
+            "id": id, "token": "USDT", "token_side": "DEPOSIT", "type": "BALANCE",
+            "amount": amount, "status": status, "tx_id": "0x8a74", "fee_token": null,
+            "fee_amount": null, "confirming_threshold": 12, "confirmed_number": confirmed,
+            "created_time": "1579399877.041", "updated_time": "1579399877.041",
+        })
+    }
+
+    fn history(rows: Vec<serde_json::Value>) -> ResponseTemplate {
+        ResponseTemplate::new(200)
+            .set_body_json(serde_json::json!({ "success": true, "rows": rows }))
+    }
+
+    #[tokio::test]
+    async fn reads_the_deposit_address_of_a_network() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/asset/deposit"))
+            .and(query_param("token", "USDT"))
+            .and(query_param("network", "ETH"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true, "address": "0x31d64B3230f8baDD91dE1710A65DF536aF8f7cDa",
+                "extra": "",
+            })))
+            .mount(&server)
+            .await;
+
+        let address = mock_woo(&server)
+            .get_deposit_address("USDT", "ETH")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            address.address,
+            "0x31d64B3230f8baDD91dE1710A65DF536aF8f7cDa"
+        );
+    }
+
+    #[tokio::test]
+    async fn follows_the_deposit_until_it_completes() {
+        let server = MockServer::start().await;
+        // an older deposit of the same amount, already credited
+        let old = deposit("1", 100, "COMPLETED", 12);
+        let polls = [
+            vec![old.clone()],
+            vec![deposit("2", 100, "CONFIRMING", 3), old.clone()],
+            vec![deposit("2", 100, "CONFIRMING", 3), old.clone()],
+            vec![deposit("2", 100, "CONFIRMING", 9), old.clone()],
+            vec![deposit("2", 100, "COMPLETED", 12), old],
+        ];
+        for rows in polls {
+            Mock::given(method("GET"))
+                .and(path("/v1/asset/history"))
+                .and(query_param("token_side", "DEPOSIT"))
+                .respond_with(history(rows))
+                .up_to_n_times(1)
+                .mount(&server)
+                .await;
+        }
+        let woo = mock_woo(&server);
+
+        let updates: Vec<_> = woo
+            .watch_deposit(
+                "USDT",
+                dec!(100),
+                Duration::from_millis(1),
+                Duration::from_secs(5),
+            )
+            .try_collect()
+            .await
+            .unwrap();
+
+        let seen: Vec<_> = updates
+            .iter()
+            .map(|deposit| {
+                (
+                    deposit.id.as_str(),
+                    deposit.status,
+                    deposit.confirmed_number,
+                )
+            })
+            .collect();
+        assert_eq!(
+            seen,
+            [
+                ("2", TransferStatus::Confirming, Some(3)),
+                ("2", TransferStatus::Confirming, Some(9)),
+                ("2", TransferStatus::Completed, Some(12)),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn ignores_a_stale_failed_deposit_of_the_same_amount() {
+        let server = MockServer::start().await;
+        let failed = deposit("1", 100, "FAILED", 0);
+        let polls = [
+            vec![failed.clone()],
+            vec![deposit("2", 100, "COMPLETED", 12), failed],
+        ];
+        for rows in polls {
+            Mock::given(method("GET"))
+                .and(path("/v1/asset/history"))
+                .respond_with(history(rows))
+                .up_to_n_times(1)
+                .mount(&server)
+                .await;
+        }
+        let woo = mock_woo(&server);
+
+        let updates: Vec<_> = woo
+            .watch_deposit(
+                "USDT",
+                dec!(100),
+                Duration::from_millis(1),
+                Duration::from_secs(5),
+            )
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].id, "2");
+        assert_eq!(updates[0].status, TransferStatus::Completed);
+    }
+
+    #[tokio::test]
+    async fn gives_up_at_the_deadline() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/asset/history"))
+            .respond_with(history(vec![deposit("2", 50, "CONFIRMING", 3)]))
+            .mount(&server)
+            .await;
+        let woo = mock_woo(&server);
+
+        let err = woo
+            .watch_deposit(
+                "USDT",
+                dec!(100),
+                Duration::from_millis(5),
+                Duration::from_millis(20),
+            )
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, WooError::NotFound(_)));
+    }
+}
@@ -0,0 +1,397 @@
+use crate::error::{WooError, WooErrorCode};
+use crate::woo::Woo;
+use crate::woo_data_structs::{
+    CancelOrder, CancelOrderByClientId, OrderSize, SendOrderRes, WooEnvelope, WooOrder,
+};
+use chrono::{DateTime, Utc};
+use reqwest::Method;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+/// Symbol [`run_smoke_test`] trades on.
+pub const SMOKE_TEST_SYMBOL: &str = "SPOT_BTC_USDT";
+/// Tag on the smoke test order, to tell it apart from a bot's.
+pub const SMOKE_TEST_ORDER_TAG: &str = "smoke-test";
+
+/// Skew beyond which signed requests start getting rejected.
+pub(crate) const MAX_CLOCK_SKEW: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SmokeStep {
+    SystemStatus,
+    ClockSkew,
+    SignedRead,
+    PlaceOrder,
+    ReadOrder,
+    CancelOrder,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum StepOutcome {
+    Passed,
+    Failed {
+        error: String,
+    },
+    /// Not run because a step it depends on failed.
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StepReport {
+    pub step: SmokeStep,
+    pub outcome: StepOutcome,
+    /// `None` for skipped steps.
+    pub latency_ms: Option<u64>,
+}
+
+/// What [`run_smoke_test`] found, serializable as a CI artifact.
+#[derive(Debug, Clone, Serialize)]
+pub struct SmokeReport {
+    pub symbol: String,
+    pub started_at: DateTime<Utc>,
+    /// Server minus local clock, `None` if it couldn't be measured.
+    pub clock_skew_ms: Option<i64>,
+    pub steps: Vec<StepReport>,
+}
+
+impl SmokeReport {
+    pub fn passed(&self) -> bool {
+        self.steps
+            .iter()
+            .all(|step| step.outcome == StepOutcome::Passed)
+    }
+}
+
+#[derive(Deserialize)]
+struct SystemStatus {
+    status: i64,
+    msg: String,
+}
+
+/// [`run_smoke_test_on`] [`SMOKE_TEST_SYMBOL`].
+pub async fn run_smoke_test(woo: &Woo) -> SmokeReport {
+    run_smoke_test_on(woo, SMOKE_TEST_SYMBOL).await
+}
+
+/// Walks the critical path a bot depends on, meant for staging before
+/// switching to production keys: system status, clock skew, a signed read of
+/// the balances, then placing a minimum size `LIMIT` buy at half the best bid,
+/// reading it back and cancelling it.
+///
+/// Every step is timed and reported rather than stopping the run. The order
+/// is cancelled whatever happened after it was placed, and if placing it
+/// failed in transit, it is cancelled by client order id in case it landed.
+pub async fn run_smoke_test_on(woo: &Woo, symbol: &str) -> SmokeReport {
+    let mut steps = Vec::new();
+    let started_at = Utc::now();
+
+    record(&mut steps, SmokeStep::SystemStatus, async {
+        let status: SystemStatus = system_info(woo).await?.into_data()?;
+        match status.status {
+            0 => Ok(()),
+            code => Err(format!("system status {code}: {}", status.msg).into()),
+        }
+    })
+    .await;
+
+    let mut clock_skew_ms = None;
+    record(&mut steps, SmokeStep::ClockSkew, async {
+        let skew_ms = clock_skew_ms_of(woo)
+            .await
+            .map_err(|err| StepError(err.to_string()))?;
+        clock_skew_ms = Some(skew_ms);
+
+        if skew_ms.unsigned_abs() > MAX_CLOCK_SKEW.as_millis() as u64 {
+            return Err(format!("local clock is {skew_ms}ms off the server").into());
+        }
+        Ok(())
+    })
+    .await;
+
+    record(&mut steps, SmokeStep::SignedRead, async {
+        woo.get_holding()
+            .await
+            .map_err(|err| StepError(err.to_string()))
+    })
+    .await;
+
+    let client_order_id = woo.next_client_order_id();
+    let mut maybe_sent = false;
+    let placed = record(&mut steps, SmokeStep::PlaceOrder, async {
+        let order = far_from_market_buy(woo, symbol).await?;
+        let order = WooOrder {
+            client_order_id: Some(client_order_id.clone()),
+            order_tag: Some(SMOKE_TEST_ORDER_TAG.to_string()),
+            ..order
+        };
+
+        woo.create_order(order).await.map_err(|err| {
+            // the order may have reached the exchange without an answer
+            // making it back
+            maybe_sent = matches!(err, WooError::Transport { .. } | WooError::Decode { .. });
+            err.into()
+        })
+    })
+    .await;
+
+    match placed {
+        Some(SendOrderRes { order_id, .. }) => {
+            record(&mut steps, SmokeStep::ReadOrder, async {
+                Ok(woo.order_detail(order_id).await?)
+            })
+            .await;
+
+            let cancel = CancelOrder {
+                order_id,
+                symbol: symbol.to_string(),
+            };
+            record(&mut steps, SmokeStep::CancelOrder, async {
+                Ok(woo.cancel_order(cancel).await?)
+            })
+            .await;
+        }
+        None if maybe_sent => {
+            skip(&mut steps, SmokeStep::ReadOrder);
+
+            let cancel = CancelOrderByClientId {
+                client_order_id,
+                symbol: symbol.to_string(),
+            };
+            record(&mut steps, SmokeStep::CancelOrder, async {
+                match woo.cancel_order_by_client_id(cancel).await {
+                    Err(WooError::OrderRejected(err))
+                        if err.error_code() == WooErrorCode::ResourceNotFound =>
+                    {
+                        // it never landed, nothing to clean up
+                        Ok(())
+                    }
+                    res => Ok(res.map(drop)?),
+                }
+            })
+            .await;
+        }
+        None => {
+            skip(&mut steps, SmokeStep::ReadOrder);
+            skip(&mut steps, SmokeStep::CancelOrder);
+        }
+    }
+
+    SmokeReport {
+        symbol: symbol.to_string(),
+        started_at,
+        clock_skew_ms,
+        steps,
+    }
+}
+
+/// Server minus local clock, taking the server time as of the middle of the
+/// round trip.
+pub(crate) async fn clock_skew_ms_of(woo: &Woo) -> anyhow::Result<i64> {
+    let sent = Utc::now();
+    let server_time = woo.server_time().await?;
+    let received = Utc::now();
+
+    Ok((server_time - (sent + (received - sent) / 2)).num_milliseconds())
+}
+
+async fn system_info(woo: &Woo) -> Result<WooEnvelope<SystemStatus>, WooError> {
+    woo.public_request(Method::GET, "v1/public/system_info", ())
+        .await
+}
+
+/// The smallest order the symbol's filters allow, priced at half the best bid
+/// so it can't fill.
+async fn far_from_market_buy(woo: &Woo, symbol: &str) -> Result<WooOrder, StepError> {
+    let info = woo.symbol_info(symbol).await?;
+    let book = woo.orderbook(symbol, Some(1)).await?;
+    let best_bid = book
+        .best_bid()
+        .ok_or_else(|| format!("{symbol} has no bids"))?
+        .price;
+
+    let price = info.snap_price(best_bid / Decimal::TWO).max(info.quote_min);
+    if price.is_zero() {
+        return Err(format!("best bid {best_bid} is too low to undercut").into());
+    }
+    let lots = (info.min_notional / price / info.base_tick).ceil();
+    let quantity = (lots * info.base_tick).max(info.base_min);
+
+    let order = WooOrder::builder(symbol, "BUY", "LIMIT")
+        .price(price)
+        .size(OrderSize::Quantity(quantity))
+        .build();
+    info.validate(&order)?;
+
+    Ok(order)
+}
+
+async fn record<T>(
+    steps: &mut Vec<StepReport>,
+    step: SmokeStep,
+    run: impl Future<Output = Result<T, StepError>>,
+) -> Option<T> {
+    let started = Instant::now();
+    let res = run.await;
+    let latency_ms = Some(started.elapsed().as_millis() as u64);
+
+    let (outcome, value) = match res {
+        Ok(value) => (StepOutcome::Passed, Some(value)),
+        Err(StepError(error)) => {
+            tracing::warn!(?step, %error, "smoke test step failed");
+            (StepOutcome::Failed { error }, None)
+        }
+    };
+    steps.push(StepReport {
+        step,
+        outcome,
+        latency_ms,
+    });
+
+    value
+}
+
+fn skip(steps: &mut Vec<StepReport>, step: SmokeStep) {
+    steps.push(StepReport {
+        step,
+        outcome: StepOutcome::Skipped,
+        latency_ms: None,
+    });
+}
+
+/// Why a step failed, as it ends up in the report.
+struct StepError(String);
+
+impl From<WooError> for StepError {
+    fn from(err: WooError) -> Self {
+        StepError(err.to_string())
+    }
+}
+
+impl From<String> for StepError {
+    fn from(error: String) -> Self {
+        StepError(error)
+    }
+}
+
+impl From<&str> for StepError {
+    fn from(error: &str) -> Self {
+        StepError(error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{exchange_info, mock_woo, mount};
+    use wiremock::matchers::{body_string_contains, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn staging(order_read_back: ResponseTemplate) -> MockServer {
+        let server = MockServer::start().await;
+        mount(
+            &server,
+            "GET",
+            "/v1/public/system_info",
+            serde_json::json!({
+                "success": true,
+                "data": { "status": 0, "msg": "System is functioning properly." },
+                "timestamp": Utc::now().timestamp_millis(),
+            }),
+        )
+        .await;
+        mount(
+            &server,
+            "GET",
+            "/v1/client/holding",
+            serde_json::json!({ "success": true, "holding": { "USDT": 1000.0 } }),
+        )
+        .await;
+        mount(&server, "GET", "/v1/public/info", exchange_info()).await;
+        mount(
+            &server,
+            "GET",
+            "/v1/public/orderbook/SPOT_BTC_USDT",
+            serde_json::json!({
+                "success": true, "timestamp": 1578565539808_i64,
+                "asks": [{ "price": 9000.5, "quantity": 1 }],
+                "bids": [{ "price": 8999, "quantity": 1 }],
+            }),
+        )
+        .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/order"))
+            .and(body_string_contains(
+                "order_price=4499.5&order_quantity=0.0023&order_tag=smoke-test&order_type=LIMIT",
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true, "timestamp": "1578565539.808", "order_id": 9,
+                "order_type": "LIMIT", "client_order_id": 1,
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/order/9"))
+            .respond_with(order_read_back)
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/v1/order"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "success": true, "status": "CANCEL_SENT" })),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        server
+    }
+
+    #[tokio::test]
+    async fn passes_the_whole_critical_path() {
+        let server = staging(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "success": true, "created_time": "1577349119.33", "side": "BUY", "status": "NEW",
+            "symbol": "SPOT_BTC_USDT", "client_order_id": 1, "order_id": 9,
+            "order_tag": "smoke-test", "type": "LIMIT", "price": 4499.5, "quantity": 0.0023,
+            "amount": null, "executed": 0, "total_fee": 0, "fee_asset": null,
+            "average_executed_price": null,
+        })))
+        .await;
+
+        let report = run_smoke_test(&mock_woo(&server)).await;
+
+        assert!(report.passed(), "{report:#?}");
+        assert!(report.clock_skew_ms.unwrap().abs() < 5_000);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["steps"].as_array().unwrap().len(), 6);
+        assert_eq!(json["steps"][3]["step"], "place_order");
+        assert_eq!(json["steps"][3]["outcome"]["result"], "passed");
+    }
+
+    #[tokio::test]
+    async fn cancels_the_order_when_reading_it_back_fails() {
+        let server = staging(ResponseTemplate::new(502).set_body_string("Bad Gateway")).await;
+
+        let report = run_smoke_test(&mock_woo(&server)).await;
+
+        assert!(!report.passed());
+        let outcome = |step| {
+            &report
+                .steps
+                .iter()
+                .find(|report| report.step == step)
+                .unwrap()
+                .outcome
+        };
+        assert!(matches!(
+            outcome(SmokeStep::ReadOrder),
+            StepOutcome::Failed { .. }
+        ));
+        assert_eq!(outcome(SmokeStep::CancelOrder), &StepOutcome::Passed);
+    }
+}
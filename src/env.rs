@@ -0,0 +1,181 @@
+use crate::builder::Credentials;
+use crate::error::WooError;
+use crate::proxy::{ProxyAuth, ProxyConfig};
+use crate::woo::Environment;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use url::Url;
+
+static DOTENV: OnceLock<()> = OnceLock::new();
+
+/// Loads `.env` into the process environment, once per process no matter how
+/// many clients get constructed.
+pub fn load_dotenv() {
+    DOTENV.get_or_init(|| {
+        dotenv::dotenv().ok();
+    });
+}
+
+pub(crate) fn var(name: &str) -> Result<String, WooError> {
+    load_dotenv();
+
+    std::env::var(name)
+        .map_err(|_| WooError::Config(format!("environment variable {name} is not set")))
+}
+
+/// Optional variant of [`var`], for settings that may be left out.
+fn optional_var(name: &str) -> Option<String> {
+    var(name).ok().filter(|value| !value.is_empty())
+}
+
+/// The http proxy configured through `PROXY_URL`, authenticated with
+/// `PROXY_USERNAME`/`PROXY_PASSWORD` when those are set. `None` if
+/// `PROXY_URL` isn't set, meaning requests go out directly.
+pub fn proxy_from_env() -> Result<Option<ProxyConfig>, WooError> {
+    let Some(url) = optional_var("PROXY_URL") else {
+        return Ok(None);
+    };
+    let url = Url::parse(&url)
+        .map_err(|err| WooError::Config(format!("PROXY_URL {url:?} is not a valid url: {err}")))?;
+
+    let basic_auth = match (
+        optional_var("PROXY_USERNAME"),
+        optional_var("PROXY_PASSWORD"),
+    ) {
+        (Some(username), Some(password)) => Some(ProxyAuth::new(username, password)),
+        (None, None) => None,
+        _ => {
+            return Err(WooError::Config(
+                "PROXY_USERNAME and PROXY_PASSWORD must be set together".to_string(),
+            ))
+        }
+    };
+
+    Ok(Some(ProxyConfig::Http { url, basic_auth }))
+}
+
+impl Environment {
+    /// Reads `WOO_ENV` (`production` or `staging`, case-insensitive), so the
+    /// target exchange is a deployment setting rather than a code change.
+    pub fn from_env() -> Result<Self, WooError> {
+        var("WOO_ENV")?.parse()
+    }
+}
+
+impl FromStr for Environment {
+    type Err = WooError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "production" | "prod" => Ok(Environment::Production),
+            "staging" => Ok(Environment::Staging),
+            _ => Err(WooError::Config(format!(
+                "unknown environment {s:?}, expected production or staging"
+            ))),
+        }
+    }
+}
+
+/// Names of the environment variables holding the api key and secret.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CredentialSource {
+    pub api_key_var: String,
+    pub api_secret_var: String,
+}
+
+impl CredentialSource {
+    pub fn new(api_key_var: impl Into<String>, api_secret_var: impl Into<String>) -> Self {
+        Self {
+            api_key_var: api_key_var.into(),
+            api_secret_var: api_secret_var.into(),
+        }
+    }
+
+    /// The crate's default names: `WOO_API_KEY`/`WOO_API_SECRET`, with a
+    /// `_STAGING` suffix for staging.
+    pub fn for_environment(environment: Environment) -> Self {
+        match environment {
+            Environment::Production => CredentialSource::new("WOO_API_KEY", "WOO_API_SECRET"),
+            Environment::Staging => {
+                CredentialSource::new("WOO_API_KEY_STAGING", "WOO_API_SECRET_STAGING")
+            }
+        }
+    }
+
+    pub fn load(&self) -> Result<Credentials, WooError> {
+        Ok(Credentials::new(
+            var(&self.api_key_var)?,
+            var(&self.api_secret_var)?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secrecy::ExposeSecret;
+
+    #[test]
+    fn parses_environment_names() {
+        assert_eq!(
+            "production".parse::<Environment>().unwrap(),
+            Environment::Production
+        );
+        assert_eq!(
+            " Staging ".parse::<Environment>().unwrap(),
+            Environment::Staging
+        );
+        assert!("mainnet".parse::<Environment>().is_err());
+    }
+
+    #[test]
+    fn credential_source_reads_custom_names() {
+        std::env::set_var("CREDENTIAL_SOURCE_TEST_KEY", "custom-key");
+        std::env::set_var("CREDENTIAL_SOURCE_TEST_SECRET", "custom-secret");
+
+        let credentials = CredentialSource::new(
+            "CREDENTIAL_SOURCE_TEST_KEY",
+            "CREDENTIAL_SOURCE_TEST_SECRET",
+        )
+        .load()
+        .unwrap();
+
+        assert_eq!(credentials.api_key, "custom-key");
+        assert_eq!(credentials.api_secret.expose_secret(), "custom-secret");
+    }
+
+    #[test]
+    fn credential_source_names_missing_variable() {
+        let err = CredentialSource::new("CREDENTIAL_SOURCE_TEST_MISSING", "ALSO_MISSING")
+            .load()
+            .unwrap_err();
+
+        assert!(err
+            .to_string()
+            .contains("CREDENTIAL_SOURCE_TEST_MISSING is not set"));
+    }
+
+    #[test]
+    fn proxy_is_optional_and_needs_both_credentials() {
+        std::env::remove_var("PROXY_URL");
+        assert!(proxy_from_env().unwrap().is_none());
+
+        std::env::set_var("PROXY_URL", "http://proxy.example:8080");
+        std::env::set_var("PROXY_USERNAME", "user");
+        std::env::remove_var("PROXY_PASSWORD");
+        assert!(proxy_from_env().is_err());
+
+        std::env::set_var("PROXY_PASSWORD", "pass");
+        match proxy_from_env().unwrap() {
+            Some(ProxyConfig::Http { url, basic_auth }) => {
+                assert_eq!(url.as_str(), "http://proxy.example:8080/");
+                assert_eq!(basic_auth.unwrap().username, "user");
+            }
+            other => panic!("unexpected proxy {other:?}"),
+        }
+
+        for name in ["PROXY_URL", "PROXY_USERNAME", "PROXY_PASSWORD"] {
+            std::env::remove_var(name);
+        }
+    }
+}
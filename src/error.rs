@@ -0,0 +1,338 @@
+use crate::account_mode::AccountMode;
+use crate::constants::ERROR_CODES;
+use crate::risk::RiskRule;
+use crate::woo_data_structs::{ClientOrderId, OrderDetail, OrderId, OrderStatus};
+use std::fmt;
+use uuid::Uuid;
+
+#[derive(Debug, thiserror::Error)]
+pub enum WooError {
+    #[error("http transport error: {source}")]
+    Transport {
+        source: reqwest::Error,
+        /// Correlation id of the failed request, `None` if the error happened
+        /// before a request existed (e.g. while building the client).
+        request_id: Option<Uuid>,
+    },
+    #[error("failed to encode request parameters: {0}")]
+    Encode(#[from] serde_qs::Error),
+    #[error(
+        "failed to decode response{}: {source} (body: {body})",
+        path.as_deref().map(|path| format!(" at `{path}`")).unwrap_or_default()
+    )]
+    Decode {
+        source: serde_json::Error,
+        /// JSON path of the offending value, with the `diagnostics` feature.
+        path: Option<String>,
+        body: String,
+        request_id: Option<Uuid>,
+    },
+    #[error(transparent)]
+    Api(#[from] WooApiError),
+    /// The exchange refused to place or cancel an order, e.g. for failing a
+    /// price filter or lacking balance.
+    #[error("order rejected: {0}")]
+    OrderRejected(WooApiError),
+    /// [`Woo::cancel_and_replace`](crate::woo::Woo::cancel_and_replace)
+    /// cancelled `order_id` but could not place its replacement, so nothing is
+    /// quoted any more.
+    #[error("order {order_id} was cancelled but its replacement failed: {source}")]
+    ReplacementFailed {
+        order_id: OrderId,
+        source: Box<WooError>,
+    },
+    /// [`Woo::cancel_and_replace`](crate::woo::Woo::cancel_and_replace)
+    /// found `order_id` already filled or cancelled, nothing was sent.
+    #[error("order {order_id} is already {status:?}")]
+    OrderTerminal {
+        order_id: OrderId,
+        status: OrderStatus,
+    },
+    /// An order was sent but the response was lost, and looking it up by its
+    /// `client_order_id` failed as well, so it may or may not be on the book.
+    /// Look it up again before sending it anew.
+    #[error("order {client_order_id} may or may not have been placed: {source}")]
+    OrderStateUnknown {
+        client_order_id: ClientOrderId,
+        source: Box<WooError>,
+    },
+    /// A `POST_ONLY` order refused because it would have taken liquidity.
+    #[error("post only order would have crossed the book: {0}")]
+    PostOnlyRejected(WooApiError),
+    #[cfg(feature = "websocket")]
+    #[error("websocket error: {0}")]
+    WebSocket(Box<tokio_tungstenite::tungstenite::Error>),
+    #[error("invalid configuration: {0}")]
+    Config(String),
+    #[error("not found: {0}")]
+    NotFound(String),
+    #[error("invalid symbol: {0:?}")]
+    InvalidSymbol(String),
+    #[error("invalid order: {0}")]
+    InvalidOrder(String),
+    /// A request would have been signed with a timestamp before the epoch,
+    /// in milliseconds, e.g. from a badly set clock.
+    #[error("cannot sign with timestamp {0}ms, it is before the epoch")]
+    InvalidTimestamp(i64),
+    #[error("request needs a {required} account, this one is in {actual} mode")]
+    WrongAccountMode {
+        required: AccountMode,
+        actual: AccountMode,
+    },
+    /// [`Woo::shutdown`](crate::woo::Woo::shutdown) has begun on this client
+    /// or a clone of it.
+    #[error("client is shutting down, no new orders are accepted")]
+    ShuttingDown,
+    #[error("order rejected by the {rule} risk check: {detail}")]
+    RiskRejected { rule: RiskRule, detail: String },
+    /// [`Woo::withdraw`](crate::woo::Woo::withdraw) refused by the withdraw
+    /// policy, before anything was signed.
+    #[error("withdrawal blocked: {0}")]
+    WithdrawBlocked(String),
+    /// The [`Signer`](crate::signer::Signer) failed, nothing was sent.
+    #[error("failed to sign request: {0}")]
+    Signing(#[source] Box<dyn std::error::Error + Send + Sync>),
+    /// The client records under [`RecordMode::DryRun`](crate::recording::RecordMode),
+    /// the request was written to the recording and never sent.
+    #[error("dry run, request {0} was recorded instead of sent")]
+    DryRun(Uuid),
+}
+
+impl WooError {
+    /// The `x-request-id` the failed request was sent with, to quote when
+    /// raising a ticket with the exchange.
+    pub fn request_id(&self) -> Option<Uuid> {
+        match self {
+            WooError::Transport { request_id, .. } | WooError::Decode { request_id, .. } => {
+                *request_id
+            }
+            WooError::Api(err) | WooError::OrderRejected(err) | WooError::PostOnlyRejected(err) => {
+                err.request_id
+            }
+            WooError::ReplacementFailed { source, .. }
+            | WooError::OrderStateUnknown { source, .. } => source.request_id(),
+            WooError::DryRun(request_id) => Some(*request_id),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for WooError {
+    fn from(source: reqwest::Error) -> Self {
+        WooError::Transport {
+            source,
+            request_id: None,
+        }
+    }
+}
+
+#[cfg(feature = "websocket")]
+impl From<tokio_tungstenite::tungstenite::Error> for WooError {
+    fn from(source: tokio_tungstenite::tungstenite::Error) -> Self {
+        WooError::WebSocket(Box::new(source))
+    }
+}
+
+/// The `{"success": false, "code": ..., "message": ...}` body WOO returns
+/// when it rejects a request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WooApiError {
+    pub code: i64,
+    pub message: String,
+    pub request_id: Option<Uuid>,
+}
+
+impl WooApiError {
+    /// WOO's "the data is not found" code.
+    pub const RESOURCE_NOT_FOUND: i64 = -1006;
+
+    pub fn error_code(&self) -> WooErrorCode {
+        WooErrorCode::from(self.code)
+    }
+
+    /// Whether a `POST_ONLY` order was refused because it would have taken
+    /// liquidity. WOO has no dedicated code for this, only the message says so.
+    pub fn is_post_only_rejection(&self) -> bool {
+        let message = self.message.to_ascii_lowercase();
+        ["post only", "post_only", "post-only", "take liquidity"]
+            .iter()
+            .any(|needle| message.contains(needle))
+    }
+}
+
+/// The error codes documented at <https://docs.woo.org/#error-codes>.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WooErrorCode {
+    /// -1000, an unknown error occurred while processing the request.
+    UnknownError,
+    /// -1001, the api key or secret is in the wrong format.
+    InvalidSignature,
+    /// -1002, the api key or secret is invalid, or lacks the permission.
+    Unauthorized,
+    /// -1003, rate limit exceeded.
+    TooManyRequests,
+    /// -1004, an unknown parameter was sent.
+    UnknownParam,
+    /// -1005, some parameters are in the wrong format.
+    InvalidParam,
+    /// -1006, the data is not found in the server.
+    ResourceNotFound,
+    /// -1007, the data already exists or the request was already sent.
+    DuplicateRequest,
+    /// -1008, the quantity of settlement is higher than it can request.
+    QuantityTooHigh,
+    /// -1009, can not request withdrawal settlement.
+    CanNotWithdraw,
+    /// -1011, can not place or cancel orders, the internal service may be
+    /// under maintenance.
+    RpcNotConnected,
+    /// -1012, the place or cancel order request was rejected internally.
+    RpcRejected,
+    /// -1101, the risk exposure for the client is too high, e.g. not enough
+    /// margin for the order.
+    RiskTooHigh,
+    /// -1102, the order value (price * size) is too small.
+    MinNotional,
+    /// -1103, the order price is not within the price filter.
+    PriceFilter,
+    /// -1104, the order quantity is not within the size filter.
+    SizeFilter,
+    /// -1105, the price is more than the allowed percentage away from the
+    /// mid price.
+    PercentageFilter,
+    /// A code this crate doesn't know about yet.
+    Unknown(i64),
+}
+
+impl From<i64> for WooErrorCode {
+    fn from(code: i64) -> Self {
+        match code {
+            -1000 => WooErrorCode::UnknownError,
+            -1001 => WooErrorCode::InvalidSignature,
+            -1002 => WooErrorCode::Unauthorized,
+            -1003 => WooErrorCode::TooManyRequests,
+            -1004 => WooErrorCode::UnknownParam,
+            -1005 => WooErrorCode::InvalidParam,
+            -1006 => WooErrorCode::ResourceNotFound,
+            -1007 => WooErrorCode::DuplicateRequest,
+            -1008 => WooErrorCode::QuantityTooHigh,
+            -1009 => WooErrorCode::CanNotWithdraw,
+            -1011 => WooErrorCode::RpcNotConnected,
+            -1012 => WooErrorCode::RpcRejected,
+            -1101 => WooErrorCode::RiskTooHigh,
+            -1102 => WooErrorCode::MinNotional,
+            -1103 => WooErrorCode::PriceFilter,
+            -1104 => WooErrorCode::SizeFilter,
+            -1105 => WooErrorCode::PercentageFilter,
+            code => WooErrorCode::Unknown(code),
+        }
+    }
+}
+
+impl WooErrorCode {
+    /// The raw code WOO sends.
+    pub fn code(&self) -> i64 {
+        match self {
+            WooErrorCode::UnknownError => -1000,
+            WooErrorCode::InvalidSignature => -1001,
+            WooErrorCode::Unauthorized => -1002,
+            WooErrorCode::TooManyRequests => -1003,
+            WooErrorCode::UnknownParam => -1004,
+            WooErrorCode::InvalidParam => -1005,
+            WooErrorCode::ResourceNotFound => -1006,
+            WooErrorCode::DuplicateRequest => -1007,
+            WooErrorCode::QuantityTooHigh => -1008,
+            WooErrorCode::CanNotWithdraw => -1009,
+            WooErrorCode::RpcNotConnected => -1011,
+            WooErrorCode::RpcRejected => -1012,
+            WooErrorCode::RiskTooHigh => -1101,
+            WooErrorCode::MinNotional => -1102,
+            WooErrorCode::PriceFilter => -1103,
+            WooErrorCode::SizeFilter => -1104,
+            WooErrorCode::PercentageFilter => -1105,
+            WooErrorCode::Unknown(code) => *code,
+        }
+    }
+
+    /// The documented explanation of the code, `None` for unknown codes.
+    pub fn description(&self) -> Option<&'static str> {
+        let code = self.code();
+        ERROR_CODES
+            .iter()
+            .find(|(known, _)| *known == code)
+            .map(|(_, message)| *message)
+    }
+}
+
+impl fmt::Display for WooApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "woo api error {}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for WooApiError {}
+
+/// Why [`Woo::wait_for_terminal_state`](crate::woo::Woo::wait_for_terminal_state)
+/// gave up.
+#[derive(Debug, thiserror::Error)]
+pub enum WaitError {
+    #[error("order {} still {:?} at the deadline", .last.order_id, .last.status)]
+    TimedOut {
+        /// Last state seen, re-read after the cancel if one was sent.
+        last: Box<OrderDetail>,
+        cancel_sent: bool,
+    },
+    #[error(transparent)]
+    Woo(#[from] WooError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the websocket types, and tokio-tungstenite with them, only exist with
+    // the `websocket` feature
+    #[cfg(feature = "websocket")]
+    mod websocket {
+        use super::*;
+        use crate::ws::WsConfig;
+        use tokio_tungstenite::tungstenite;
+
+        #[test]
+        fn websocket_types_come_with_the_feature() {
+            let err = WooError::from(tungstenite::Error::ConnectionClosed);
+
+            assert!(matches!(err, WooError::WebSocket(_)));
+            let _ = WsConfig::new(crate::woo::Environment::Staging, "app");
+        }
+    }
+
+    #[test]
+    fn maps_raw_codes_to_variants() {
+        let api_error = |code| WooApiError {
+            code,
+            message: String::new(),
+            request_id: None,
+        };
+
+        assert_eq!(api_error(-1003).error_code(), WooErrorCode::TooManyRequests);
+        assert_eq!(
+            api_error(-1006).error_code(),
+            WooErrorCode::ResourceNotFound
+        );
+        assert_eq!(api_error(-1101).error_code(), WooErrorCode::RiskTooHigh);
+        assert_eq!(api_error(-1103).error_code(), WooErrorCode::PriceFilter);
+        assert_eq!(api_error(-1010).error_code(), WooErrorCode::Unknown(-1010));
+    }
+
+    #[test]
+    fn every_documented_code_has_a_variant() {
+        for &(code, message) in ERROR_CODES {
+            let error_code = WooErrorCode::from(code);
+            assert!(!matches!(error_code, WooErrorCode::Unknown(_)), "{code}");
+            assert_eq!(error_code.code(), code);
+            assert_eq!(error_code.description(), Some(message));
+        }
+        assert_eq!(WooErrorCode::Unknown(-1010).description(), None);
+    }
+}
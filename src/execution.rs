@@ -0,0 +1,741 @@
+use crate::error::{WaitError, WooError};
+use crate::wait::CancelOnTimeout;
+use crate::woo::Woo;
+use crate::woo_data_structs::{
+    CancelOrder, ClientOrderId, OrderDetail, OrderId, OrderSize, OrderStatus, WooOrder,
+};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::{self, Instant};
+
+const FILL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+const FILL_DEADLINE: Duration = Duration::from_secs(5);
+const LOOKUP_ATTEMPTS: u32 = 3;
+const LOOKUP_BACKOFF: Duration = Duration::from_millis(200);
+
+/// What a slice does when its order can't be placed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlicePolicy {
+    /// Tries again up to `attempts` more times, then skips the slice.
+    Retry { attempts: u32 },
+    /// Leaves the slice's quantity to the following slices.
+    Skip,
+}
+
+#[derive(Debug, Clone)]
+pub enum SliceOutcome {
+    Executed {
+        order_id: OrderId,
+        executed: Decimal,
+        average_price: Option<Decimal>,
+    },
+    Failed(String),
+    /// The execution was cancelled before this slice was due.
+    Cancelled,
+}
+
+#[derive(Debug, Clone)]
+pub struct SliceReport {
+    pub index: u32,
+    pub client_order_id: Option<ClientOrderId>,
+    pub quantity: Decimal,
+    pub outcome: SliceOutcome,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionReport {
+    pub filled_quantity: Decimal,
+    /// Volume-weighted over every slice, `None` if nothing filled.
+    pub average_price: Option<Decimal>,
+    pub fees: HashMap<String, Decimal>,
+    pub slices: Vec<SliceReport>,
+    pub cancelled: bool,
+}
+
+/// Splits `total_quantity` into `slice_count` orders spread evenly over
+/// `duration`.
+///
+/// Each slice is sized from what is still unfilled, rounded down to whole
+/// lots, so underfilled slices are made up later and the total is never
+/// exceeded. A slice whose submission fails without a clear answer from the
+/// exchange is looked up by its `client_order_id` before anything is resent,
+/// and given up on if the lookup fails too.
+#[derive(Debug, Clone)]
+pub struct Twap {
+    woo: Woo,
+    symbol: String,
+    side: String,
+    total_quantity: Decimal,
+    duration: Duration,
+    slice_count: u32,
+    limit_price: Option<Decimal>,
+    policy: SlicePolicy,
+}
+
+impl Twap {
+    pub fn new(
+        woo: Woo,
+        symbol: impl Into<String>,
+        side: impl Into<String>,
+        total_quantity: Decimal,
+        duration: Duration,
+        slice_count: u32,
+    ) -> Self {
+        Self {
+            woo,
+            symbol: symbol.into(),
+            side: side.into(),
+            total_quantity,
+            duration,
+            slice_count: slice_count.max(1),
+            limit_price: None,
+            policy: SlicePolicy::Retry { attempts: 1 },
+        }
+    }
+
+    /// Sends slices as IOC limit orders at `price` instead of market orders,
+    /// so no fill is worse than it.
+    pub fn limit_price(mut self, price: Decimal) -> Self {
+        self.limit_price = Some(price);
+        self
+    }
+
+    pub fn on_failure(mut self, policy: SlicePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn spawn(self) -> TwapHandle {
+        let (cancel, cancelled) = watch::channel(false);
+        let task = tokio::spawn(self.run(cancelled));
+
+        TwapHandle { cancel, task }
+    }
+
+    async fn run(self, mut cancelled: watch::Receiver<bool>) -> ExecutionReport {
+        let start = Instant::now();
+        let interval = self.duration / self.slice_count;
+        let mut report = ExecutionReport::default();
+        let mut notional = Decimal::ZERO;
+
+        // slices are sized in whole lots, so nothing can go out without them
+        let info = match self.woo.symbol_info(&self.symbol).await {
+            Ok(info) => info,
+            Err(err) => {
+                report.slices = (0..self.slice_count)
+                    .map(|index| SliceReport {
+                        index,
+                        client_order_id: None,
+                        quantity: Decimal::ZERO,
+                        outcome: SliceOutcome::Failed(err.to_string()),
+                    })
+                    .collect();
+                return report;
+            }
+        };
+
+        for index in 0..self.slice_count {
+            let due = start + interval * index;
+            let already_cancelled = *cancelled.borrow();
+            let stopped = already_cancelled
+                || tokio::select! {
+                    _ = time::sleep_until(due) => false,
+                    // a dropped handle detaches the execution rather than stopping it
+                    Ok(_) = cancelled.wait_for(|cancelled| *cancelled) => true,
+                };
+
+            let remaining = self.total_quantity - report.filled_quantity;
+            let quantity = info.snap_quantity(remaining / Decimal::from(self.slice_count - index));
+
+            if stopped {
+                report.cancelled = true;
+                report.slices.push(SliceReport {
+                    index,
+                    client_order_id: None,
+                    quantity,
+                    outcome: SliceOutcome::Cancelled,
+                });
+                continue;
+            }
+
+            if quantity.is_zero() {
+                report.slices.push(SliceReport {
+                    index,
+                    client_order_id: None,
+                    quantity,
+                    outcome: SliceOutcome::Failed(format!(
+                        "{remaining} left is less than a lot of {}",
+                        info.base_tick
+                    )),
+                });
+                continue;
+            }
+
+            let client_order_id = self.woo.next_client_order_id();
+            let outcome = match self
+                .execute_slice(client_order_id.clone(), quantity, &mut cancelled)
+                .await
+            {
+                Ok(detail) => {
+                    let executed = detail.executed;
+                    let average_price = detail.average_executed_price;
+
+                    report.filled_quantity += executed;
+                    notional += executed * average_price.unwrap_or_default();
+                    if let Some(fee_asset) = &detail.fee_asset {
+                        *report.fees.entry(fee_asset.clone()).or_default() += detail.total_fee;
+                    }
+
+                    SliceOutcome::Executed {
+                        order_id: detail.order_id,
+                        executed,
+                        average_price,
+                    }
+                }
+                Err(err) => SliceOutcome::Failed(err.to_string()),
+            };
+
+            report.slices.push(SliceReport {
+                index,
+                client_order_id: Some(client_order_id),
+                quantity,
+                outcome,
+            });
+        }
+
+        if !report.filled_quantity.is_zero() {
+            report.average_price = Some(notional / report.filled_quantity);
+        }
+
+        report
+    }
+
+    async fn execute_slice(
+        &self,
+        client_order_id: ClientOrderId,
+        quantity: Decimal,
+        cancelled: &mut watch::Receiver<bool>,
+    ) -> Result<OrderDetail, WooError> {
+        let order_type = if self.limit_price.is_some() {
+            "IOC"
+        } else {
+            "MARKET"
+        };
+        let mut order = WooOrder::builder(&self.symbol, &self.side, order_type)
+            .client_order_id(client_order_id.clone())
+            .size(OrderSize::Quantity(quantity));
+        if let Some(price) = self.limit_price {
+            order = order.price(price);
+        }
+        let order = order.build();
+
+        let attempts = match self.policy {
+            SlicePolicy::Retry { attempts } => attempts + 1,
+            SlicePolicy::Skip => 1,
+        };
+
+        let mut last_error = None;
+        for _ in 0..attempts {
+            match self.woo.place_or_look_up(&order, &client_order_id).await {
+                Ok(Some(order_id)) => return self.await_fill(order_id, cancelled).await,
+                Ok(None) => {}
+                Err(err @ WooError::OrderStateUnknown { .. }) => return Err(err),
+                Err(err) => last_error = Some(err),
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            WooError::InvalidOrder(format!("slice {client_order_id} was never placed"))
+        }))
+    }
+
+    /// Waits for the slice `order_id` to end, cancelling it first if the
+    /// execution is cancelled meanwhile.
+    async fn await_fill(
+        &self,
+        order_id: OrderId,
+        cancelled: &mut watch::Receiver<bool>,
+    ) -> Result<OrderDetail, WooError> {
+        let wait = || {
+            self.woo.wait_for_terminal_state(
+                order_id,
+                FILL_POLL_INTERVAL,
+                FILL_DEADLINE,
+                CancelOnTimeout::Yes,
+                None,
+            )
+        };
+        let waited = tokio::select! {
+            waited = wait() => waited,
+            Ok(()) = async { cancelled.wait_for(|cancelled| *cancelled).await.map(drop) } => {
+                let cancel = CancelOrder {
+                    order_id,
+                    symbol: self.symbol.clone(),
+                };
+                if let Err(err) = self.woo.cancel_order(cancel).await {
+                    tracing::debug!(%order_id, %err, "slice ended before it could be cancelled");
+                }
+                wait().await
+            }
+        };
+
+        match waited {
+            Ok(detail) => Ok(detail),
+            Err(WaitError::TimedOut { last, .. }) => Ok(*last),
+            Err(WaitError::Woo(err)) => Err(err),
+        }
+    }
+}
+
+/// A large limit order worked client-side as a series of `clip_quantity`
+/// orders, each posted once the one before it has filled. For when the
+/// native `visible_quantity` doesn't hide enough.
+#[derive(Debug, Clone)]
+pub struct IcebergPlan {
+    pub symbol: String,
+    pub side: String,
+    pub price: Decimal,
+    pub total_quantity: Decimal,
+    pub clip_quantity: Decimal,
+    pub poll_interval: Duration,
+    /// How long one clip may rest before it is cancelled and the iceberg
+    /// stopped.
+    pub clip_timeout: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IcebergProgress {
+    pub executed: Decimal,
+    pub total_quantity: Decimal,
+    pub clips: u32,
+    /// Whether the whole quantity executed; false if a clip timed out or was
+    /// cancelled by someone else.
+    pub completed: bool,
+}
+
+impl Woo {
+    /// Submits `order`, returning its id if it reached the book. When the
+    /// response is lost the order is looked up instead, `Ok(None)` meaning
+    /// the lookup found it never arrived and it is safe to resend. If the
+    /// lookups fail too, the order fails with
+    /// [`WooError::OrderStateUnknown`] rather than risk placing it twice.
+    pub(crate) async fn place_or_look_up(
+        &self,
+        order: &WooOrder,
+        client_order_id: &ClientOrderId,
+    ) -> Result<Option<OrderId>, WooError> {
+        let err = match self.place_order(order).await {
+            Ok(res) => return Ok(Some(res.order_id)),
+            // without a response, the order may have landed anyway
+            Err(err @ (WooError::Transport { .. } | WooError::Decode { .. })) => err,
+            Err(err) => return Err(err),
+        };
+
+        for attempt in 0..LOOKUP_ATTEMPTS {
+            if attempt > 0 {
+                time::sleep(LOOKUP_BACKOFF * attempt).await;
+            }
+            match self.get_order_by_client_id(client_order_id.clone()).await {
+                Ok(row) => return Ok(Some(row.order_id)),
+                Err(lookup) => match lookup.downcast::<WooError>() {
+                    Ok(WooError::NotFound(_)) => return Ok(None),
+                    lookup => {
+                        tracing::warn!(%client_order_id, ?lookup, "looking up an order whose response was lost failed")
+                    }
+                },
+            }
+        }
+
+        Err(WooError::OrderStateUnknown {
+            client_order_id: client_order_id.clone(),
+            source: Box::new(err),
+        })
+    }
+
+    /// Runs `plan`, reporting cumulative execution to `on_progress` after
+    /// every clip.
+    pub async fn run_iceberg(
+        &self,
+        plan: &IcebergPlan,
+        mut on_progress: Option<&mut (dyn FnMut(&IcebergProgress) + Send)>,
+    ) -> anyhow::Result<IcebergProgress> {
+        if plan.clip_quantity <= Decimal::ZERO {
+            return Err(
+                WooError::InvalidOrder("clip quantity must be positive".to_string()).into(),
+            );
+        }
+
+        let mut progress = IcebergProgress {
+            executed: Decimal::ZERO,
+            total_quantity: plan.total_quantity,
+            clips: 0,
+            completed: false,
+        };
+
+        while progress.executed < plan.total_quantity {
+            let clip = plan
+                .clip_quantity
+                .min(plan.total_quantity - progress.executed);
+            let order = WooOrder::builder(&plan.symbol, &plan.side, "LIMIT")
+                .price(plan.price)
+                .size(OrderSize::Quantity(clip))
+                .build();
+            let placed = self.place_order(&order).await?;
+
+            let (detail, rested_too_long) = match self
+                .wait_for_terminal_state(
+                    placed.order_id,
+                    plan.poll_interval,
+                    plan.clip_timeout,
+                    CancelOnTimeout::Yes,
+                    None,
+                )
+                .await
+            {
+                Ok(detail) => (detail, false),
+                Err(WaitError::TimedOut { last, .. }) => (*last, true),
+                Err(WaitError::Woo(err)) => return Err(err.into()),
+            };
+
+            progress.executed += detail.executed;
+            progress.clips += 1;
+            let clip_filled = detail.status == OrderStatus::Filled;
+            progress.completed = progress.executed >= plan.total_quantity;
+
+            if let Some(on_progress) = on_progress.as_mut() {
+                on_progress(&progress);
+            }
+            if rested_too_long || !clip_filled {
+                break;
+            }
+        }
+
+        Ok(progress)
+    }
+}
+
+/// A running [`Twap`].
+#[derive(Debug)]
+pub struct TwapHandle {
+    cancel: watch::Sender<bool>,
+    task: JoinHandle<ExecutionReport>,
+}
+
+impl TwapHandle {
+    /// Stops scheduling slices, and cancels what is left of the slice on the
+    /// book, if any.
+    pub fn cancel(&self) {
+        self.cancel.send_replace(true);
+    }
+
+    pub async fn join(self) -> anyhow::Result<ExecutionReport> {
+        Ok(self.task.await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{exchange_info, mock_woo, mount, ok, order_detail, with};
+    use rust_decimal_macros::dec;
+    use wiremock::matchers::{method, path, path_regex};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn filled(executed: f64) -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "success": true,
+            "created_time": "1577349119.33",
+            "side": "BUY",
+            "status": "FILLED",
+            "symbol": "SPOT_BTC_USDT",
+            "order_id": 7,
+            "type": "MARKET",
+            "price": null,
+            "quantity": executed,
+            "executed": executed,
+            "average_executed_price": 100,
+            "total_fee": 0.001,
+            "fee_asset": "BTC",
+        }))
+    }
+
+    async fn mount_info(server: &MockServer) {
+        mount(server, "GET", "/v1/public/info", exchange_info()).await;
+    }
+
+    const SEND_ORDER_RES: &str = r#"{"success":true,"timestamp":"1578565539.808","order_id":7,"order_type":"MARKET","client_order_id":1}"#;
+
+    #[tokio::test]
+    async fn slices_total_quantity_evenly() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/order"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(SEND_ORDER_RES))
+            .expect(3)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/order/7"))
+            .respond_with(filled(0.1))
+            .mount(&server)
+            .await;
+
+        mount_info(&server).await;
+        let twap = Twap::new(
+            mock_woo(&server),
+            "SPOT_BTC_USDT",
+            "BUY",
+            dec!(0.3),
+            Duration::from_millis(30),
+            3,
+        );
+        let report = twap.spawn().join().await.unwrap();
+
+        assert_eq!(report.filled_quantity, dec!(0.3));
+        assert_eq!(report.average_price, Some(dec!(100)));
+        assert_eq!(report.fees["BTC"], dec!(0.003));
+        assert!(report
+            .slices
+            .iter()
+            .all(|slice| slice.quantity == dec!(0.1)));
+    }
+
+    #[tokio::test]
+    async fn slices_are_whole_lots() {
+        let server = MockServer::start().await;
+        mount_info(&server).await;
+        Mock::given(method("POST"))
+            .and(path("/v1/order"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(SEND_ORDER_RES))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/order/7"))
+            .respond_with(filled(0.3333))
+            .mount(&server)
+            .await;
+
+        let twap = Twap::new(
+            mock_woo(&server),
+            "SPOT_BTC_USDT",
+            "BUY",
+            dec!(1),
+            Duration::ZERO,
+            3,
+        );
+        let report = twap.spawn().join().await.unwrap();
+
+        let quantities: Vec<_> = report.slices.iter().map(|slice| slice.quantity).collect();
+        assert_eq!(quantities, [dec!(0.3333), dec!(0.3333), dec!(0.3334)]);
+        let sent: Vec<_> = server
+            .received_requests()
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|request| request.method.as_str() == "POST")
+            .map(|request| String::from_utf8(request.body).unwrap())
+            .collect();
+        assert!(sent[2].contains("order_quantity=0.3334&"), "{}", sent[2]);
+    }
+
+    #[tokio::test]
+    async fn lost_response_is_reconciled_not_resent() {
+        let server = MockServer::start().await;
+        // the order lands but the response never makes it back intact
+        Mock::given(method("POST"))
+            .and(path("/v1/order"))
+            .respond_with(ResponseTemplate::new(502).set_body_string("Bad Gateway"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path_regex(r"^/v1/client/order/\d+$"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "side": "BUY", "status": "FILLED", "symbol": "SPOT_BTC_USDT",
+                "client_order_id": 1, "reduce_only": false, "order_id": 7,
+                "order_tag": "default", "type": "MARKET", "price": 0, "quantity": 0.3,
+                "amount": null, "visible": 0, "executed": 0.3, "total_fee": 0.001,
+                "fee_asset": "BTC", "total_rebate": null, "rebate_asset": null,
+                "created_time": "1577349119.33", "updated_time": "1577349119.33",
+                "average_executed_price": 100, "position_side": "BOTH", "realized_pnl": null,
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/order/7"))
+            .respond_with(filled(0.3))
+            .mount(&server)
+            .await;
+
+        mount_info(&server).await;
+        let twap = Twap::new(
+            mock_woo(&server),
+            "SPOT_BTC_USDT",
+            "BUY",
+            dec!(0.3),
+            Duration::ZERO,
+            1,
+        );
+        let report = twap.spawn().join().await.unwrap();
+
+        assert_eq!(report.filled_quantity, dec!(0.3));
+    }
+
+    #[tokio::test]
+    async fn cancel_stops_scheduling() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/order"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(SEND_ORDER_RES))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/order/7"))
+            .respond_with(filled(0.1))
+            .mount(&server)
+            .await;
+
+        mount_info(&server).await;
+        let twap = Twap::new(
+            mock_woo(&server),
+            "SPOT_BTC_USDT",
+            "BUY",
+            dec!(0.3),
+            Duration::from_secs(60),
+            3,
+        );
+        let handle = twap.spawn();
+        time::sleep(Duration::from_millis(200)).await;
+        handle.cancel();
+        let report = handle.join().await.unwrap();
+
+        assert!(report.cancelled);
+        assert_eq!(report.filled_quantity, dec!(0.1));
+        assert!(matches!(report.slices[2].outcome, SliceOutcome::Cancelled));
+    }
+
+    #[tokio::test]
+    async fn cancel_takes_the_resting_slice_off_the_book() {
+        let server = MockServer::start().await;
+        mount_info(&server).await;
+        Mock::given(method("POST"))
+            .and(path("/v1/order"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(SEND_ORDER_RES))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let resting = |status: &str, executed: f64| {
+            ok(with(
+                order_detail(7, status, executed),
+                serde_json::json!({ "type": "IOC", "quantity": 0.3 }),
+            ))
+        };
+        Mock::given(method("DELETE"))
+            .and(path("/v1/order"))
+            .respond_with(ok(
+                serde_json::json!({ "success": true, "status": "CANCEL_SENT" }),
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+        // resting until the cancel, then cancelled with what filled by then
+        Mock::given(method("GET"))
+            .and(path("/v1/order/7"))
+            .respond_with(resting("NEW", 0.1))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/order/7"))
+            .respond_with(resting("CANCELLED", 0.1))
+            .mount(&server)
+            .await;
+
+        let twap = Twap::new(
+            mock_woo(&server),
+            "SPOT_BTC_USDT",
+            "BUY",
+            dec!(0.3),
+            Duration::ZERO,
+            1,
+        )
+        .limit_price(dec!(9000));
+        let handle = twap.spawn();
+        while !server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .any(|request| request.url.path() == "/v1/order/7")
+        {
+            time::sleep(Duration::from_millis(5)).await;
+        }
+        handle.cancel();
+        let report = handle.join().await.unwrap();
+
+        assert_eq!(report.filled_quantity, dec!(0.1));
+        let methods: Vec<_> = server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .map(|request| request.method.to_string())
+            .collect();
+        assert!(methods.contains(&"DELETE".to_string()), "{methods:?}");
+    }
+
+    #[tokio::test]
+    async fn iceberg_posts_clips_until_filled() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/order"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(SEND_ORDER_RES))
+            .expect(3)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/order/7"))
+            .respond_with(filled(0.4))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/order/7"))
+            .respond_with(filled(0.2))
+            .mount(&server)
+            .await;
+
+        let plan = IcebergPlan {
+            symbol: "SPOT_BTC_USDT".to_string(),
+            side: "BUY".to_string(),
+            price: dec!(100),
+            total_quantity: dec!(1),
+            clip_quantity: dec!(0.4),
+            poll_interval: Duration::from_millis(5),
+            clip_timeout: Duration::from_secs(5),
+        };
+        let mut seen = Vec::new();
+        let mut on_progress = |progress: &IcebergProgress| seen.push(progress.executed);
+
+        let progress = mock_woo(&server)
+            .run_iceberg(&plan, Some(&mut on_progress))
+            .await
+            .unwrap();
+
+        assert!(progress.completed);
+        assert_eq!(progress.clips, 3);
+        assert_eq!(seen, [dec!(0.4), dec!(0.8), dec!(1)]);
+
+        let last_clip = server.received_requests().await.unwrap();
+        let last_post = last_clip
+            .iter()
+            .rev()
+            .find(|request| request.method.as_str() == "POST")
+            .unwrap();
+        assert!(String::from_utf8_lossy(&last_post.body).contains("order_quantity=0.2"));
+    }
+}
@@ -0,0 +1,295 @@
+use crate::error::WooError;
+use crate::serde_helpers::parse_woo_timestamp;
+use crate::woo::Woo;
+use crate::woo_data_structs::{GetOrder, GetTrades, Row, Trade};
+use chrono::SecondsFormat;
+use futures::{pin_mut, Stream, TryStreamExt};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+/// A row of an export. Numbers are written as their exact decimal text and
+/// timestamps as ISO-8601 in UTC.
+pub trait ExportRecord {
+    /// Column names, in the order [`ExportRecord::values`] returns them. This
+    /// order is stable, new columns are only ever appended.
+    const COLUMNS: &'static [&'static str];
+
+    fn values(&self) -> Vec<String>;
+}
+
+impl ExportRecord for Trade {
+    const COLUMNS: &'static [&'static str] = &[
+        "id",
+        "executed_at",
+        "symbol",
+        "side",
+        "order_id",
+        "executed_price",
+        "executed_quantity",
+        "fee",
+        "fee_asset",
+        "is_maker",
+    ];
+
+    fn values(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            iso_8601(&self.executed_timestamp),
+            self.symbol.clone(),
+            self.side.clone(),
+            self.order_id.to_string(),
+            self.executed_price.to_string(),
+            self.executed_quantity.to_string(),
+            self.fee.to_string(),
+            self.fee_asset.clone(),
+            self.is_maker.to_string(),
+        ]
+    }
+}
+
+impl ExportRecord for Row {
+    const COLUMNS: &'static [&'static str] = &[
+        "order_id",
+        "client_order_id",
+        "created_at",
+        "updated_at",
+        "symbol",
+        "side",
+        "type",
+        "status",
+        "price",
+        "quantity",
+        "executed",
+        "average_executed_price",
+        "total_fee",
+        "fee_asset",
+        "order_tag",
+    ];
+
+    fn values(&self) -> Vec<String> {
+        let optional = |value: Option<String>| value.unwrap_or_default();
+
+        vec![
+            self.order_id.to_string(),
+            optional(self.client_order_id.as_ref().map(|id| id.to_string())),
+            self.created_time
+                .to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            self.updated_time
+                .to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            self.symbol.clone(),
+            self.side.clone(),
+            self.r#type.clone(),
+            self.status.clone(),
+            self.price.to_string(),
+            self.quantity.to_string(),
+            self.executed.to_string(),
+            optional(self.average_executed_price.map(|price| price.to_string())),
+            self.total_fee.to_string(),
+            optional(self.fee_asset.clone()),
+            self.order_tag.clone(),
+        ]
+    }
+}
+
+fn iso_8601(timestamp: &str) -> String {
+    parse_woo_timestamp(timestamp)
+        .map(|time| time.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Writes a header line and then one line per record, returning how many
+/// records were written.
+pub async fn write_csv<R, S, W>(records: S, writer: &mut W) -> anyhow::Result<usize>
+where
+    R: ExportRecord,
+    S: Stream<Item = Result<R, WooError>>,
+    W: AsyncWrite + Unpin,
+{
+    writer
+        .write_all(format!("{}\n", R::COLUMNS.join(",")).as_bytes())
+        .await?;
+
+    let mut count = 0;
+    pin_mut!(records);
+    while let Some(record) = records.try_next().await? {
+        let line = record
+            .values()
+            .iter()
+            .map(|value| csv_field(value))
+            .collect::<Vec<_>>()
+            .join(",");
+        writer.write_all(format!("{line}\n").as_bytes()).await?;
+        count += 1;
+    }
+
+    writer.flush().await?;
+    Ok(count)
+}
+
+/// Writes one JSON object per line, keyed by [`ExportRecord::COLUMNS`], with
+/// every value as a string so no precision is lost to floats.
+pub async fn write_ndjson<R, S, W>(records: S, writer: &mut W) -> anyhow::Result<usize>
+where
+    R: ExportRecord,
+    S: Stream<Item = Result<R, WooError>>,
+    W: AsyncWrite + Unpin,
+{
+    let mut count = 0;
+    pin_mut!(records);
+    while let Some(record) = records.try_next().await? {
+        let object: serde_json::Map<_, _> = R::COLUMNS
+            .iter()
+            .map(|column| column.to_string())
+            .zip(record.values().into_iter().map(serde_json::Value::String))
+            .collect();
+        let mut line = serde_json::to_string(&object)?;
+        line.push('\n');
+        writer.write_all(line.as_bytes()).await?;
+        count += 1;
+    }
+
+    writer.flush().await?;
+    Ok(count)
+}
+
+impl Woo {
+    pub async fn export_trades_csv<W>(
+        &self,
+        filter: GetTrades,
+        writer: &mut W,
+    ) -> anyhow::Result<usize>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        write_csv(self.get_trades_stream(filter), writer).await
+    }
+
+    pub async fn export_trades_ndjson<W>(
+        &self,
+        filter: GetTrades,
+        writer: &mut W,
+    ) -> anyhow::Result<usize>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        write_ndjson(self.get_trades_stream(filter), writer).await
+    }
+
+    pub async fn export_orders_csv<W>(
+        &self,
+        filter: GetOrder,
+        writer: &mut W,
+    ) -> anyhow::Result<usize>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        write_csv(self.get_orders_stream(filter), writer).await
+    }
+
+    pub async fn export_orders_ndjson<W>(
+        &self,
+        filter: GetOrder,
+        writer: &mut W,
+    ) -> anyhow::Result<usize>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        write_ndjson(self.get_orders_stream(filter), writer).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mock_woo;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn mock_trades(server: &MockServer) -> Woo {
+        for (page, id, price) in [(1, 1, "9000.123456789"), (2, 2, "9001.5")] {
+            Mock::given(method("GET"))
+                .and(path("/v1/client/trades"))
+                .and(query_param("page", page.to_string()))
+                .respond_with(ResponseTemplate::new(200).set_body_string(format!(
+                    r#"{{
+                        "success": true,
+                        "meta": {{ "total": 2, "records_per_page": 1, "current_page": {page} }},
+                        "rows": [{{
+                            "id": {id},
+                            "symbol": "SPOT_BTC_USDT",
+                            "order_id": 10,
+                            "side": "BUY",
+                            "executed_price": "{price}",
+                            "executed_quantity": "0.00012345",
+                            "fee": "0.000000012",
+                            "fee_asset": "BTC",
+                            "executed_timestamp": "1578565539.808",
+                            "is_maker": 1
+                        }}]
+                    }}"#
+                )))
+                .mount(server)
+                .await;
+        }
+
+        mock_woo(server)
+    }
+
+    #[tokio::test]
+    async fn trades_csv_keeps_precision_and_column_order() {
+        let server = MockServer::start().await;
+        let woo = mock_trades(&server).await;
+
+        let mut out = Vec::new();
+        let count = woo
+            .export_trades_csv(GetTrades::default(), &mut out)
+            .await
+            .unwrap();
+
+        assert_eq!(count, 2);
+        let csv = String::from_utf8(out).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "id,executed_at,symbol,side,order_id,executed_price,executed_quantity,fee,fee_asset,is_maker"
+        );
+        assert_eq!(
+            lines[1],
+            "1,2020-01-09T10:25:39.808Z,SPOT_BTC_USDT,BUY,10,9000.123456789,0.00012345,0.000000012,BTC,true"
+        );
+    }
+
+    #[tokio::test]
+    async fn trades_ndjson_writes_one_object_per_line() {
+        let server = MockServer::start().await;
+        let woo = mock_trades(&server).await;
+
+        let mut out = Vec::new();
+        woo.export_trades_ndjson(GetTrades::default(), &mut out)
+            .await
+            .unwrap();
+
+        let lines: Vec<serde_json::Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["executed_price"], "9001.5");
+        assert_eq!(lines[1]["executed_at"], "2020-01-09T10:25:39.808Z");
+    }
+
+    #[test]
+    fn csv_fields_are_quoted_when_needed() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
@@ -0,0 +1,104 @@
+use crate::woo::Woo;
+use crate::woo_data_structs::{AccountInfo, OrderType, WooOrder};
+use rust_decimal::Decimal;
+
+/// Maker and taker fee rates on a symbol, as fractions of the notional traded
+/// (0.0002 for 2 bps).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SymbolFee {
+    pub maker_rate: Decimal,
+    pub taker_rate: Decimal,
+}
+
+impl SymbolFee {
+    /// Rates given in basis points, e.g. `from_bps(2, 5)` for 0.02% maker and
+    /// 0.05% taker.
+    pub fn from_bps(maker_bps: impl Into<Decimal>, taker_bps: impl Into<Decimal>) -> Self {
+        let bps = Decimal::new(1, 4);
+        Self {
+            maker_rate: maker_bps.into() * bps,
+            taker_rate: taker_bps.into() * bps,
+        }
+    }
+
+    /// The account wide rates of `v1/client/info`, `None` if it didn't send
+    /// both.
+    pub fn from_account(info: &AccountInfo) -> Option<Self> {
+        Some(Self {
+            maker_rate: Decimal::try_from(info.maker_fee_rate?).ok()?,
+            taker_rate: Decimal::try_from(info.taker_fee_rate?).ok()?,
+        })
+    }
+}
+
+impl Woo {
+    /// The fee `order` would pay if filled in full, in quote currency.
+    ///
+    /// `POST_ONLY` and plain `LIMIT` orders are charged the maker rate, on the
+    /// assumption that they rest; a limit priced through the book takes and
+    /// pays the taker rate instead. Everything else takes. `None` for orders
+    /// whose notional isn't known before they fill: market, `ASK` and `BID`
+    /// orders sized by quantity.
+    pub fn estimate_fee(&self, order: &WooOrder, fee: &SymbolFee) -> Option<Decimal> {
+        let notional = match (order.order_amount, order.order_price, order.order_quantity) {
+            (Some(amount), _, _) => amount,
+            (None, Some(price), Some(quantity)) => price * quantity,
+            _ => return None,
+        };
+        let rests = matches!(
+            order.wire_order_type().and_then(str::parse),
+            Ok(OrderType::Limit | OrderType::PostOnly)
+        );
+        let rate = if rests {
+            fee.maker_rate
+        } else {
+            fee.taker_rate
+        };
+
+        Some(notional * rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::Credentials;
+    use crate::woo::Environment;
+    use crate::woo_data_structs::{OrderSize, TimeInForce};
+    use rust_decimal_macros::dec;
+
+    const FEE: SymbolFee = SymbolFee {
+        maker_rate: dec!(0.0002),
+        taker_rate: dec!(0.0005),
+    };
+
+    fn woo() -> Woo {
+        Woo::builder(Environment::Staging, Credentials::new("key", "secret"))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn resting_limit_orders_pay_the_maker_rate() {
+        let woo = woo();
+        let order = WooOrder::limit("SPOT_BTC_USDT", "BUY", dec!(60000), dec!(0.5));
+
+        assert_eq!(woo.estimate_fee(&order, &FEE), Some(dec!(6)));
+        let post_only = WooOrder::post_only("SPOT_BTC_USDT", "BUY", dec!(60000), dec!(0.5));
+        assert_eq!(woo.estimate_fee(&post_only, &FEE), Some(dec!(6)));
+
+        let mut ioc = order;
+        ioc.time_in_force = Some(TimeInForce::Ioc);
+        assert_eq!(woo.estimate_fee(&ioc, &FEE), Some(dec!(15)));
+    }
+
+    #[test]
+    fn market_orders_pay_the_taker_rate_on_a_known_notional() {
+        let woo = woo();
+        let by_amount = WooOrder::market_by_amount("SPOT_BTC_USDT", dec!(1000));
+
+        assert_eq!(woo.estimate_fee(&by_amount, &FEE), Some(dec!(0.5)));
+        let by_quantity = WooOrder::market("SPOT_BTC_USDT", "SELL", OrderSize::Quantity(dec!(1)));
+        assert_eq!(woo.estimate_fee(&by_quantity, &FEE), None);
+    }
+}
@@ -0,0 +1,319 @@
+use crate::constants::{MAX_KLINE_LIMIT, PUBLIC_RATE_LIMIT};
+use crate::error::WooError;
+use crate::woo::Woo;
+use crate::woo_data_structs::WooEnvelope;
+use chrono::{DateTime, TimeDelta, Utc};
+use futures::stream::{self, Stream, TryStreamExt};
+use reqwest::Method;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tokio::time::Instant;
+
+/// Candle widths WOO offers.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KlineInterval {
+    #[serde(rename = "1m")]
+    OneMinute,
+    #[serde(rename = "5m")]
+    FiveMinutes,
+    #[serde(rename = "15m")]
+    FifteenMinutes,
+    #[serde(rename = "30m")]
+    ThirtyMinutes,
+    #[serde(rename = "1h")]
+    OneHour,
+    #[serde(rename = "4h")]
+    FourHours,
+    #[serde(rename = "12h")]
+    TwelveHours,
+    #[serde(rename = "1d")]
+    OneDay,
+    #[serde(rename = "1w")]
+    OneWeek,
+    #[serde(rename = "1mon")]
+    OneMonth,
+}
+
+impl KlineInterval {
+    /// Width of one candle; months count as 31 days, the longest they get.
+    pub fn duration(self) -> TimeDelta {
+        match self {
+            KlineInterval::OneMinute => TimeDelta::minutes(1),
+            KlineInterval::FiveMinutes => TimeDelta::minutes(5),
+            KlineInterval::FifteenMinutes => TimeDelta::minutes(15),
+            KlineInterval::ThirtyMinutes => TimeDelta::minutes(30),
+            KlineInterval::OneHour => TimeDelta::hours(1),
+            KlineInterval::FourHours => TimeDelta::hours(4),
+            KlineInterval::TwelveHours => TimeDelta::hours(12),
+            KlineInterval::OneDay => TimeDelta::days(1),
+            KlineInterval::OneWeek => TimeDelta::weeks(1),
+            KlineInterval::OneMonth => TimeDelta::days(31),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Candle {
+    pub symbol: String,
+    pub open: Decimal,
+    pub close: Decimal,
+    pub high: Decimal,
+    pub low: Decimal,
+    /// Traded quantity in base currency.
+    pub volume: Decimal,
+    /// Traded value in quote currency.
+    pub amount: Decimal,
+    /// Milliseconds since the epoch.
+    pub start_timestamp: i64,
+    pub end_timestamp: i64,
+}
+
+impl Candle {
+    pub fn start_time(&self) -> Option<DateTime<Utc>> {
+        DateTime::from_timestamp_millis(self.start_timestamp)
+    }
+}
+
+#[derive(Serialize)]
+struct KlineQuery<'a> {
+    symbol: &'a str,
+    #[serde(rename = "type")]
+    interval: KlineInterval,
+    start_time: i64,
+    end_time: i64,
+    size: u32,
+}
+
+// https://docs.woo.org/#kline-historical-data-public
+#[derive(Deserialize, Debug)]
+struct KlineData {
+    rows: Vec<Candle>,
+}
+
+impl Woo {
+    /// Every `interval` candle of `symbol` starting in `[start, end)`, oldest
+    /// first, see [`Woo::get_kline_range_stream`].
+    pub async fn get_kline_range(
+        &self,
+        symbol: &str,
+        interval: KlineInterval,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<Candle>> {
+        Ok(self
+            .get_kline_range_stream(symbol, interval, start, end)
+            .try_collect()
+            .await?)
+    }
+
+    /// Every `interval` candle of `symbol` starting in `[start, end)`, oldest
+    /// first, fetched a window of at most [`MAX_KLINE_LIMIT`] candles at a
+    /// time as the stream is polled. Windows are requested one after the
+    /// other within [`PUBLIC_RATE_LIMIT`]; candles repeated across windows are
+    /// kept once, and periods without trading are simply absent.
+    pub fn get_kline_range_stream<'a>(
+        &'a self,
+        symbol: &'a str,
+        interval: KlineInterval,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> impl Stream<Item = Result<Candle, WooError>> + 'a {
+        let window = interval.duration() * MAX_KLINE_LIMIT as i32;
+        let spacing = PUBLIC_RATE_LIMIT.per / PUBLIC_RATE_LIMIT.requests;
+
+        stream::try_unfold((start, None), move |(cursor, last_request)| async move {
+            if cursor >= end {
+                return Ok(None);
+            }
+            if let Some(last_request) = last_request {
+                tokio::time::sleep_until(last_request + spacing).await;
+            }
+
+            let window_end = (cursor + window).min(end);
+            let query = KlineQuery {
+                symbol,
+                interval,
+                start_time: cursor.timestamp_millis(),
+                end_time: window_end.timestamp_millis(),
+                size: MAX_KLINE_LIMIT,
+            };
+            let requested_at = Instant::now();
+            let data = self
+                .public_data_request::<_, WooEnvelope<KlineData>>(
+                    Method::GET,
+                    "v1/hist/kline",
+                    &query,
+                )
+                .await?
+                .into_data()?;
+
+            // windows are half open, so a boundary candle only belongs to the
+            // window it starts in, and the map puts them oldest first
+            let window_ms = query.start_time..query.end_time;
+            let candles: BTreeMap<_, _> = data
+                .rows
+                .into_iter()
+                .filter(|candle| window_ms.contains(&candle.start_timestamp))
+                .map(|candle| (candle.start_timestamp, candle))
+                .collect();
+
+            Ok::<_, WooError>(Some((
+                stream::iter(candles.into_values().map(Ok)),
+                (window_end, Some(requested_at)),
+            )))
+        })
+        .try_flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::Credentials;
+    use crate::testing::mock_woo;
+    use crate::woo::Environment;
+    use url::Url;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const MINUTE: i64 = 60_000;
+
+    fn candle(start_timestamp: i64) -> serde_json::Value {
+        serde_json::json!({
+            "symbol": "SPOT_BTC_USDT", "type": "1m",
+            "open": "9000", "close": "9001", "high": "9002", "low": "8999",
+            "volume": "1.5", "amount": "13500",
+            "start_timestamp": start_timestamp, "end_timestamp": start_timestamp + MINUTE,
+        })
+    }
+
+    async fn mount_page(server: &MockServer, start_time: i64, rows: Vec<serde_json::Value>) {
+        Mock::given(method("GET"))
+            .and(path("/v1/hist/kline"))
+            .and(query_param("start_time", start_time.to_string()))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "rows": rows, "meta": { "total": 3, "records_per_page": 1000, "current_page": 1 } },
+            })))
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn stream_fetches_windows_lazily_and_spaced_out() {
+        let server = MockServer::start().await;
+        let start = 1_700_000_000_000;
+        let second_window = start + 1000 * MINUTE;
+        mount_page(&server, start, vec![candle(start)]).await;
+        mount_page(&server, second_window, vec![candle(second_window)]).await;
+
+        let woo = mock_woo(&server);
+        let candles = woo.get_kline_range_stream(
+            "SPOT_BTC_USDT",
+            KlineInterval::OneMinute,
+            DateTime::from_timestamp_millis(start).unwrap(),
+            DateTime::from_timestamp_millis(second_window + MINUTE).unwrap(),
+        );
+        futures::pin_mut!(candles);
+
+        let started = Instant::now();
+        assert_eq!(
+            candles.try_next().await.unwrap().unwrap().start_timestamp,
+            start
+        );
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+
+        assert_eq!(
+            candles.try_next().await.unwrap().unwrap().start_timestamp,
+            second_window
+        );
+        assert!(candles.try_next().await.unwrap().is_none());
+        assert!(started.elapsed() >= PUBLIC_RATE_LIMIT.per / PUBLIC_RATE_LIMIT.requests);
+    }
+
+    #[tokio::test]
+    async fn pages_through_the_range_and_dedupes() {
+        let server = MockServer::start().await;
+        let start = 1_700_000_000_000;
+        let second_window = start + 1000 * MINUTE;
+
+        // newest first, like WOO, and the window boundary candle in both pages
+        let pages = [
+            (
+                start,
+                vec![candle(second_window), candle(start + MINUTE), candle(start)],
+            ),
+            // one minute without trades in the second window
+            (
+                second_window,
+                vec![candle(second_window + 2 * MINUTE), candle(second_window)],
+            ),
+        ];
+        for (start_time, rows) in pages {
+            Mock::given(method("GET"))
+                .and(path("/v1/hist/kline"))
+                .and(query_param("start_time", start_time.to_string()))
+                .and(query_param("type", "1m"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "success": true,
+                    "data": { "rows": rows, "meta": { "total": 3, "records_per_page": 1000, "current_page": 1 } },
+                })))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+
+        let woo = mock_woo(&server);
+        let candles = woo
+            .get_kline_range(
+                "SPOT_BTC_USDT",
+                KlineInterval::OneMinute,
+                DateTime::from_timestamp_millis(start).unwrap(),
+                DateTime::from_timestamp_millis(second_window + 3 * MINUTE).unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let starts: Vec<_> = candles
+            .iter()
+            .map(|candle| candle.start_timestamp)
+            .collect();
+        assert_eq!(
+            starts,
+            [
+                start,
+                start + MINUTE,
+                second_window,
+                second_window + 2 * MINUTE
+            ]
+        );
+        assert_eq!(candles[0].volume, Decimal::new(15, 1));
+    }
+
+    #[tokio::test]
+    async fn asks_the_public_data_host_not_the_trading_one() {
+        let trading = MockServer::start().await;
+        let public_data = MockServer::start().await;
+        let start = 1_700_000_000_000;
+        mount_page(&public_data, start, vec![candle(start)]).await;
+
+        let woo = Woo::builder(Environment::Staging, Credentials::new("key", "secret"))
+            .base_url(Url::parse(&trading.uri()).unwrap())
+            .public_data_url(Url::parse(&public_data.uri()).unwrap())
+            .build()
+            .unwrap();
+        let candles = woo
+            .get_kline_range(
+                "SPOT_BTC_USDT",
+                KlineInterval::OneMinute,
+                DateTime::from_timestamp_millis(start).unwrap(),
+                DateTime::from_timestamp_millis(start + MINUTE).unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(candles.len(), 1);
+        assert!(trading.received_requests().await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,276 @@
+use crate::woo::Woo;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Weight of the newest sample in the moving average.
+const ALPHA: f64 = 0.2;
+
+/// One-way latency and clock offset over the last requests, see
+/// [`Woo::latency_stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatencyStats {
+    pub samples: usize,
+    /// One-way latency, estimated as half the round trip, so it includes any
+    /// proxy on the way.
+    pub p50: Duration,
+    pub p95: Duration,
+    pub max: Duration,
+    /// Median of the server's clock minus the local one, in milliseconds.
+    pub clock_offset_ms: i64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ClockSample {
+    one_way: Duration,
+    offset_ms: i64,
+}
+
+/// Compares the local send and receive times of every request with the
+/// server timestamp of its response.
+#[derive(Debug)]
+pub(crate) struct ClockTelemetry {
+    window: usize,
+    max_skew: Duration,
+    samples: Mutex<VecDeque<ClockSample>>,
+    /// The current median offset, read by every signed request.
+    offset_ms: AtomicI64,
+}
+
+impl ClockTelemetry {
+    fn new(window: usize, max_skew: Duration) -> Self {
+        Self {
+            window: window.max(1),
+            max_skew,
+            samples: Mutex::new(VecDeque::new()),
+            offset_ms: AtomicI64::new(0),
+        }
+    }
+
+    /// `sent` is the local time the request went out and `round_trip` how
+    /// long the response took to come back after that.
+    pub(crate) fn record(&self, sent: DateTime<Utc>, round_trip: Duration, server: DateTime<Utc>) {
+        let one_way = round_trip / 2;
+        // the server most likely stamped the response halfway through
+        let midpoint = sent + chrono::Duration::from_std(one_way).unwrap_or_default();
+        let sample = ClockSample {
+            one_way,
+            offset_ms: (server - midpoint).num_milliseconds(),
+        };
+
+        let mut samples = self.samples.lock().unwrap();
+        if samples.len() == self.window {
+            samples.pop_front();
+        }
+        samples.push_back(sample);
+
+        let mut offsets: Vec<_> = samples.iter().map(|sample| sample.offset_ms).collect();
+        offsets.sort_unstable();
+        let offset_ms = offsets[offsets.len() / 2];
+        drop(samples);
+
+        let previous = self.offset_ms.swap(offset_ms, Ordering::Relaxed);
+        let max_skew = self.max_skew.as_millis() as u64;
+        // only warn when the skew first crosses the threshold, not on every
+        // request after
+        if offset_ms.unsigned_abs() > max_skew && previous.unsigned_abs() <= max_skew {
+            tracing::warn!(
+                clock_offset_ms = offset_ms,
+                max_skew_ms = max_skew,
+                "local clock is off the server's"
+            );
+        }
+    }
+
+    pub(crate) fn offset_ms(&self) -> i64 {
+        self.offset_ms.load(Ordering::Relaxed)
+    }
+
+    fn stats(&self) -> Option<LatencyStats> {
+        let samples = self.samples.lock().unwrap();
+        if samples.is_empty() {
+            return None;
+        }
+
+        let mut latencies: Vec<_> = samples.iter().map(|sample| sample.one_way).collect();
+        latencies.sort_unstable();
+        // nearest rank
+        let percentile = |q: f64| {
+            let rank = (q * latencies.len() as f64).ceil() as usize;
+            latencies[rank.clamp(1, latencies.len()) - 1]
+        };
+
+        Some(LatencyStats {
+            samples: latencies.len(),
+            p50: percentile(0.5),
+            p95: percentile(0.95),
+            max: latencies[latencies.len() - 1],
+            clock_offset_ms: self.offset_ms(),
+        })
+    }
+}
+
+/// Exponential moving average of request latency, per endpoint.
+#[derive(Debug, Default)]
+pub(crate) struct LatencyTracker {
+    averages: Mutex<HashMap<String, Duration>>,
+}
+
+impl LatencyTracker {
+    pub(crate) fn record(&self, path: &str, sample: Duration) {
+        let mut averages = self.averages.lock().unwrap();
+
+        averages
+            .entry(endpoint_key(path))
+            .and_modify(|average| {
+                *average = average.mul_f64(1.0 - ALPHA) + sample.mul_f64(ALPHA);
+            })
+            .or_insert(sample);
+    }
+
+    pub(crate) fn get(&self, endpoint: &str) -> Option<Duration> {
+        self.averages
+            .lock()
+            .unwrap()
+            .get(&endpoint_key(endpoint))
+            .copied()
+    }
+}
+
+/// `/v1/order/123` and `v1/order/{id}` both become `v1/order/{id}`, so ids in
+/// the path don't each get their own average.
+pub(crate) fn endpoint_key(path: &str) -> String {
+    path.trim_matches('/')
+        .split('/')
+        .map(|segment| {
+            if !segment.is_empty() && segment.bytes().all(|byte| byte.is_ascii_digit()) {
+                "{id}"
+            } else {
+                segment
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+impl Woo {
+    /// Moving average of how long `endpoint` (e.g. `v1/orders`, or
+    /// `v1/order/{id}` for paths with an id) took to answer, `None` before the
+    /// first request to it. Shared by every clone of this client.
+    pub fn endpoint_latency(&self, endpoint: &str) -> Option<Duration> {
+        self.latency.get(endpoint)
+    }
+
+    /// Measures every request against the server timestamp of its response,
+    /// over the last `window` requests. The clock offset found is added to
+    /// the timestamp of signed requests, and logged as a warning once it
+    /// exceeds `max_skew`. Shared by clones made after this call.
+    pub fn with_clock_telemetry(mut self, window: usize, max_skew: Duration) -> Self {
+        self.telemetry = Some(Arc::new(ClockTelemetry::new(window, max_skew)));
+        self
+    }
+
+    /// One-way latency percentiles and clock offset over the telemetry
+    /// window, `None` without [`Woo::with_clock_telemetry`] or before the
+    /// first timestamped response.
+    pub fn latency_stats(&self) -> Option<LatencyStats> {
+        self.telemetry
+            .as_ref()
+            .and_then(|telemetry| telemetry.stats())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mock_woo;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn average_converges_to_new_latency() {
+        let tracker = LatencyTracker::default();
+
+        tracker.record("/v1/orders", Duration::from_millis(10));
+        assert_eq!(tracker.get("v1/orders"), Some(Duration::from_millis(10)));
+
+        for _ in 0..50 {
+            tracker.record("/v1/orders", Duration::from_millis(100));
+        }
+        let average = tracker.get("v1/orders").unwrap();
+        assert!(average > Duration::from_millis(99) && average <= Duration::from_millis(100));
+    }
+
+    #[test]
+    fn ids_in_paths_share_an_average() {
+        let tracker = LatencyTracker::default();
+
+        tracker.record("/v1/order/1", Duration::from_millis(10));
+        tracker.record("/v1/order/2", Duration::from_millis(20));
+
+        assert_eq!(
+            tracker.get("v1/order/{id}"),
+            Some(Duration::from_millis(12))
+        );
+        assert_eq!(tracker.get("v1/orders"), None);
+    }
+
+    #[test]
+    fn stats_cover_the_last_window_of_samples() {
+        let telemetry = ClockTelemetry::new(20, Duration::from_secs(1));
+        let sent = Utc::now();
+
+        // pushed out of the window by the 20 after it
+        telemetry.record(sent, Duration::from_secs(10), sent);
+        for ms in 1..=20 {
+            let round_trip = Duration::from_millis(2 * ms);
+            let server = sent + chrono::Duration::milliseconds(ms as i64 + 250);
+            telemetry.record(sent, round_trip, server);
+        }
+
+        let stats = telemetry.stats().unwrap();
+        assert_eq!(stats.samples, 20);
+        assert_eq!(stats.p50, Duration::from_millis(10));
+        assert_eq!(stats.p95, Duration::from_millis(19));
+        assert_eq!(stats.max, Duration::from_millis(20));
+        assert_eq!(stats.clock_offset_ms, 250);
+    }
+
+    #[tokio::test]
+    async fn signed_requests_follow_the_server_clock() {
+        let server = MockServer::start().await;
+        let server_time = Utc::now() + chrono::Duration::seconds(30);
+        Mock::given(method("GET"))
+            .and(path("/v1/client/holding"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "holding": {},
+                "timestamp": format!("{:.3}", server_time.timestamp_millis() as f64 / 1000.0),
+            })))
+            .mount(&server)
+            .await;
+        let woo = mock_woo(&server).with_clock_telemetry(8, Duration::from_secs(1));
+        assert!(woo.latency_stats().is_none());
+
+        woo.get_holding().await.unwrap();
+        woo.get_holding().await.unwrap();
+
+        let stats = woo.latency_stats().unwrap();
+        assert_eq!(stats.samples, 2);
+        assert!((stats.clock_offset_ms - 30_000).abs() < 1_000);
+
+        let requests = server.received_requests().await.unwrap();
+        let timestamp = |index: usize| -> i64 {
+            requests[index].headers["x-api-timestamp"]
+                .to_str()
+                .unwrap()
+                .parse()
+                .unwrap()
+        };
+        // the first went out before there was anything to go by
+        assert!((timestamp(0) - Utc::now().timestamp_millis()).abs() < 5_000);
+        assert!((timestamp(1) - server_time.timestamp_millis()).abs() < 5_000);
+    }
+}
@@ -1,3 +1,55 @@
+pub mod account_mode;
+pub mod accounting;
+pub mod algo;
+pub mod audit;
+pub mod builder;
+pub mod bulk;
+pub mod cancel;
+pub mod carry;
+pub mod client;
+pub mod client_order_id;
+pub mod compat;
 pub mod constants;
+pub mod cursor;
+pub mod deposit;
+pub mod diagnostics;
+pub mod env;
+pub mod error;
+pub mod execution;
+pub mod export;
+pub mod fee;
+pub mod kline;
+pub mod latency;
+pub mod market_trades;
+#[cfg(feature = "test-util")]
+pub mod mock;
+pub mod pair;
+pub mod paper;
+pub mod portfolio;
+pub mod proxy;
+pub mod public_cache;
+pub mod quoting;
+pub mod reconcile;
+pub mod recording;
+pub mod registry;
+pub mod replace;
+pub mod response_log;
+pub mod risk;
+pub mod serde_helpers;
+pub mod shutdown;
+pub mod signer;
+pub mod stats;
+pub mod symbol;
+pub mod symbol_cache;
+#[cfg(test)]
+mod testing;
+pub mod throttle;
+pub mod tls;
+pub mod wait;
+pub mod withdraw;
 pub mod woo;
 pub mod woo_data_structs;
+#[cfg(feature = "websocket")]
+pub mod ws;
+
+pub use reqwest::Method;
@@ -0,0 +1,225 @@
+use crate::constants::{MAX_HIST_TRADES_SIZE, PUBLIC_RATE_LIMIT};
+use crate::error::WooError;
+use crate::serde_helpers::string_or_number;
+use crate::woo::Woo;
+use crate::woo_data_structs::WooEnvelope;
+use chrono::{DateTime, TimeDelta, Utc};
+use futures::stream::{self, Stream, TryStreamExt};
+use reqwest::Method;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+
+/// Span of one `v1/hist/trades` query; busier windows take several pages.
+const TRADES_WINDOW: TimeDelta = TimeDelta::hours(1);
+
+/// A trade of the public tape.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MarketTrade {
+    pub symbol: String,
+    pub side: String,
+    pub executed_price: Decimal,
+    pub executed_quantity: Decimal,
+    /// Milliseconds since the epoch.
+    #[serde(deserialize_with = "string_or_number::deserialize")]
+    pub executed_ts: i64,
+}
+
+impl MarketTrade {
+    pub fn executed_time(&self) -> Option<DateTime<Utc>> {
+        DateTime::from_timestamp_millis(self.executed_ts)
+    }
+}
+
+#[derive(Serialize)]
+struct TradesQuery<'a> {
+    symbol: &'a str,
+    start_time: i64,
+    end_time: i64,
+    page: u32,
+    size: u32,
+}
+
+// https://docs.woo.org/#trades-historical-data-public
+#[derive(Deserialize, Debug)]
+struct TradesData {
+    rows: Vec<MarketTrade>,
+}
+
+impl Woo {
+    /// Every public trade of `symbol` executed in `[start, end)`, oldest
+    /// first, see [`Woo::get_market_trades_range_stream`].
+    pub async fn get_market_trades_range(
+        &self,
+        symbol: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<MarketTrade>> {
+        Ok(self
+            .get_market_trades_range_stream(symbol, start, end)
+            .try_collect()
+            .await?)
+    }
+
+    /// Every public trade of `symbol` executed in `[start, end)`, oldest
+    /// first, fetched an hour at a time as the stream is polled, each hour
+    /// paged through [`MAX_HIST_TRADES_SIZE`] trades at a time. Requests go
+    /// out one after the other within [`PUBLIC_RATE_LIMIT`], and trades
+    /// returned for two windows are kept once.
+    pub fn get_market_trades_range_stream<'a>(
+        &'a self,
+        symbol: &'a str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> impl Stream<Item = Result<MarketTrade, WooError>> + 'a {
+        let spacing = PUBLIC_RATE_LIMIT.per / PUBLIC_RATE_LIMIT.requests;
+
+        stream::try_unfold(
+            (start, None),
+            move |(cursor, mut last_request)| async move {
+                if cursor >= end {
+                    return Ok(None);
+                }
+
+                let window_end = (cursor + TRADES_WINDOW).min(end);
+                let window_ms = cursor.timestamp_millis()..window_end.timestamp_millis();
+                let mut trades = Vec::new();
+                for page in 1.. {
+                    if let Some(last_request) = last_request {
+                        tokio::time::sleep_until(last_request + spacing).await;
+                    }
+
+                    let query = TradesQuery {
+                        symbol,
+                        start_time: window_ms.start,
+                        end_time: window_ms.end,
+                        page,
+                        size: MAX_HIST_TRADES_SIZE,
+                    };
+                    last_request = Some(Instant::now());
+                    let rows = self
+                        .public_data_request::<_, WooEnvelope<TradesData>>(
+                            Method::GET,
+                            "v1/hist/trades",
+                            &query,
+                        )
+                        .await?
+                        .into_data()?
+                        .rows;
+
+                    let last_page = rows.len() < MAX_HIST_TRADES_SIZE as usize;
+                    trades.extend(rows);
+                    if last_page {
+                        break;
+                    }
+                }
+
+                // windows are half open, so a trade on the boundary only belongs
+                // to the window it falls in
+                trades.retain(|trade| window_ms.contains(&trade.executed_ts));
+                trades.sort_by_key(|trade| trade.executed_ts);
+
+                Ok::<_, WooError>(Some((
+                    stream::iter(trades.into_iter().map(Ok)),
+                    (window_end, last_request),
+                )))
+            },
+        )
+        .try_flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mock_woo;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const HOUR: i64 = 3_600_000;
+
+    fn trade(executed_ts: i64) -> serde_json::Value {
+        serde_json::json!({
+            "symbol": "SPOT_BTC_USDT", "side": "BUY", "source": 0,
+            "executed_price": "9000", "executed_quantity": "0.1",
+            "executed_ts": executed_ts,
+        })
+    }
+
+    async fn mount_page(
+        server: &MockServer,
+        start_time: i64,
+        page: u32,
+        rows: Vec<serde_json::Value>,
+    ) {
+        Mock::given(method("GET"))
+            .and(path("/v1/hist/trades"))
+            .and(query_param("start_time", start_time.to_string()))
+            .and(query_param("page", page.to_string()))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "rows": rows, "meta": { "total": 3, "records_per_page": 1000, "current_page": page } },
+            })))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn pages_through_windows_in_order_and_dedupes() {
+        let server = MockServer::start().await;
+        let start = 1_700_000_000_000;
+        let second_window = start + HOUR;
+
+        // a full first page, newest first like WOO, and the trade on the
+        // window boundary in both windows
+        let mut full_page = vec![trade(second_window)];
+        full_page.extend(
+            (1..MAX_HIST_TRADES_SIZE as i64)
+                .rev()
+                .map(|i| trade(start + i)),
+        );
+        mount_page(&server, start, 1, full_page).await;
+        mount_page(&server, start, 2, vec![trade(start)]).await;
+        mount_page(&server, second_window, 1, vec![trade(second_window)]).await;
+
+        let woo = mock_woo(&server);
+        let started = Instant::now();
+        let trades = woo
+            .get_market_trades_range(
+                "SPOT_BTC_USDT",
+                DateTime::from_timestamp_millis(start).unwrap(),
+                DateTime::from_timestamp_millis(second_window + HOUR).unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let times: Vec<_> = trades.iter().map(|trade| trade.executed_ts).collect();
+        let expected: Vec<_> = (0..MAX_HIST_TRADES_SIZE as i64)
+            .map(|i| start + i)
+            .chain([second_window])
+            .collect();
+        assert_eq!(times, expected);
+        assert_eq!(trades[0].executed_price, Decimal::new(9000, 0));
+        assert!(started.elapsed() >= 2 * PUBLIC_RATE_LIMIT.per / PUBLIC_RATE_LIMIT.requests);
+    }
+
+    #[tokio::test]
+    async fn quiet_windows_yield_nothing() {
+        let server = MockServer::start().await;
+        let start = 1_700_000_000_000;
+        mount_page(&server, start, 1, Vec::new()).await;
+
+        let woo = mock_woo(&server);
+        let trades = woo
+            .get_market_trades_range(
+                "SPOT_BTC_USDT",
+                DateTime::from_timestamp_millis(start).unwrap(),
+                DateTime::from_timestamp_millis(start + HOUR).unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert!(trades.is_empty());
+    }
+}
@@ -0,0 +1,222 @@
+use crate::client::{page_of_orders, WooClient};
+use crate::error::{WooApiError, WooError};
+use crate::woo_data_structs::{
+    CancelOrder, CancelOrderRes, CancelStatus, GetOrder, GetOrderRes, HoldingRes, OrderDetail,
+    OrderId, OrderStatus, PositionsRes, Row, SendOrderRes, WooOrder,
+};
+use chrono::Utc;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// An in-memory exchange for testing strategy code offline.
+///
+/// Orders fill completely, at their own limit or at the market price for
+/// market orders, as soon as the market price set with
+/// [`MockExchange::set_market_price`] reaches them.
+#[derive(Debug, Default)]
+pub struct MockExchange {
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    next_order_id: u64,
+    market_prices: HashMap<String, Decimal>,
+    orders: HashMap<OrderId, OrderDetail>,
+}
+
+impl MockExchange {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Moves the market of `symbol` to `price`, filling every resting order
+    /// it crosses.
+    pub fn set_market_price(&self, symbol: &str, price: Decimal) {
+        let mut state = self.state.lock().unwrap();
+        state.market_prices.insert(symbol.to_string(), price);
+
+        for order in state.orders.values_mut() {
+            if order.symbol == symbol {
+                fill_if_crossed(order, price);
+            }
+        }
+    }
+}
+
+fn fill_if_crossed(order: &mut OrderDetail, market_price: Decimal) {
+    if order.status.is_terminal() {
+        return;
+    }
+
+    let fill_price = match order.price {
+        // market order
+        None => market_price,
+        Some(limit) => {
+            let crossed = match order.side.as_str() {
+                "SELL" => market_price >= limit,
+                _ => market_price <= limit,
+            };
+            if !crossed {
+                return;
+            }
+            limit
+        }
+    };
+
+    order.status = OrderStatus::Filled;
+    order.executed = order.quantity.unwrap_or_default();
+    order.average_executed_price = Some(fill_price);
+}
+
+impl WooClient for MockExchange {
+    async fn create_order(&self, order: WooOrder) -> anyhow::Result<SendOrderRes> {
+        let mut state = self.state.lock().unwrap();
+        state.next_order_id += 1;
+        let order_id = OrderId(state.next_order_id);
+
+        let to_f64 = |value: Option<Decimal>| value.and_then(|value| value.to_f64());
+        let order_type = order.wire_order_type()?.to_string();
+        let is_market = order_type == "MARKET";
+        let mut detail = OrderDetail {
+            symbol: order.symbol.clone(),
+            side: order.side.clone(),
+            status: OrderStatus::New,
+            order_id,
+            client_order_id: order.client_order_id.clone(),
+            order_tag: order.order_tag.clone(),
+            r#type: order_type.clone(),
+            price: if is_market { None } else { order.order_price },
+            quantity: order.order_quantity,
+            amount: order.order_amount,
+            executed: Decimal::ZERO,
+            average_executed_price: None,
+            total_fee: Decimal::ZERO,
+            fee_asset: None,
+            created_time: Utc::now(),
+        };
+        if let Some(&price) = state.market_prices.get(&order.symbol) {
+            fill_if_crossed(&mut detail, price);
+        }
+        state.orders.insert(order_id, detail);
+
+        Ok(SendOrderRes {
+            success: true,
+            timestamp: Utc::now(),
+            order_id,
+            order_type,
+            client_order_id: order.client_order_id.unwrap_or_default(),
+            order_price: to_f64(order.order_price),
+            order_quantity: to_f64(order.order_quantity),
+            order_amount: to_f64(order.order_amount),
+            reduce_only: order.reduce_only,
+        })
+    }
+
+    async fn cancel_order(&self, cancel_order: CancelOrder) -> anyhow::Result<CancelOrderRes> {
+        let mut state = self.state.lock().unwrap();
+
+        match state.orders.get_mut(&cancel_order.order_id) {
+            Some(order) if !order.status.is_terminal() => {
+                order.status = OrderStatus::Cancelled;
+                Ok(CancelOrderRes {
+                    success: true,
+                    status: CancelStatus::CancelSent,
+                })
+            }
+            _ => Err(WooError::OrderRejected(WooApiError {
+                code: WooApiError::RESOURCE_NOT_FOUND,
+                message: "Your order and symbol are not valid or already canceled.".to_string(),
+                request_id: None,
+            })
+            .into()),
+        }
+    }
+
+    async fn get_order_by_id(&self, order_id: OrderId) -> anyhow::Result<OrderDetail> {
+        self.state
+            .lock()
+            .unwrap()
+            .orders
+            .get(&order_id)
+            .cloned()
+            .ok_or_else(|| WooError::NotFound(format!("no order with order_id {order_id}")).into())
+    }
+
+    async fn get_orders(&self, filter: GetOrder) -> anyhow::Result<GetOrderRes> {
+        let state = self.state.lock().unwrap();
+        let rows = state
+            .orders
+            .values()
+            .map(|order| Row::from_detail(order, false));
+
+        Ok(page_of_orders(&filter, rows))
+    }
+
+    /// Always empty, the mock keeps no balances.
+    async fn get_holding(&self) -> anyhow::Result<HoldingRes> {
+        Ok(HoldingRes {
+            success: true,
+            holding: HashMap::new(),
+        })
+    }
+
+    /// Always empty, the mock keeps no positions.
+    async fn get_positions(&self) -> anyhow::Result<PositionsRes> {
+        Ok(PositionsRes {
+            success: true,
+            free_collateral: None,
+            total_collateral: None,
+            positions: Vec::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::woo_data_structs::OrderSize;
+    use rust_decimal_macros::dec;
+
+    /// Strategy code only sees the trait, so the same function runs against
+    /// the real client.
+    async fn place_bid(client: &impl WooClient, price: Decimal) -> OrderId {
+        let order = WooOrder::builder("SPOT_BTC_USDT", "BUY", "LIMIT")
+            .price(price)
+            .size(OrderSize::Quantity(dec!(0.5)))
+            .build();
+        client.create_order(order).await.unwrap().order_id
+    }
+
+    #[tokio::test]
+    async fn limit_order_fills_once_price_crosses() {
+        let exchange = MockExchange::new();
+        exchange.set_market_price("SPOT_BTC_USDT", dec!(9100));
+
+        let order_id = place_bid(&exchange, dec!(9000)).await;
+        let order = exchange.get_order_by_id(order_id).await.unwrap();
+        assert_eq!(order.status, OrderStatus::New);
+
+        exchange.set_market_price("SPOT_BTC_USDT", dec!(8999));
+        let order = exchange.get_order_by_id(order_id).await.unwrap();
+        assert_eq!(order.status, OrderStatus::Filled);
+        assert_eq!(order.executed, 0.5);
+        assert_eq!(order.average_executed_price, Some(9000.0));
+    }
+
+    #[tokio::test]
+    async fn filled_order_can_not_be_cancelled() {
+        let exchange = MockExchange::new();
+        exchange.set_market_price("SPOT_BTC_USDT", dec!(8000));
+
+        let order_id = place_bid(&exchange, dec!(9000)).await;
+        let cancel = CancelOrder {
+            order_id,
+            symbol: "SPOT_BTC_USDT".to_string(),
+        };
+
+        assert!(exchange.cancel_order(cancel).await.is_err());
+    }
+}
@@ -0,0 +1,339 @@
+use crate::client::WooClient;
+use crate::error::WooError;
+use crate::woo::Woo;
+use crate::woo_data_structs::{
+    CancelOrder, CancelOrderByClientId, CancelOrderRes, GetOrder, GetOrderRes, HoldingRes,
+    OrderDetail, OrderId, PositionsRes, SendOrderRes, WooOrder,
+};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Which calls [`WooPair`] copies to staging. Anything not listed here, reads
+/// and above all anything moving funds, only ever goes to production.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MirrorConfig {
+    pub create_order: bool,
+    pub cancel_order: bool,
+    /// Appended to the `order_tag` of mirrored orders, so they stand out on
+    /// the staging account.
+    pub tag_suffix: String,
+}
+
+impl Default for MirrorConfig {
+    fn default() -> Self {
+        Self {
+            create_order: true,
+            cancel_order: true,
+            tag_suffix: "shadow".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Shadow {
+    /// Production order id to the id of its copy on staging.
+    order_ids: Mutex<HashMap<OrderId, OrderId>>,
+    errors: AtomicU64,
+}
+
+/// Shadow trading: sends every order to production and, in the background,
+/// a copy of it to staging to validate behaviour changes against.
+///
+/// Only the production result is ever returned. Staging calls are fire and
+/// forget, their failures are logged and counted in
+/// [`WooPair::mirror_errors`] and never reach the caller.
+#[derive(Debug, Clone)]
+pub struct WooPair {
+    production: Woo,
+    staging: Woo,
+    config: MirrorConfig,
+    shadow: Arc<Shadow>,
+}
+
+impl WooPair {
+    pub fn new(production: Woo, staging: Woo, config: MirrorConfig) -> Self {
+        Self {
+            production,
+            staging,
+            config,
+            shadow: Arc::default(),
+        }
+    }
+
+    pub fn production(&self) -> &Woo {
+        &self.production
+    }
+
+    pub fn staging(&self) -> &Woo {
+        &self.staging
+    }
+
+    /// How many mirrored calls failed on staging.
+    pub fn mirror_errors(&self) -> u64 {
+        self.shadow.errors.load(Ordering::Relaxed)
+    }
+
+    pub async fn create_order(&self, order: WooOrder) -> Result<SendOrderRes, WooError> {
+        let mirrored = self
+            .config
+            .create_order
+            .then(|| self.mirrored(order.clone()));
+        let res = self.production.create_order(order).await;
+
+        // only orders production took are worth comparing against
+        if let (Some(mirrored), Ok(res)) = (mirrored, &res) {
+            let production_id = res.order_id;
+            self.mirror("create_order", move |staging, shadow| async move {
+                let res = staging.create_order(mirrored).await?;
+                shadow
+                    .order_ids
+                    .lock()
+                    .unwrap()
+                    .insert(production_id, res.order_id);
+                Ok(())
+            });
+        }
+
+        res
+    }
+
+    /// Staging gets the cancel only if the copy of the order was placed there
+    /// by the time production acknowledged it.
+    pub async fn cancel_order(
+        &self,
+        cancel_order: CancelOrder,
+    ) -> Result<CancelOrderRes, WooError> {
+        let res = self.production.cancel_order(cancel_order.clone()).await;
+
+        if self.config.cancel_order && res.is_ok() {
+            let staging_id = self
+                .shadow
+                .order_ids
+                .lock()
+                .unwrap()
+                .remove(&cancel_order.order_id);
+            if let Some(order_id) = staging_id {
+                let cancel_order = CancelOrder {
+                    order_id,
+                    ..cancel_order
+                };
+                self.mirror("cancel_order", move |staging, _| async move {
+                    staging.cancel_order(cancel_order).await?;
+                    Ok(())
+                });
+            }
+        }
+
+        res
+    }
+
+    /// Mirrored orders keep their client order id, so this cancel is copied
+    /// as is.
+    pub async fn cancel_order_by_client_id(
+        &self,
+        cancel_order: CancelOrderByClientId,
+    ) -> Result<CancelOrderRes, WooError> {
+        let res = self
+            .production
+            .cancel_order_by_client_id(cancel_order.clone())
+            .await;
+
+        if self.config.cancel_order && res.is_ok() {
+            self.mirror("cancel_order_by_client_id", move |staging, _| async move {
+                staging.cancel_order_by_client_id(cancel_order).await?;
+                Ok(())
+            });
+        }
+
+        res
+    }
+
+    fn mirrored(&self, mut order: WooOrder) -> WooOrder {
+        let suffix = &self.config.tag_suffix;
+        order.order_tag = Some(match order.order_tag {
+            Some(tag) => format!("{tag}-{suffix}"),
+            None => suffix.clone(),
+        });
+        order
+    }
+
+    fn mirror<F, Fut>(&self, call: &'static str, mirror: F)
+    where
+        F: FnOnce(Woo, Arc<Shadow>) -> Fut,
+        Fut: Future<Output = Result<(), WooError>> + Send + 'static,
+    {
+        let shadow = Arc::clone(&self.shadow);
+        let mirrored = mirror(self.staging.clone(), Arc::clone(&shadow));
+        tokio::spawn(async move {
+            if let Err(err) = mirrored.await {
+                let errors = shadow.errors.fetch_add(1, Ordering::Relaxed) + 1;
+                tracing::warn!(call, errors, %err, "mirroring to staging failed");
+            }
+        });
+    }
+}
+
+impl WooClient for WooPair {
+    async fn create_order(&self, order: WooOrder) -> anyhow::Result<SendOrderRes> {
+        Ok(WooPair::create_order(self, order).await?)
+    }
+
+    async fn cancel_order(&self, cancel_order: CancelOrder) -> anyhow::Result<CancelOrderRes> {
+        Ok(WooPair::cancel_order(self, cancel_order).await?)
+    }
+
+    async fn get_order_by_id(&self, order_id: OrderId) -> anyhow::Result<OrderDetail> {
+        self.production.get_order(order_id).await
+    }
+
+    async fn get_orders(&self, filter: GetOrder) -> anyhow::Result<GetOrderRes> {
+        self.production.get_orders(filter).await
+    }
+
+    async fn get_holding(&self) -> anyhow::Result<HoldingRes> {
+        self.production.get_holding().await
+    }
+
+    async fn get_positions(&self) -> anyhow::Result<PositionsRes> {
+        self.production.get_positions().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{mock_woo, ok, order_res};
+    use rust_decimal_macros::dec;
+    use std::time::Duration;
+    use wiremock::matchers::{body_string_contains, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn order() -> WooOrder {
+        let mut order = WooOrder::post_only("SPOT_BTC_USDT", "BUY", dec!(9000), dec!(0.1));
+        order.order_tag = Some("mm".to_string());
+        order
+    }
+
+    async fn wait_for_requests(server: &MockServer, count: usize) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while server.received_requests().await.unwrap().len() < count {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn mirrors_orders_and_their_cancels_to_staging() {
+        let (production, staging) = (MockServer::start().await, MockServer::start().await);
+        Mock::given(method("POST"))
+            .and(path("/v1/order"))
+            .respond_with(ok(order_res(1)))
+            .mount(&production)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/order"))
+            .and(body_string_contains("order_tag=mm-shadow"))
+            .respond_with(ok(order_res(901)))
+            .mount(&staging)
+            .await;
+        let cancelled = ResponseTemplate::new(200)
+            .set_body_json(serde_json::json!({ "success": true, "status": "CANCEL_SENT" }));
+        Mock::given(method("DELETE"))
+            .and(path("/v1/order"))
+            .respond_with(cancelled.clone())
+            .mount(&production)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/v1/order"))
+            .respond_with(cancelled)
+            .mount(&staging)
+            .await;
+
+        let pair = WooPair::new(
+            mock_woo(&production),
+            mock_woo(&staging),
+            MirrorConfig::default(),
+        );
+        let res = pair.create_order(order()).await.unwrap();
+        assert_eq!(res.order_id, OrderId(1));
+        wait_for_requests(&staging, 1).await;
+        // the staging id only becomes known after its response is handled
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while pair.shadow.order_ids.lock().unwrap().is_empty() {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        pair.cancel_order(CancelOrder {
+            order_id: OrderId(1),
+            symbol: "SPOT_BTC_USDT".to_string(),
+        })
+        .await
+        .unwrap();
+        wait_for_requests(&staging, 2).await;
+
+        let requests = staging.received_requests().await.unwrap();
+        assert_eq!(
+            requests[1].url.query(),
+            Some("order_id=901&symbol=SPOT_BTC_USDT")
+        );
+        assert_eq!(pair.mirror_errors(), 0);
+    }
+
+    #[tokio::test]
+    async fn staging_failures_only_count() {
+        let (production, staging) = (MockServer::start().await, MockServer::start().await);
+        Mock::given(method("POST"))
+            .and(path("/v1/order"))
+            .respond_with(ok(order_res(1)))
+            .mount(&production)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/order"))
+            .respond_with(ResponseTemplate::new(500).set_body_string("down"))
+            .mount(&staging)
+            .await;
+
+        let pair = WooPair::new(
+            mock_woo(&production),
+            mock_woo(&staging),
+            MirrorConfig::default(),
+        );
+        let res = pair.create_order(order()).await.unwrap();
+
+        assert_eq!(res.order_id, OrderId(1));
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while pair.mirror_errors() == 0 {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn unmirrored_calls_stay_on_production() {
+        let (production, staging) = (MockServer::start().await, MockServer::start().await);
+        Mock::given(method("POST"))
+            .and(path("/v1/order"))
+            .respond_with(ok(order_res(1)))
+            .mount(&production)
+            .await;
+
+        let config = MirrorConfig {
+            create_order: false,
+            ..MirrorConfig::default()
+        };
+        let pair = WooPair::new(mock_woo(&production), mock_woo(&staging), config);
+        pair.create_order(order()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert!(staging.received_requests().await.unwrap().is_empty());
+    }
+}
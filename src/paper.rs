@@ -0,0 +1,717 @@
+use crate::accounting::{CostBasis, InitialPosition, PnlCalculator, PnlReport};
+use crate::client::{page_of_orders, WooClient};
+use crate::error::{WooApiError, WooError, WooErrorCode};
+use crate::fee::SymbolFee;
+use crate::symbol::{Market, Symbol};
+use crate::woo::Woo;
+use crate::woo_data_structs::{
+    CancelOrder, CancelOrderRes, CancelStatus, ClientOrderId, GetOrder, GetOrderRes, HoldingRes,
+    OrderDetail, OrderId, OrderStatus, OrderType, Position, PositionsRes, Row, SendOrderRes, Trade,
+    WooOrder,
+};
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+/// Best bid and ask of a symbol, what paper orders fill against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PaperQuote {
+    pub bid: Decimal,
+    pub ask: Decimal,
+}
+
+/// Paper trading: a [`WooClient`] keeping balances, positions and orders in
+/// memory, so strategy code written against the trait runs unchanged without
+/// risking funds.
+///
+/// Prices come from [`PaperWoo::set_price`] or [`PaperWoo::set_quote`], or
+/// from WOO's public order book with [`PaperWoo::refresh_quote`]. An order
+/// that crosses the quote when placed fills at once, at the quote, and pays
+/// the taker fee; one that rests fills at its limit, as a maker, once the
+/// quote moves through it. Orders fill in full, the quote is taken to be
+/// deep enough. Fees are charged in the quote currency.
+///
+/// Spot orders need the balance they spend, net of what resting orders have
+/// reserved; perpetuals are not margin checked, their realized PnL and fees
+/// settle into the quote currency balance.
+#[derive(Debug)]
+pub struct PaperWoo {
+    fee: SymbolFee,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    next_order_id: u64,
+    next_trade_id: u64,
+    quotes: HashMap<String, PaperQuote>,
+    orders: BTreeMap<OrderId, PaperOrder>,
+    balances: HashMap<String, Decimal>,
+    /// Signed quantity and average entry price of each perpetual.
+    positions: HashMap<String, (Decimal, Decimal)>,
+    fills: Vec<Trade>,
+}
+
+#[derive(Debug, Clone)]
+struct PaperOrder {
+    order_id: OrderId,
+    symbol: Symbol,
+    buy: bool,
+    order_type: OrderType,
+    /// `None` for market orders.
+    price: Option<Decimal>,
+    quantity: Decimal,
+    amount: Option<Decimal>,
+    client_order_id: Option<ClientOrderId>,
+    order_tag: Option<String>,
+    reduce_only: bool,
+    status: OrderStatus,
+    executed: Decimal,
+    average_price: Option<Decimal>,
+    fee: Decimal,
+    created_time: DateTime<Utc>,
+    updated_time: DateTime<Utc>,
+}
+
+impl PaperOrder {
+    fn side(&self) -> &'static str {
+        if self.buy {
+            "BUY"
+        } else {
+            "SELL"
+        }
+    }
+
+    /// Whether `quote` is through the order: its ask at or below a buy, its
+    /// bid at or above a sell.
+    fn crossed_by(&self, quote: PaperQuote) -> bool {
+        let touch = if self.buy { quote.ask } else { quote.bid };
+        self.price.is_none_or(|price| {
+            if self.buy {
+                touch <= price
+            } else {
+                touch >= price
+            }
+        })
+    }
+
+    fn detail(&self) -> OrderDetail {
+        OrderDetail {
+            symbol: self.symbol.to_string(),
+            side: self.side().to_string(),
+            status: self.status,
+            order_id: self.order_id,
+            client_order_id: self.client_order_id.clone(),
+            order_tag: self.order_tag.clone(),
+            r#type: self.order_type.as_str().to_string(),
+            price: self.price,
+            quantity: Some(self.quantity),
+            amount: self.amount,
+            executed: self.executed,
+            average_executed_price: self.average_price,
+            total_fee: self.fee,
+            fee_asset: Some(self.symbol.quote.clone()),
+            created_time: self.created_time,
+        }
+    }
+}
+
+fn rejected(code: WooErrorCode, message: String) -> WooError {
+    WooError::OrderRejected(WooApiError {
+        code: code.code(),
+        message,
+        request_id: None,
+    })
+}
+
+impl PaperWoo {
+    /// An empty account charging `fee` on every fill, see
+    /// [`SymbolFee::from_bps`].
+    pub fn new(fee: SymbolFee) -> Self {
+        Self {
+            fee,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Credits `amount` of `token`, e.g. the starting balance.
+    pub fn deposit(&self, token: &str, amount: Decimal) {
+        let mut state = self.state.lock().unwrap();
+        *state.balances.entry(token.to_string()).or_default() += amount;
+    }
+
+    /// [`PaperWoo::set_quote`] with no spread.
+    pub fn set_price(&self, symbol: &str, price: Decimal) {
+        self.set_quote(
+            symbol,
+            PaperQuote {
+                bid: price,
+                ask: price,
+            },
+        );
+    }
+
+    /// Moves the market of `symbol` to `quote`, filling every resting order
+    /// it crosses.
+    pub fn set_quote(&self, symbol: &str, quote: PaperQuote) {
+        let mut state = self.state.lock().unwrap();
+        state.quotes.insert(symbol.to_string(), quote);
+
+        let crossed: Vec<OrderId> = state
+            .orders
+            .values()
+            .filter(|order| order.symbol.to_string() == symbol && !order.status.is_terminal())
+            .filter(|order| order.crossed_by(quote))
+            .map(|order| order.order_id)
+            .collect();
+        for order_id in crossed {
+            let mut order = state
+                .orders
+                .remove(&order_id)
+                .expect("crossed order exists");
+            let price = order.price.expect("resting orders have a price");
+            state.execute(&self.fee, &mut order, price, true);
+            state.orders.insert(order_id, order);
+        }
+    }
+
+    /// Sets the quote of `symbol` to the top of WOO's public order book.
+    pub async fn refresh_quote(&self, woo: &Woo, symbol: &str) -> anyhow::Result<()> {
+        let book = woo.get_orderbook(symbol, Some(1)).await?;
+        let (Some(bid), Some(ask)) = (book.bids.first(), book.asks.first()) else {
+            anyhow::bail!("the {symbol} order book has an empty side");
+        };
+
+        self.set_quote(
+            symbol,
+            PaperQuote {
+                bid: bid.price,
+                ask: ask.price,
+            },
+        );
+        Ok(())
+    }
+
+    /// Every fill so far, oldest first, as `v1/client/trades` would list them.
+    pub fn fills(&self) -> Vec<Trade> {
+        self.state.lock().unwrap().fills.clone()
+    }
+
+    /// Realized PnL, fees and volume of the fills on `symbol`, on an average
+    /// cost basis.
+    pub fn pnl(&self, symbol: &str) -> PnlReport {
+        let state = self.state.lock().unwrap();
+        let mut pnl = PnlCalculator::new(CostBasis::AverageCost, InitialPosition::default());
+        for fill in state.fills.iter().filter(|fill| fill.symbol == symbol) {
+            pnl.add(fill);
+        }
+
+        pnl.finish()
+    }
+
+    fn place(&self, order: WooOrder) -> Result<SendOrderRes, WooError> {
+        let order_type: OrderType = order.wire_order_type()?.parse()?;
+        let symbol: Symbol = order.symbol.parse()?;
+        let buy = match order.side.as_str() {
+            "BUY" => true,
+            "SELL" => false,
+            side => return Err(WooError::InvalidOrder(format!("unknown side {side:?}"))),
+        };
+        if let Some(client_order_id) = &order.client_order_id {
+            client_order_id.validate()?;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        let quote = state.quotes.get(&order.symbol).copied();
+        let no_price = || {
+            WooError::NotFound(format!(
+                "price of {}, set one with PaperWoo::set_price",
+                order.symbol
+            ))
+        };
+        let price = match order_type {
+            OrderType::Market => None,
+            OrderType::Ask => Some(quote.ok_or_else(no_price)?.ask),
+            OrderType::Bid => Some(quote.ok_or_else(no_price)?.bid),
+            _ => Some(order.order_price.ok_or_else(|| {
+                WooError::InvalidOrder(format!("{} order without a price", order.order_type))
+            })?),
+        };
+        let touch = quote.map(|quote| if buy { quote.ask } else { quote.bid });
+        let quantity = match (order.order_quantity, order.order_amount, price) {
+            (Some(quantity), None, _) => quantity,
+            (None, Some(amount), None) => amount / touch.ok_or_else(no_price)?,
+            _ => {
+                return Err(WooError::InvalidOrder(
+                    "order needs either a quantity, or an amount for market orders".to_string(),
+                ))
+            }
+        };
+        if quantity <= Decimal::ZERO {
+            return Err(WooError::InvalidOrder(format!(
+                "quantity {quantity} is not positive"
+            )));
+        }
+
+        let now = Utc::now();
+        let mut paper = PaperOrder {
+            order_id: OrderId(state.next_order_id + 1),
+            symbol,
+            buy,
+            order_type,
+            price,
+            quantity,
+            amount: order.order_amount,
+            client_order_id: order.client_order_id.clone(),
+            order_tag: order.order_tag.clone(),
+            reduce_only: order.reduce_only.unwrap_or(false),
+            status: OrderStatus::New,
+            executed: Decimal::ZERO,
+            average_price: None,
+            fee: Decimal::ZERO,
+            created_time: now,
+            updated_time: now,
+        };
+        let crossed = quote.is_some_and(|quote| paper.crossed_by(quote));
+        if price.is_none() && !crossed {
+            return Err(no_price());
+        }
+        if order_type == OrderType::PostOnly && crossed {
+            return Err(WooError::PostOnlyRejected(WooApiError {
+                code: WooErrorCode::RpcRejected.code(),
+                message: "post only order would take liquidity".to_string(),
+                request_id: None,
+            }));
+        }
+        state.check_reduce_only(&paper)?;
+        let reference_price = match (crossed, touch, price) {
+            (true, Some(touch), _) => touch,
+            (_, _, Some(price)) => price,
+            _ => return Err(no_price()),
+        };
+        state.check_funds(&self.fee, &paper, reference_price)?;
+
+        state.next_order_id += 1;
+        if crossed {
+            state.execute(&self.fee, &mut paper, reference_price, false);
+        } else if matches!(order_type, OrderType::Ioc | OrderType::Fok) {
+            paper.status = OrderStatus::Cancelled;
+        }
+        let order_id = paper.order_id;
+        state.orders.insert(order_id, paper);
+
+        Ok(SendOrderRes {
+            success: true,
+            timestamp: now,
+            order_id,
+            order_type: order_type.as_str().to_string(),
+            client_order_id: order.client_order_id.unwrap_or_default(),
+            order_price: order.order_price.and_then(|price| price.to_f64()),
+            order_quantity: order.order_quantity.and_then(|quantity| quantity.to_f64()),
+            order_amount: order.order_amount.and_then(|amount| amount.to_f64()),
+            reduce_only: order.reduce_only,
+        })
+    }
+}
+
+impl State {
+    fn balance(&self, token: &str) -> Decimal {
+        self.balances.get(token).copied().unwrap_or_default()
+    }
+
+    /// What resting spot orders hold of `token`: the quote currency a buy
+    /// will spend, the base currency a sell will deliver.
+    fn reserved(&self, fee: &SymbolFee, token: &str) -> Decimal {
+        self.orders
+            .values()
+            .filter(|order| order.symbol.market == Market::Spot && !order.status.is_terminal())
+            .map(|order| {
+                let remaining = order.quantity - order.executed;
+                match order.price {
+                    Some(price) if order.buy && order.symbol.quote == token => {
+                        remaining * price * (Decimal::ONE + fee.maker_rate)
+                    }
+                    _ if !order.buy && order.symbol.base == token => remaining,
+                    _ => Decimal::ZERO,
+                }
+            })
+            .sum()
+    }
+
+    fn check_funds(
+        &self,
+        fee: &SymbolFee,
+        order: &PaperOrder,
+        price: Decimal,
+    ) -> Result<(), WooError> {
+        if order.symbol.market != Market::Spot {
+            return Ok(());
+        }
+
+        let (token, needed) = if order.buy {
+            let rate = fee.maker_rate.max(fee.taker_rate);
+            let cost = order.quantity * price * (Decimal::ONE + rate);
+            (&order.symbol.quote, cost)
+        } else {
+            (&order.symbol.base, order.quantity)
+        };
+        let available = self.balance(token) - self.reserved(fee, token);
+        if needed > available {
+            return Err(rejected(
+                WooErrorCode::RiskTooHigh,
+                format!("not enough {token}: {needed} needed, {available} available"),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn check_reduce_only(&self, order: &PaperOrder) -> Result<(), WooError> {
+        if !order.reduce_only {
+            return Ok(());
+        }
+
+        let (holding, _) = self
+            .positions
+            .get(&order.symbol.to_string())
+            .copied()
+            .unwrap_or_default();
+        let reduces = if order.buy {
+            holding.is_sign_negative() && order.quantity <= -holding
+        } else {
+            holding.is_sign_positive() && order.quantity <= holding
+        };
+        if holding.is_zero() || !reduces {
+            return Err(rejected(
+                WooErrorCode::InvalidParam,
+                format!(
+                    "reduce only order for {} would not reduce a position of {holding}",
+                    order.quantity
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Fills what is left of `order` at `price`, settling balances, position
+    /// and fees.
+    fn execute(&mut self, fee: &SymbolFee, order: &mut PaperOrder, price: Decimal, maker: bool) {
+        let quantity = order.quantity - order.executed;
+        let notional = quantity * price;
+        let fee = notional
+            * if maker {
+                fee.maker_rate
+            } else {
+                fee.taker_rate
+            };
+        let (base, quote) = (order.symbol.base.clone(), order.symbol.quote.clone());
+
+        match order.symbol.market {
+            Market::Spot if order.buy => {
+                *self.balances.entry(base).or_default() += quantity;
+                *self.balances.entry(quote).or_default() -= notional + fee;
+            }
+            Market::Spot => {
+                *self.balances.entry(base).or_default() -= quantity;
+                *self.balances.entry(quote).or_default() += notional - fee;
+            }
+            Market::Perp => {
+                let signed = if order.buy { quantity } else { -quantity };
+                let realized = self.trade_position(&order.symbol.to_string(), signed, price);
+                *self.balances.entry(quote).or_default() += realized - fee;
+            }
+        }
+
+        let now = Utc::now();
+        order.executed = order.quantity;
+        order.average_price = Some(price);
+        order.fee += fee;
+        order.status = OrderStatus::Filled;
+        order.updated_time = now;
+
+        self.next_trade_id += 1;
+        self.fills.push(Trade {
+            id: self.next_trade_id,
+            symbol: order.symbol.to_string(),
+            order_id: order.order_id,
+            side: order.side().to_string(),
+            executed_price: price,
+            executed_quantity: quantity,
+            fee,
+            fee_asset: order.symbol.quote.clone(),
+            executed_timestamp: format!("{:.3}", now.timestamp_millis() as f64 / 1000.0),
+            is_maker: maker,
+        });
+    }
+
+    /// Adds `quantity` (negative when selling) at `price` to the position on
+    /// `symbol` and returns the PnL realized by whatever it closed.
+    fn trade_position(&mut self, symbol: &str, quantity: Decimal, price: Decimal) -> Decimal {
+        let (holding, average) = self.positions.entry(symbol.to_string()).or_default();
+        if holding.is_zero() || holding.is_sign_positive() == quantity.is_sign_positive() {
+            *average = (*holding * *average + quantity * price) / (*holding + quantity);
+            *holding += quantity;
+            return Decimal::ZERO;
+        }
+
+        let closed = quantity.abs().min(holding.abs());
+        let direction = if holding.is_sign_positive() {
+            Decimal::ONE
+        } else {
+            Decimal::NEGATIVE_ONE
+        };
+        let realized = closed * (price - *average) * direction;
+        let flipped = quantity.abs() > holding.abs();
+        *holding += quantity;
+        if flipped {
+            *average = price;
+        } else if holding.is_zero() {
+            *average = Decimal::ZERO;
+        }
+
+        realized
+    }
+}
+
+impl WooClient for PaperWoo {
+    async fn create_order(&self, order: WooOrder) -> anyhow::Result<SendOrderRes> {
+        Ok(self.place(order)?)
+    }
+
+    async fn cancel_order(&self, cancel_order: CancelOrder) -> anyhow::Result<CancelOrderRes> {
+        let mut state = self.state.lock().unwrap();
+
+        match state.orders.get_mut(&cancel_order.order_id) {
+            Some(order) if !order.status.is_terminal() => {
+                order.status = OrderStatus::Cancelled;
+                order.updated_time = Utc::now();
+                Ok(CancelOrderRes {
+                    success: true,
+                    status: CancelStatus::CancelSent,
+                })
+            }
+            _ => Err(rejected(
+                WooErrorCode::ResourceNotFound,
+                "Your order and symbol are not valid or already canceled.".to_string(),
+            )
+            .into()),
+        }
+    }
+
+    async fn get_order_by_id(&self, order_id: OrderId) -> anyhow::Result<OrderDetail> {
+        self.state
+            .lock()
+            .unwrap()
+            .orders
+            .get(&order_id)
+            .map(PaperOrder::detail)
+            .ok_or_else(|| WooError::NotFound(format!("no order with order_id {order_id}")).into())
+    }
+
+    async fn get_orders(&self, filter: GetOrder) -> anyhow::Result<GetOrderRes> {
+        let state = self.state.lock().unwrap();
+        let rows = state.orders.values().map(|order| Row {
+            updated_time: order.updated_time,
+            ..Row::from_detail(&order.detail(), order.reduce_only)
+        });
+
+        Ok(page_of_orders(&filter, rows))
+    }
+
+    async fn get_holding(&self) -> anyhow::Result<HoldingRes> {
+        let state = self.state.lock().unwrap();
+        let holding = state
+            .balances
+            .iter()
+            .map(|(token, amount)| (token.clone(), amount.to_f64().unwrap_or_default()))
+            .collect();
+
+        Ok(HoldingRes {
+            success: true,
+            holding,
+        })
+    }
+
+    async fn get_positions(&self) -> anyhow::Result<PositionsRes> {
+        let state = self.state.lock().unwrap();
+        let pending = |symbol: &str, buy: bool| -> Decimal {
+            state
+                .orders
+                .values()
+                .filter(|order| order.symbol.to_string() == symbol && order.buy == buy)
+                .filter(|order| !order.status.is_terminal())
+                .map(|order| order.quantity - order.executed)
+                .sum()
+        };
+        let timestamp = Utc::now();
+
+        let positions = state
+            .positions
+            .iter()
+            .filter(|(_, (holding, _))| !holding.is_zero())
+            .map(|(symbol, (holding, average))| {
+                let mark = state
+                    .quotes
+                    .get(symbol)
+                    .map_or(*average, |quote| (quote.bid + quote.ask) / Decimal::TWO);
+                Position {
+                    symbol: symbol.clone(),
+                    holding: holding.to_f64().unwrap_or_default(),
+                    pending_long_qty: pending(symbol, true).to_f64(),
+                    pending_short_qty: pending(symbol, false).to_f64(),
+                    average_open_price: average.to_f64().unwrap_or_default(),
+                    mark_price: mark.to_f64().unwrap_or_default(),
+                    est_liq_price: None,
+                    timestamp: Some(timestamp),
+                }
+            })
+            .collect();
+
+        Ok(PositionsRes {
+            success: true,
+            free_collateral: None,
+            total_collateral: None,
+            positions,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::woo_data_structs::OrderSize;
+    use rust_decimal_macros::dec;
+
+    fn paper() -> PaperWoo {
+        let paper = PaperWoo::new(SymbolFee::from_bps(2, 5));
+        paper.deposit("USDT", dec!(10000));
+        paper
+    }
+
+    fn order(symbol: &str, side: &str, order_type: &str, quantity: Decimal) -> WooOrder {
+        WooOrder::builder(symbol, side, order_type)
+            .size(OrderSize::Quantity(quantity))
+            .build()
+    }
+
+    /// Strategy code only sees the trait, so it runs on paper unchanged.
+    async fn place_bid(client: &impl WooClient, price: Decimal) -> anyhow::Result<OrderId> {
+        let order = WooOrder {
+            order_price: Some(price),
+            ..order("SPOT_BTC_USDT", "BUY", "LIMIT", dec!(0.5))
+        };
+        Ok(client.create_order(order).await?.order_id)
+    }
+
+    async fn holding(paper: &PaperWoo, token: &str) -> f64 {
+        paper.get_holding().await.unwrap().holding[token]
+    }
+
+    #[tokio::test]
+    async fn resting_bid_fills_as_maker_and_market_sell_takes() {
+        let paper = paper();
+        paper.set_price("SPOT_BTC_USDT", dec!(9100));
+
+        let order_id = place_bid(&paper, dec!(9000)).await.unwrap();
+        let detail = paper.get_order_by_id(order_id).await.unwrap();
+        assert_eq!(detail.status, OrderStatus::New);
+
+        paper.set_quote(
+            "SPOT_BTC_USDT",
+            PaperQuote {
+                bid: dec!(8998),
+                ask: dec!(8999),
+            },
+        );
+        let detail = paper.get_order_by_id(order_id).await.unwrap();
+        assert_eq!(detail.status, OrderStatus::Filled);
+        assert_eq!(detail.average_executed_price, Some(dec!(9000)));
+        assert_eq!(holding(&paper, "BTC").await, 0.5);
+        // 4500 spent and 2 bps of it as maker fee
+        assert_eq!(holding(&paper, "USDT").await, 5499.1);
+
+        paper.set_price("SPOT_BTC_USDT", dec!(9200));
+        let sell = order("SPOT_BTC_USDT", "SELL", "MARKET", dec!(0.5));
+        paper.create_order(sell).await.unwrap();
+
+        let fills = paper.fills();
+        assert_eq!(fills.len(), 2);
+        assert!(fills[0].is_maker);
+        assert!(!fills[1].is_maker);
+        assert_eq!(fills[1].fee, dec!(2.3));
+        assert_eq!(holding(&paper, "USDT").await, 10096.8);
+        let pnl = paper.pnl("SPOT_BTC_USDT");
+        assert_eq!(pnl.realized_pnl, dec!(100));
+        assert_eq!(pnl.fees["USDT"], dec!(3.2));
+        assert_eq!(pnl.closing_position, dec!(0));
+    }
+
+    #[tokio::test]
+    async fn rejects_what_woo_would() {
+        let paper = paper();
+        paper.set_price("SPOT_BTC_USDT", dec!(9000));
+
+        let post_only = WooOrder {
+            order_price: Some(dec!(9100)),
+            ..order("SPOT_BTC_USDT", "BUY", "POST_ONLY", dec!(0.1))
+        };
+        let err = paper.create_order(post_only).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(WooError::PostOnlyRejected(_))
+        ));
+
+        // the first bid reserves what the second would need
+        place_bid(&paper, dec!(8000)).await.unwrap();
+        let too_big = WooOrder {
+            order_price: Some(dec!(8000)),
+            ..order("SPOT_BTC_USDT", "BUY", "LIMIT", dec!(0.8))
+        };
+        let err = paper.create_order(too_big).await.unwrap_err();
+        match err.downcast_ref() {
+            Some(WooError::OrderRejected(err)) => {
+                assert_eq!(err.code, WooErrorCode::RiskTooHigh.code());
+                assert!(err.message.starts_with("not enough USDT"));
+            }
+            other => panic!("expected a rejection, got {other:?}"),
+        }
+
+        let err = place_bid(&PaperWoo::new(SymbolFee::from_bps(0, 0)), dec!(8000))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not enough USDT"));
+    }
+
+    #[tokio::test]
+    async fn perp_position_settles_pnl_into_the_quote_balance() {
+        let paper = paper();
+        paper.set_price("PERP_BTC_USDT", dec!(9000));
+
+        let buy = order("PERP_BTC_USDT", "BUY", "MARKET", dec!(1));
+        paper.create_order(buy).await.unwrap();
+        let positions = paper.get_positions().await.unwrap().positions;
+        assert_eq!(positions[0].holding, 1.0);
+        assert_eq!(positions[0].average_open_price, 9000.0);
+
+        // selling more than the position holds doesn't reduce it
+        let flip = WooOrder {
+            reduce_only: Some(true),
+            ..order("PERP_BTC_USDT", "SELL", "MARKET", dec!(2))
+        };
+        assert!(paper.create_order(flip).await.is_err());
+
+        paper.set_price("PERP_BTC_USDT", dec!(9500));
+        let close = WooOrder {
+            reduce_only: Some(true),
+            ..order("PERP_BTC_USDT", "SELL", "MARKET", dec!(1))
+        };
+        paper.create_order(close).await.unwrap();
+
+        assert!(paper.get_positions().await.unwrap().positions.is_empty());
+        // 500 realized, less 4.5 and 4.75 of taker fees
+        assert_eq!(holding(&paper, "USDT").await, 10490.75);
+    }
+}
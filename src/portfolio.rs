@@ -0,0 +1,182 @@
+use crate::woo::Woo;
+use crate::woo_data_structs::{AccountInfo, Position, Row};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortfolioSection {
+    Balances,
+    Positions,
+    OpenOrders,
+    AccountInfo,
+}
+
+/// One endpoint of the snapshot that failed; the rest of it is still usable.
+#[derive(Debug)]
+pub struct SectionError {
+    pub section: PortfolioSection,
+    pub error: anyhow::Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct PositionSnapshot {
+    pub symbol: String,
+    pub holding: f64,
+    pub average_open_price: f64,
+    pub mark_price: f64,
+    pub unrealized_pnl: f64,
+}
+
+impl From<Position> for PositionSnapshot {
+    fn from(position: Position) -> Self {
+        Self {
+            unrealized_pnl: position.unrealized_pnl(),
+            symbol: position.symbol,
+            holding: position.holding,
+            average_open_price: position.average_open_price,
+            mark_price: position.mark_price,
+        }
+    }
+}
+
+/// Balances, positions, open orders and account settings as of one moment.
+/// Sections whose endpoint failed are left empty and listed in `errors`.
+#[derive(Debug, Default)]
+pub struct Portfolio {
+    pub holdings: HashMap<String, f64>,
+    pub positions: Vec<PositionSnapshot>,
+    pub open_orders: Vec<Row>,
+    /// Quote value of the unfilled part of every open order.
+    pub open_order_notional: Decimal,
+    pub free_collateral: Option<f64>,
+    pub account: Option<AccountInfo>,
+    pub errors: Vec<SectionError>,
+}
+
+impl Portfolio {
+    pub fn is_complete(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+impl Woo {
+    /// Fetches balances, positions, open orders and account info
+    /// concurrently and assembles them into one [`Portfolio`]. Only fails if
+    /// every one of them did.
+    pub async fn get_portfolio(&self) -> anyhow::Result<Portfolio> {
+        let (holding, positions, open_orders, account) = tokio::join!(
+            self.get_holding(),
+            self.get_positions(),
+            self.get_open_orders(None),
+            self.get_account_info(),
+        );
+
+        let mut portfolio = Portfolio::default();
+        let mut errors = Vec::new();
+        let mut failed = |section, error| errors.push(SectionError { section, error });
+
+        match holding {
+            Ok(holding) => portfolio.holdings = holding.holding,
+            Err(error) => failed(PortfolioSection::Balances, error),
+        }
+        match positions {
+            Ok(positions) => {
+                portfolio.free_collateral = positions.free_collateral;
+                portfolio.positions = positions.positions.into_iter().map(Into::into).collect();
+            }
+            Err(error) => failed(PortfolioSection::Positions, error),
+        }
+        match open_orders {
+            Ok(open_orders) => {
+                portfolio.open_order_notional = open_orders.iter().map(open_notional).sum();
+                portfolio.open_orders = open_orders;
+            }
+            Err(error) => failed(PortfolioSection::OpenOrders, error),
+        }
+        match account {
+            Ok(account) => portfolio.account = Some(account),
+            Err(error) => failed(PortfolioSection::AccountInfo, error),
+        }
+
+        if errors.len() == 4 {
+            let SectionError { error, .. } = errors.swap_remove(0);
+            return Err(error.context("every portfolio endpoint failed"));
+        }
+
+        portfolio.errors = errors;
+        Ok(portfolio)
+    }
+}
+
+fn open_notional(order: &Row) -> Decimal {
+    match order.amount {
+        // market orders sized in quote currency have no price
+        Some(amount) if order.price.is_zero() => amount,
+        _ => order.price * (order.quantity - order.executed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{mock_woo, mount, order_row, page, with};
+    use rust_decimal_macros::dec;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn failed_section_keeps_the_rest() {
+        let server = MockServer::start().await;
+
+        mount(
+            &server,
+            "GET",
+            "/v1/client/holding",
+            serde_json::json!({ "success": true, "holding": { "BTC": 1.5, "USDT": 1000.0 } }),
+        )
+        .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/positions"))
+            .respond_with(ResponseTemplate::new(502).set_body_string("Bad Gateway"))
+            .mount(&server)
+            .await;
+        mount(
+            &server,
+            "GET",
+            "/v1/orders",
+            page(
+                vec![with(
+                    order_row(1, "PARTIAL_FILLED"),
+                    serde_json::json!({
+                        "quantity": 0.3, "visible": 0.3, "executed": 0.1,
+                        "average_executed_price": 9000,
+                    }),
+                )],
+                1,
+                25,
+                1,
+            ),
+        )
+        .await;
+        mount(
+            &server,
+            "GET",
+            "/v1/client/info",
+            serde_json::json!({
+                "success": true,
+                "application": { "application_id": "app", "account": "main", "leverage": 5 },
+            }),
+        )
+        .await;
+
+        let woo = mock_woo(&server);
+        let portfolio = woo.get_portfolio().await.unwrap();
+
+        assert_eq!(portfolio.holdings["BTC"], 1.5);
+        assert_eq!(portfolio.open_order_notional, dec!(1800));
+        assert_eq!(portfolio.account.unwrap().account, "main");
+        assert!(portfolio.positions.is_empty());
+        assert_eq!(portfolio.errors.len(), 1);
+        assert_eq!(portfolio.errors[0].section, PortfolioSection::Positions);
+    }
+}
@@ -0,0 +1,134 @@
+use crate::latency::endpoint_key;
+use crate::woo::Woo;
+use reqwest::Method;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Endpoints [`Woo::with_public_cache`] caches: reference data that changes
+/// with listings and network maintenance, not with the market.
+const REFERENCE_ENDPOINTS: &[&str] = &[
+    "v1/public/info",
+    "v1/public/token",
+    "v1/public/token_network",
+];
+
+/// Public `GET` responses kept for a per-endpoint time to live, keyed by
+/// their full url so different query params are cached apart.
+#[derive(Debug, Default)]
+pub(crate) struct PublicCache {
+    ttls: HashMap<String, Duration>,
+    responses: Mutex<HashMap<String, (Instant, serde_json::Value)>>,
+}
+
+impl PublicCache {
+    /// How long responses of `path` are kept, `None` if they aren't.
+    pub(crate) fn ttl(&self, method: &Method, path: &str) -> Option<Duration> {
+        if method != Method::GET {
+            return None;
+        }
+
+        self.ttls.get(&endpoint_key(path)).copied()
+    }
+
+    pub(crate) fn get(&self, url: &str, ttl: Duration) -> Option<serde_json::Value> {
+        match self.responses.lock().unwrap().get(url) {
+            Some((fetched_at, response)) if fetched_at.elapsed() < ttl => Some(response.clone()),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn put(&self, url: String, response: serde_json::Value) {
+        self.responses
+            .lock()
+            .unwrap()
+            .insert(url, (Instant::now(), response));
+    }
+}
+
+impl Woo {
+    /// Serves `get_tokens`, `get_token_networks` and `get_exchange_info` from
+    /// memory for `ttl` after each response, instead of asking again. Only
+    /// successful responses are kept. The cache is shared with clones made
+    /// after this call.
+    pub fn with_public_cache(self, ttl: Duration) -> Self {
+        REFERENCE_ENDPOINTS.iter().fold(self, |woo, endpoint| {
+            woo.with_public_cache_ttl(endpoint, ttl)
+        })
+    }
+
+    /// Caches public `GET`s of `endpoint` (e.g. `v1/public/info`) for `ttl`,
+    /// alongside whatever is cached already. Starts the cache afresh.
+    pub fn with_public_cache_ttl(mut self, endpoint: &str, ttl: Duration) -> Self {
+        let mut ttls = self
+            .public_cache
+            .as_ref()
+            .map(|cache| cache.ttls.clone())
+            .unwrap_or_default();
+        ttls.insert(endpoint_key(endpoint), ttl);
+
+        self.public_cache = Some(Arc::new(PublicCache {
+            ttls,
+            responses: Mutex::default(),
+        }));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mock_woo;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn repeated_calls_within_the_ttl_are_served_from_memory() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/public/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "rows": [{
+                    "token": "BTC", "fullname": "Bitcoin", "decimals": 8,
+                    "balance_token": "BTC", "created_time": "1575014248.99",
+                    "updated_time": "1575014248.99",
+                }],
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let woo = mock_woo(&server).with_public_cache(Duration::from_secs(60));
+
+        let first = woo.get_tokens().await.unwrap();
+        let second = woo.clone().get_tokens().await.unwrap();
+
+        assert_eq!(first[0].token, "BTC");
+        assert_eq!(second[0].fullname, "Bitcoin");
+    }
+
+    #[tokio::test]
+    async fn query_params_are_part_of_the_key() {
+        let server = MockServer::start().await;
+        for max_level in ["1", "5"] {
+            Mock::given(method("GET"))
+                .and(path("/v1/public/orderbook/SPOT_BTC_USDT"))
+                .and(query_param("max_level", max_level))
+                .respond_with(ResponseTemplate::new(200).set_body_string(
+                    r#"{"success":true,"asks":[{"price":9000.5,"quantity":1}],"bids":[{"price":8999,"quantity":1}],"timestamp":1578565539808}"#,
+                ))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+        let woo = mock_woo(&server)
+            .with_public_cache_ttl("v1/public/orderbook/SPOT_BTC_USDT", Duration::from_secs(60));
+
+        for max_level in [1, 5, 1, 5] {
+            woo.get_orderbook("SPOT_BTC_USDT", Some(max_level))
+                .await
+                .unwrap();
+        }
+    }
+}
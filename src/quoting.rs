@@ -0,0 +1,154 @@
+use crate::error::WooError;
+use crate::woo::Woo;
+use crate::woo_data_structs::{OrderType, SendOrderRes, WooOrder};
+use rust_decimal::Decimal;
+
+/// A `POST_ONLY` order that made it onto the book.
+#[derive(Debug)]
+pub struct PostOnlyPlacement {
+    /// 1 for the order as given, higher when it had to be re-priced.
+    pub attempt: u32,
+    pub price: Decimal,
+    pub order: SendOrderRes,
+}
+
+impl Woo {
+    /// Submits `order` as `POST_ONLY` and, each time WOO refuses it for
+    /// crossing the book, moves its price `tick_offset` behind the touch of
+    /// a fresh orderbook snapshot and tries again, up to `max_attempts` in
+    /// all. `tick_offset` defaults to the symbol's price tick.
+    pub async fn create_post_only_with_reprice(
+        &self,
+        mut order: WooOrder,
+        max_attempts: u32,
+        tick_offset: Option<Decimal>,
+    ) -> anyhow::Result<PostOnlyPlacement> {
+        order.order_type = OrderType::PostOnly.as_str().to_string();
+        let mut attempt = 1;
+
+        loop {
+            let err = match self.place_order(&order).await {
+                Ok(placed) => {
+                    return Ok(PostOnlyPlacement {
+                        attempt,
+                        price: order.order_price.unwrap_or_default(),
+                        order: placed,
+                    })
+                }
+                Err(WooError::PostOnlyRejected(err)) => err,
+                Err(err) => return Err(err.into()),
+            };
+
+            if attempt >= max_attempts {
+                return Err(WooError::PostOnlyRejected(err).into());
+            }
+            attempt += 1;
+
+            let tick_offset = match tick_offset {
+                Some(tick_offset) => tick_offset,
+                None => self.symbol_info(&order.symbol).await?.quote_tick,
+            };
+            order.order_price = Some(self.price_behind_touch(&order, tick_offset).await?);
+        }
+    }
+
+    async fn price_behind_touch(
+        &self,
+        order: &WooOrder,
+        tick_offset: Decimal,
+    ) -> Result<Decimal, WooError> {
+        let book = self.orderbook(&order.symbol, Some(1)).await?;
+        let empty = || WooError::InvalidOrder(format!("{} has an empty book", order.symbol));
+
+        // a maker buy has to rest below the best ask, a maker sell above the
+        // best bid
+        if order.side.eq_ignore_ascii_case("BUY") {
+            Ok(book.best_ask().ok_or_else(empty)?.price - tick_offset)
+        } else {
+            Ok(book.best_bid().ok_or_else(empty)?.price + tick_offset)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mock_woo;
+    use crate::woo_data_structs::{OrderId, OrderSize};
+    use rust_decimal_macros::dec;
+    use wiremock::matchers::{body_string_contains, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const REJECTED: &str =
+        r#"{"success":false,"code":-1012,"message":"Post only order would take liquidity."}"#;
+
+    async fn setup() -> (MockServer, Woo) {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/public/orderbook/SPOT_BTC_USDT"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{"success":true,"asks":[{"price":9000.5,"quantity":1}],"bids":[{"price":8999,"quantity":1}],"timestamp":1578565539808}"#,
+            ))
+            .mount(&server)
+            .await;
+
+        let woo = mock_woo(&server);
+        (server, woo)
+    }
+
+    fn crossing_buy() -> WooOrder {
+        WooOrder::builder("SPOT_BTC_USDT", "BUY", "LIMIT")
+            .price(dec!(9001))
+            .size(OrderSize::Quantity(dec!(0.1)))
+            .build()
+    }
+
+    #[tokio::test]
+    async fn reprices_behind_the_touch_after_rejection() {
+        let (server, woo) = setup().await;
+        Mock::given(method("POST"))
+            .and(body_string_contains("order_price=9001&"))
+            .respond_with(ResponseTemplate::new(400).set_body_string(REJECTED))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_string_contains("order_price=9000.49&"))
+            .and(body_string_contains("order_type=POST_ONLY"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{"success":true,"timestamp":"1578565539.808","order_id":9,"order_type":"POST_ONLY","client_order_id":0}"#,
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let placed = woo
+            .create_post_only_with_reprice(crossing_buy(), 3, Some(dec!(0.01)))
+            .await
+            .unwrap();
+
+        assert_eq!(placed.attempt, 2);
+        assert_eq!(placed.price, dec!(9000.49));
+        assert_eq!(placed.order.order_id, OrderId(9));
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let (server, woo) = setup().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(400).set_body_string(REJECTED))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let err = woo
+            .create_post_only_with_reprice(crossing_buy(), 2, Some(dec!(0.01)))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            err.downcast_ref::<WooError>(),
+            Some(WooError::PostOnlyRejected(_))
+        ));
+    }
+}
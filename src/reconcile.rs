@@ -0,0 +1,247 @@
+use crate::woo::Woo;
+use crate::woo_data_structs::{GetTrades, OrderId};
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// What a strategy believes the account looks like, to check against the
+/// exchange with [`Woo::reconcile`].
+#[derive(Debug, Clone)]
+pub struct LocalState {
+    /// Net position per symbol, missing symbols count as flat.
+    pub positions: HashMap<String, f64>,
+    pub open_order_ids: HashSet<OrderId>,
+    /// Trades already applied to `positions`.
+    pub processed_trade_ids: HashSet<u64>,
+    /// Only trades executed from this moment on are checked.
+    pub trades_since: DateTime<Utc>,
+    /// Largest position difference still treated as equal.
+    pub position_tolerance: f64,
+}
+
+impl LocalState {
+    pub fn new(trades_since: DateTime<Utc>) -> Self {
+        Self {
+            positions: HashMap::new(),
+            open_order_ids: HashSet::new(),
+            processed_trade_ids: HashSet::new(),
+            trades_since,
+            position_tolerance: 1e-9,
+        }
+    }
+}
+
+/// One way the local state disagrees with the exchange.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Discrepancy {
+    /// Tracked as open locally, but not open on the exchange.
+    MissingOrder { order_id: OrderId },
+    /// Open on the exchange, but not tracked locally.
+    UntrackedOrder { order_id: OrderId, symbol: String },
+    UnprocessedFill {
+        trade_id: u64,
+        order_id: OrderId,
+        symbol: String,
+        side: String,
+        executed_price: Decimal,
+        executed_quantity: Decimal,
+    },
+    PositionMismatch {
+        symbol: String,
+        expected: f64,
+        actual: f64,
+    },
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReconciliationReport {
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+impl ReconciliationReport {
+    pub fn is_clean(&self) -> bool {
+        self.discrepancies.is_empty()
+    }
+}
+
+impl Woo {
+    /// Compares `expected` against the exchange's open orders, trades and
+    /// positions, listing every difference found.
+    pub async fn reconcile(&self, expected: &LocalState) -> anyhow::Result<ReconciliationReport> {
+        let trades = GetTrades {
+            start_t: Some(expected.trades_since.timestamp_millis()),
+            ..Default::default()
+        };
+        let (open_orders, trades, positions) = tokio::try_join!(
+            self.open_orders(None, None),
+            self.get_trades_stream(trades).try_collect::<Vec<_>>(),
+            self.positions(),
+        )?;
+
+        let mut discrepancies = Vec::new();
+
+        let open_ids: HashSet<OrderId> = open_orders.iter().map(|order| order.order_id).collect();
+        let mut missing: Vec<_> = expected.open_order_ids.difference(&open_ids).collect();
+        missing.sort_unstable();
+        discrepancies.extend(
+            missing
+                .into_iter()
+                .map(|&order_id| Discrepancy::MissingOrder { order_id }),
+        );
+        discrepancies.extend(
+            open_orders
+                .into_iter()
+                .filter(|order| !expected.open_order_ids.contains(&order.order_id))
+                .map(|order| Discrepancy::UntrackedOrder {
+                    order_id: order.order_id,
+                    symbol: order.symbol,
+                }),
+        );
+
+        discrepancies.extend(
+            trades
+                .into_iter()
+                .filter(|trade| !expected.processed_trade_ids.contains(&trade.id))
+                .map(|trade| Discrepancy::UnprocessedFill {
+                    trade_id: trade.id,
+                    order_id: trade.order_id,
+                    symbol: trade.symbol,
+                    side: trade.side,
+                    executed_price: trade.executed_price,
+                    executed_quantity: trade.executed_quantity,
+                }),
+        );
+
+        let mut actual: HashMap<&str, f64> = positions
+            .positions
+            .iter()
+            .map(|position| (position.symbol.as_str(), position.holding))
+            .collect();
+        let mut symbols: Vec<&str> = actual
+            .keys()
+            .copied()
+            .chain(expected.positions.keys().map(String::as_str))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        symbols.sort_unstable();
+        for symbol in symbols {
+            let expected_holding = expected.positions.get(symbol).copied().unwrap_or(0.0);
+            let actual_holding = actual.remove(symbol).unwrap_or(0.0);
+            if (expected_holding - actual_holding).abs() > expected.position_tolerance {
+                discrepancies.push(Discrepancy::PositionMismatch {
+                    symbol: symbol.to_string(),
+                    expected: expected_holding,
+                    actual: actual_holding,
+                });
+            }
+        }
+
+        Ok(ReconciliationReport { discrepancies })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{mock_woo, mount, order_row, page, with};
+    use wiremock::MockServer;
+
+    #[tokio::test]
+    async fn reports_every_kind_of_discrepancy() {
+        let server = MockServer::start().await;
+
+        mount(
+            &server,
+            "GET",
+            "/v1/orders",
+            page(
+                vec![with(
+                    order_row(7, "NEW"),
+                    serde_json::json!({ "symbol": "PERP_BTC_USDT" }),
+                )],
+                1,
+                500,
+                1,
+            ),
+        )
+        .await;
+        let trade = |id: u64| {
+            serde_json::json!({
+                "id": id, "symbol": "PERP_BTC_USDT", "order_id": 5, "side": "BUY",
+                "executed_price": "9000", "executed_quantity": "0.2", "fee": "0.1",
+                "fee_asset": "USDT", "executed_timestamp": "1578565539.808", "is_maker": 0,
+            })
+        };
+        mount(
+            &server,
+            "GET",
+            "/v1/client/trades",
+            serde_json::json!({
+                "success": true,
+                "meta": { "total": 2, "records_per_page": 25, "current_page": 1 },
+                "rows": [trade(100), trade(101)],
+            }),
+        )
+        .await;
+        mount(
+            &server,
+            "GET",
+            "/v1/positions",
+            serde_json::json!({
+                "success": true,
+                "free_collateral": 1000.0,
+                "total_collateral": 1000.0,
+                "positions": [
+                    { "symbol": "PERP_BTC_USDT", "holding": 0.4, "average_open_price": 9000,
+                      "mark_price": 9100 },
+                    { "symbol": "PERP_ETH_USDT", "holding": 2.0, "average_open_price": 200,
+                      "mark_price": 210 },
+                ],
+            }),
+        )
+        .await;
+
+        let woo = mock_woo(&server);
+        let mut local = LocalState::new(DateTime::from_timestamp(1578565000, 0).unwrap());
+        local.open_order_ids.insert(OrderId(5));
+        local.processed_trade_ids.insert(100);
+        local.positions.insert("PERP_BTC_USDT".to_string(), 0.2);
+        local
+            .positions
+            .insert("PERP_ETH_USDT".to_string(), 2.0 + 1e-12);
+
+        let report = woo.reconcile(&local).await.unwrap();
+
+        assert_eq!(
+            report.discrepancies,
+            vec![
+                Discrepancy::MissingOrder {
+                    order_id: OrderId(5)
+                },
+                Discrepancy::UntrackedOrder {
+                    order_id: OrderId(7),
+                    symbol: "PERP_BTC_USDT".to_string(),
+                },
+                Discrepancy::UnprocessedFill {
+                    trade_id: 101,
+                    order_id: OrderId(5),
+                    symbol: "PERP_BTC_USDT".to_string(),
+                    side: "BUY".to_string(),
+                    executed_price: Decimal::from(9000),
+                    executed_quantity: Decimal::new(2, 1),
+                },
+                Discrepancy::PositionMismatch {
+                    symbol: "PERP_BTC_USDT".to_string(),
+                    expected: 0.2,
+                    actual: 0.4,
+                },
+            ]
+        );
+        let json = serde_json::to_value(&report.discrepancies[0]).unwrap();
+        assert_eq!(json["kind"], "missing_order");
+    }
+}
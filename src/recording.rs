@@ -0,0 +1,234 @@
+use crate::error::WooError;
+use crate::response_log;
+use crate::woo::{RequestBody, Woo};
+use chrono::{DateTime, Utc};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, DisplayFromStr};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use url::Url;
+use uuid::Uuid;
+
+/// Whether recorded requests still go out, see [`Woo::record_to`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordMode {
+    /// Record every request and send it as usual.
+    AlsoSend,
+    /// Only record: every call fails with [`WooError::DryRun`] instead of
+    /// reaching WOO.
+    DryRun,
+}
+
+/// One prepared request as written by [`Woo::record_to`], a line of JSON.
+#[serde_as]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RecordedRequest {
+    pub at: DateTime<Utc>,
+    #[serde_as(as = "DisplayFromStr")]
+    pub request_id: Uuid,
+    #[serde_as(as = "DisplayFromStr")]
+    pub method: Method,
+    #[serde_as(as = "DisplayFromStr")]
+    pub url: Url,
+    /// Credentials and signature are `[REDACTED]`.
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+}
+
+impl RecordedRequest {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Headers [`Woo::replay_from`] sets afresh instead of copying.
+const SIGNING_HEADERS: &[&str] = &[
+    "x-api-key",
+    "x-api-timestamp",
+    "x-api-signature",
+    "x-request-id",
+];
+
+#[derive(Debug)]
+pub(crate) struct RequestRecorder {
+    mode: RecordMode,
+    file: Mutex<File>,
+}
+
+impl RequestRecorder {
+    pub(crate) fn dry_run(&self) -> bool {
+        self.mode == RecordMode::DryRun
+    }
+
+    /// Appends `request` to the file. A line that can't be written is logged
+    /// and skipped, the request itself goes ahead.
+    pub(crate) fn record(&self, request: &reqwest::Request, request_id: Uuid) {
+        let recorded = RecordedRequest {
+            at: Utc::now(),
+            request_id,
+            method: request.method().clone(),
+            url: request.url().clone(),
+            headers: response_log::redacted(request.headers()),
+            body: request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .map(|body| String::from_utf8_lossy(body).into_owned()),
+        };
+
+        let mut line = serde_json::to_string(&recorded).expect("recorded requests encode");
+        line.push('\n');
+        if let Err(err) = self.file.lock().unwrap().write_all(line.as_bytes()) {
+            tracing::warn!(%request_id, %err, "failed to record request");
+        }
+    }
+}
+
+impl Woo {
+    /// Appends every request this client prepares to the JSON lines file at
+    /// `path`, with credentials and signatures redacted, to send them again
+    /// later with [`Woo::replay_from`]. Under [`RecordMode::DryRun`] nothing
+    /// is sent at all. Shared by clones made after this call.
+    pub fn record_to(mut self, path: impl AsRef<Path>, mode: RecordMode) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        self.recorder = Some(Arc::new(RequestRecorder {
+            mode,
+            file: Mutex::new(file),
+        }));
+        Ok(self)
+    }
+
+    /// Sends every request recorded in `path` again, in order, to this
+    /// client's base url. Signed requests are signed afresh, with this
+    /// client's credentials and the current time; the response of each, or
+    /// why it failed, comes back in the same order.
+    pub async fn replay_from(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> anyhow::Result<Vec<Result<serde_json::Value, WooError>>> {
+        let mut recorded = Vec::new();
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                recorded.push(serde_json::from_str::<RecordedRequest>(&line)?);
+            }
+        }
+
+        let mut responses = Vec::with_capacity(recorded.len());
+        for request in recorded {
+            responses.push(self.replay(request).await);
+        }
+
+        Ok(responses)
+    }
+
+    async fn replay(&self, request: RecordedRequest) -> Result<serde_json::Value, WooError> {
+        let mut url = self.endpoint(request.url.path());
+        url.set_query(request.url.query());
+
+        let mut req_builder = if request.header("x-api-signature").is_some() {
+            let body = if url.path().starts_with("/v3/") {
+                RequestBody::Json(request.body.clone().unwrap_or_default())
+            } else {
+                let payload = match &request.body {
+                    Some(body) => body.clone(),
+                    None => url.query().unwrap_or_default().to_string(),
+                };
+                RequestBody::Form(payload)
+            };
+            self.signed_builder(request.method.clone(), url, body)
+                .await?
+                .0
+        } else {
+            let mut req_builder = self.http_client.request(request.method.clone(), url);
+            if let Some(content_type) = request.header(CONTENT_TYPE.as_str()) {
+                req_builder = req_builder.header(CONTENT_TYPE, content_type);
+            }
+            if let Some(body) = &request.body {
+                req_builder = req_builder.body(body.clone());
+            }
+            req_builder
+        };
+        for (name, value) in &request.headers {
+            if !SIGNING_HEADERS.contains(&name.as_str()) && name != CONTENT_TYPE.as_str() {
+                req_builder = req_builder.header(name, value);
+            }
+        }
+
+        self.send(req_builder, None).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::Credentials;
+    use crate::woo::Environment;
+    use crate::woo_data_structs::WooOrder;
+    use rust_decimal_macros::dec;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn records_orders_and_replays_them_signed_afresh() {
+        let file = std::env::temp_dir().join(format!("woo-record-{}.jsonl", Uuid::new_v4()));
+        let woo = Woo::builder(Environment::Staging, Credentials::new("key", "secret"))
+            .build()
+            .unwrap()
+            .record_to(&file, RecordMode::DryRun)
+            .unwrap();
+
+        for symbol in ["SPOT_BTC_USDT", "SPOT_ETH_USDT"] {
+            let order = WooOrder::limit(symbol, "BUY", dec!(1000), dec!(1));
+            let err = woo.create_order(order).await.unwrap_err();
+            assert!(matches!(err, WooError::DryRun(_)));
+        }
+
+        let contents = std::fs::read_to_string(&file).unwrap();
+        let recorded: Vec<RecordedRequest> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(recorded.len(), 2);
+        assert_eq!(recorded[0].method, Method::POST);
+        assert_eq!(recorded[0].url.path(), "/v1/order");
+        assert!(recorded[1]
+            .body
+            .as_ref()
+            .unwrap()
+            .contains("symbol=SPOT_ETH_USDT"));
+        assert_eq!(recorded[0].header("x-api-signature"), Some("[REDACTED]"));
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/order"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true, "order_id": 1,
+            })))
+            .expect(2)
+            .mount(&server)
+            .await;
+        let staging = Woo::builder(Environment::Staging, Credentials::new("key", "other"))
+            .base_url(Url::parse(&server.uri()).unwrap())
+            .build()
+            .unwrap();
+
+        let responses = staging.replay_from(&file).await.unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        assert!(responses.iter().all(Result::is_ok));
+        for request in server.received_requests().await.unwrap() {
+            let body = String::from_utf8(request.body.clone()).unwrap();
+            let timestamp = request.headers["x-api-timestamp"].to_str().unwrap();
+            let expected =
+                Woo::generate_hmac_sha256_signature(body, timestamp.parse().unwrap(), "other");
+            assert_eq!(request.headers["x-api-signature"], expected.as_str());
+        }
+    }
+}
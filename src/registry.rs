@@ -0,0 +1,207 @@
+use crate::woo::Woo;
+use crate::woo_data_structs::{OrderId, OrderStatus};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+/// An order the registry knows to be open, see [`Woo::with_order_registry`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrackedOrder {
+    pub order_id: OrderId,
+    pub submitted_at: DateTime<Utc>,
+    /// As of the last time the order was read, [`OrderStatus::New`] before.
+    pub status: OrderStatus,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    orders: HashMap<OrderId, (u64, TrackedOrder)>,
+    /// Orders by when they were last touched, the oldest first.
+    recency: BTreeMap<u64, OrderId>,
+    tick: u64,
+}
+
+impl Entries {
+    fn touch(&mut self, order: TrackedOrder) {
+        self.tick += 1;
+        if let Some((tick, _)) = self
+            .orders
+            .insert(order.order_id, (self.tick, order.clone()))
+        {
+            self.recency.remove(&tick);
+        }
+        self.recency.insert(self.tick, order.order_id);
+    }
+
+    fn remove(&mut self, order_id: OrderId) -> Option<TrackedOrder> {
+        let (tick, order) = self.orders.remove(&order_id)?;
+        self.recency.remove(&tick);
+        Some(order)
+    }
+}
+
+/// Open orders this client submitted, at most `capacity` of them: past it
+/// the least recently submitted or read ones are forgotten.
+#[derive(Debug)]
+pub(crate) struct OrderRegistry {
+    capacity: usize,
+    entries: Mutex<Entries>,
+}
+
+impl OrderRegistry {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: Mutex::default(),
+        }
+    }
+
+    pub(crate) fn submitted(&self, order_id: OrderId) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.touch(TrackedOrder {
+            order_id,
+            submitted_at: Utc::now(),
+            status: OrderStatus::New,
+        });
+
+        while entries.orders.len() > self.capacity {
+            let Some((_, evicted)) = entries.recency.pop_first() else {
+                break;
+            };
+            entries.orders.remove(&evicted);
+            tracing::debug!(%evicted, "order registry full, forgot the oldest order");
+        }
+    }
+
+    /// Records a status read from the exchange. Orders that reached a
+    /// terminal state are dropped; unknown ones aren't added, the registry
+    /// only tracks orders submitted through it.
+    pub(crate) fn observed(&self, order_id: OrderId, status: OrderStatus) {
+        let mut entries = self.entries.lock().unwrap();
+        let Some(mut order) = entries.remove(order_id) else {
+            return;
+        };
+
+        if !status.is_terminal() {
+            order.status = status;
+            entries.touch(order);
+        }
+    }
+
+    fn forget(&self, order_id: OrderId) -> Option<TrackedOrder> {
+        self.entries.lock().unwrap().remove(order_id)
+    }
+
+    fn open_orders(&self) -> Vec<TrackedOrder> {
+        let entries = self.entries.lock().unwrap();
+        let mut orders: Vec<_> = entries
+            .orders
+            .values()
+            .map(|(_, order)| order.clone())
+            .collect();
+        orders.sort_by_key(|order| order.order_id);
+        orders
+    }
+}
+
+impl Woo {
+    /// Keeps track of up to `capacity` open orders submitted through this
+    /// client, e.g. to reconcile them after a restart. Reading an order, by
+    /// [`Woo::get_order`] or [`Woo::wait_for_terminal_state`], updates its
+    /// status and drops it once it is terminal. Shared by clones made after
+    /// this call.
+    pub fn with_order_registry(mut self, capacity: usize) -> Self {
+        self.registry = Some(Arc::new(OrderRegistry::new(capacity)));
+        self
+    }
+
+    /// Ids of the orders in the registry, in ascending order; empty without
+    /// [`Woo::with_order_registry`].
+    pub fn open_order_ids(&self) -> Vec<OrderId> {
+        self.tracked_orders()
+            .into_iter()
+            .map(|order| order.order_id)
+            .collect()
+    }
+
+    /// The orders in the registry with their submit time and last known
+    /// status.
+    pub fn tracked_orders(&self) -> Vec<TrackedOrder> {
+        self.registry
+            .as_ref()
+            .map(|registry| registry.open_orders())
+            .unwrap_or_default()
+    }
+
+    /// Stops tracking `order_id`, returning what the registry knew about it.
+    pub fn forget(&self, order_id: OrderId) -> Option<TrackedOrder> {
+        self.registry.as_ref()?.forget(order_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{mock_woo, ok, order_detail, order_res};
+    use crate::woo_data_structs::WooOrder;
+    use rust_decimal_macros::dec;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer};
+
+    #[tokio::test]
+    async fn submitted_orders_are_tracked_until_terminal() {
+        let server = MockServer::start().await;
+        for (order_id, status) in [(1, "FILLED"), (2, "PARTIAL_FILLED")] {
+            Mock::given(method("POST"))
+                .and(path("/v1/order"))
+                .and(wiremock::matchers::body_string_contains(format!(
+                    "order_price={}",
+                    9000 + order_id
+                )))
+                .respond_with(ok(order_res(order_id)))
+                .mount(&server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path(format!("/v1/order/{order_id}")))
+                .respond_with(ok(order_detail(order_id, status, 0.0)))
+                .mount(&server)
+                .await;
+        }
+        let woo = mock_woo(&server).with_order_registry(16);
+
+        for price in [dec!(9001), dec!(9002)] {
+            let order = WooOrder::post_only("SPOT_BTC_USDT", "BUY", price, dec!(0.1));
+            woo.create_order(order).await.unwrap();
+        }
+        assert_eq!(woo.open_order_ids(), [OrderId(1), OrderId(2)]);
+
+        woo.get_order(OrderId(1)).await.unwrap();
+        woo.get_order(OrderId(2)).await.unwrap();
+
+        let open = woo.tracked_orders();
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0].order_id, OrderId(2));
+        assert_eq!(open[0].status, OrderStatus::PartialFilled);
+
+        assert_eq!(woo.forget(OrderId(2)).unwrap().order_id, OrderId(2));
+        assert!(woo.open_order_ids().is_empty());
+    }
+
+    #[test]
+    fn evicts_the_least_recently_touched_order() {
+        let registry = OrderRegistry::new(2);
+
+        registry.submitted(OrderId(1));
+        registry.submitted(OrderId(2));
+        // reading 1 makes 2 the oldest
+        registry.observed(OrderId(1), OrderStatus::New);
+        registry.submitted(OrderId(3));
+
+        let ids: Vec<_> = registry
+            .open_orders()
+            .into_iter()
+            .map(|order| order.order_id)
+            .collect();
+        assert_eq!(ids, [OrderId(1), OrderId(3)]);
+    }
+}
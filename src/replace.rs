@@ -0,0 +1,511 @@
+use crate::error::{WooApiError, WooError};
+use crate::wait::CancelOnTimeout;
+use crate::woo::Woo;
+use crate::woo_data_structs::{
+    CancelOrder, CancelOrderRes, EditOrder, EditOrderRes, OrderDetail, OrderId, OrderSize,
+    OrderStatus, SendOrderRes, WooEnvelope, WooOrder,
+};
+use reqwest::Method;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::time::Duration;
+
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+const CANCEL_DEADLINE: Duration = Duration::from_secs(5);
+
+/// How a [`Woo::replace_order`] played out.
+#[derive(Debug)]
+pub enum ReplaceOutcome {
+    /// The original was cancelled and the replacement, shrunk by whatever
+    /// filled in the meantime, accepted.
+    Replaced {
+        cancelled: OrderDetail,
+        replacement: SendOrderRes,
+    },
+    /// The original filled before the cancel landed, or filled enough that
+    /// nothing is left for the replacement to do. Nothing was submitted.
+    OriginalFilled(OrderDetail),
+    /// The original is gone but WOO refused the replacement, so the position
+    /// is now unquoted.
+    ReplacementRejected {
+        cancelled: OrderDetail,
+        error: WooApiError,
+    },
+}
+
+impl Woo {
+    /// Cancels `order_id` and submits `new_order` in its place.
+    ///
+    /// The cancel is confirmed by polling the original until it is terminal,
+    /// and any quantity it executed in the meantime is taken off the
+    /// replacement, so the total filled plus resting stays what `new_order`
+    /// asked for.
+    pub async fn replace_order(
+        &self,
+        order_id: OrderId,
+        new_order: WooOrder,
+    ) -> anyhow::Result<ReplaceOutcome> {
+        let cancel = CancelOrder {
+            order_id,
+            symbol: new_order.symbol.clone(),
+        };
+
+        // a fill can beat the cancel, WOO then no longer knows the order as
+        // cancellable and the poll below tells what happened to it
+        match self
+            .signed_request::<_, CancelOrderRes>(Method::DELETE, "v1/order", &cancel)
+            .await
+        {
+            Err(WooError::Api(err)) if err.code == WooApiError::RESOURCE_NOT_FOUND => {}
+            res => {
+                res?;
+            }
+        }
+
+        let original = self
+            .wait_for_terminal_state(
+                order_id,
+                CANCEL_POLL_INTERVAL,
+                CANCEL_DEADLINE,
+                CancelOnTimeout::No,
+                None,
+            )
+            .await?;
+
+        if original.status == OrderStatus::Filled {
+            return Ok(ReplaceOutcome::OriginalFilled(original));
+        }
+
+        let Some(replacement) = Woo::shrink_by_executed(new_order, &original)? else {
+            return Ok(ReplaceOutcome::OriginalFilled(original));
+        };
+
+        match self.place_order(&replacement).await {
+            Ok(replacement) => Ok(ReplaceOutcome::Replaced {
+                cancelled: original,
+                replacement,
+            }),
+            Err(WooError::OrderRejected(error) | WooError::PostOnlyRejected(error)) => {
+                Ok(ReplaceOutcome::ReplacementRejected {
+                    cancelled: original,
+                    error,
+                })
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Moves `order_id` to `new_order`'s price and size. When only those
+    /// differ the order is amended in place, keeping it on the book the whole
+    /// time (and, for a size change only, its queue position). Anything else
+    /// is cancelled and `new_order` created; if the cancel went through but
+    /// the create didn't, the error is [`WooError::ReplacementFailed`]. An
+    /// order already filled or cancelled is [`WooError::OrderTerminal`].
+    ///
+    /// Unlike [`Woo::replace_order`] the cancel isn't confirmed and fills in
+    /// between aren't taken off the replacement.
+    pub async fn cancel_and_replace(
+        &self,
+        order_id: OrderId,
+        new_order: WooOrder,
+    ) -> anyhow::Result<SendOrderRes> {
+        let original = self.order_detail(order_id).await?;
+        if original.status.is_terminal() {
+            return Err(WooError::OrderTerminal {
+                order_id,
+                status: original.status,
+            }
+            .into());
+        }
+
+        if let Some(edit) = Woo::as_amendment(&original, &new_order)? {
+            return Ok(self.amend_order(&original, &edit, new_order).await?);
+        }
+
+        let cancel = CancelOrder {
+            order_id,
+            symbol: original.symbol,
+        };
+        self.cancel_order(cancel).await?;
+
+        self.place_order(&new_order).await.map_err(|source| {
+            WooError::ReplacementFailed {
+                order_id,
+                source: Box::new(source),
+            }
+            .into()
+        })
+    }
+
+    async fn amend_order(
+        &self,
+        original: &OrderDetail,
+        edit: &EditOrder,
+        new_order: WooOrder,
+    ) -> Result<SendOrderRes, WooError> {
+        let order_id = original.order_id;
+        let mut timestamp = chrono::Utc::now();
+        // nothing to do if the order already looks like `new_order`
+        if edit.price.is_some() || edit.quantity.is_some() {
+            let _in_flight = self.guard_order(&new_order).await?;
+            let res = self
+                .signed_v3_request::<_, WooEnvelope<EditOrderRes>>(
+                    Method::PUT,
+                    &format!("v3/order/{order_id}"),
+                    edit,
+                )
+                .await?;
+            timestamp = res.timestamp.unwrap_or(timestamp);
+            res.into_data()?;
+        }
+
+        let to_f64 = |value: Option<Decimal>| value.and_then(|value| value.to_f64());
+        Ok(SendOrderRes {
+            success: true,
+            timestamp,
+            order_id,
+            order_type: new_order.wire_order_type()?.to_string(),
+            client_order_id: original.client_order_id.clone().unwrap_or_default(),
+            order_price: to_f64(new_order.order_price),
+            order_quantity: to_f64(new_order.order_quantity),
+            order_amount: None,
+            reduce_only: new_order.reduce_only,
+        })
+    }
+
+    /// The edit turning `original` into `new_order`, `None` if they differ in
+    /// more than price and quantity.
+    fn as_amendment(
+        original: &OrderDetail,
+        new_order: &WooOrder,
+    ) -> anyhow::Result<Option<EditOrder>> {
+        let same_order = original.symbol == new_order.symbol
+            && original.side == new_order.side
+            && new_order
+                .wire_order_type()
+                .is_ok_and(|order_type| original.r#type == order_type)
+            && new_order.order_amount.is_none()
+            && new_order.visible_quantity.is_none()
+            && new_order.reduce_only.is_none()
+            && new_order.position_side.is_none()
+            && new_order
+                .client_order_id
+                .as_ref()
+                .is_none_or(|id| original.client_order_id.as_ref() == Some(id))
+            && new_order
+                .order_tag
+                .as_ref()
+                .is_none_or(|tag| original.order_tag.as_ref() == Some(tag));
+        if !same_order {
+            return Ok(None);
+        }
+
+        let changed =
+            |new: Option<Decimal>, old: Option<Decimal>| new.filter(|new| Some(*new) != old);
+
+        Ok(Some(EditOrder {
+            price: changed(new_order.order_price, original.price),
+            quantity: changed(new_order.order_quantity, original.quantity),
+        }))
+    }
+
+    /// `order` minus what `original` executed, `None` if that leaves nothing.
+    fn shrink_by_executed(
+        mut order: WooOrder,
+        original: &OrderDetail,
+    ) -> anyhow::Result<Option<WooOrder>> {
+        let executed = original.executed;
+
+        let remaining = match order.size() {
+            Some(OrderSize::Quantity(quantity)) => OrderSize::Quantity(quantity - executed),
+            Some(OrderSize::Amount(amount)) => {
+                let price = original
+                    .average_executed_price
+                    .or(original.price)
+                    .unwrap_or_default();
+                OrderSize::Amount(amount - executed * price)
+            }
+            None => anyhow::bail!("replacement order must set exactly one of quantity or amount"),
+        };
+
+        match remaining {
+            OrderSize::Quantity(size) | OrderSize::Amount(size) if size <= Decimal::ZERO => {
+                Ok(None)
+            }
+            OrderSize::Quantity(quantity) => {
+                order.order_quantity = Some(quantity);
+                Ok(Some(order))
+            }
+            OrderSize::Amount(amount) => {
+                order.order_amount = Some(amount);
+                Ok(Some(order))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::risk::RiskLimits;
+    use crate::testing::{mock_woo, ok, order_detail, order_res, with};
+    use crate::woo_data_structs::ClientOrderId;
+    use hmac::{Hmac, Mac};
+    use rust_decimal_macros::dec;
+    use sha2::Sha256;
+    use wiremock::matchers::{body_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn order(status: &str, executed: f64) -> ResponseTemplate {
+        let detail = order_detail(7, status, executed);
+        ok(with(
+            detail,
+            serde_json::json!({
+                "quantity": 0.3, "average_executed_price": 9000, "client_order_id": 42,
+            }),
+        ))
+    }
+
+    async fn mount_cancel(server: &MockServer, response: ResponseTemplate) {
+        Mock::given(method("DELETE"))
+            .and(path("/v1/order"))
+            .respond_with(response)
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+
+    async fn mount_order_state(server: &MockServer, status: &str, executed: f64) {
+        Mock::given(method("GET"))
+            .and(path("/v1/order/7"))
+            .respond_with(order(status, executed))
+            .mount(server)
+            .await;
+    }
+
+    fn new_order() -> WooOrder {
+        WooOrder::builder("SPOT_BTC_USDT", "BUY", "LIMIT")
+            .price(dec!(9100))
+            .size(OrderSize::Quantity(dec!(0.3)))
+            .build()
+    }
+
+    #[tokio::test]
+    async fn replacement_is_shrunk_by_fills_before_cancel() {
+        let server = MockServer::start().await;
+
+        mount_cancel(
+            &server,
+            ResponseTemplate::new(200)
+                .set_body_string(r#"{"success":true,"status":"CANCEL_SENT"}"#),
+        )
+        .await;
+        mount_order_state(&server, "CANCELLED", 0.1).await;
+        Mock::given(method("POST"))
+            .and(path("/v1/order"))
+            .respond_with(ok(order_res(8)))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let woo = mock_woo(&server);
+        let outcome = woo.replace_order(OrderId(7), new_order()).await.unwrap();
+
+        match outcome {
+            ReplaceOutcome::Replaced { replacement, .. } => {
+                assert_eq!(replacement.order_id, OrderId(8))
+            }
+            other => panic!("expected a replacement, got {other:?}"),
+        }
+
+        let requests = server.received_requests().await.unwrap();
+        let submitted = requests.last().unwrap();
+        let body = String::from_utf8_lossy(&submitted.body);
+        assert!(body.contains("order_quantity=0.2"), "{body}");
+    }
+
+    #[tokio::test]
+    async fn fill_racing_the_cancel_submits_nothing() {
+        let server = MockServer::start().await;
+
+        mount_cancel(
+            &server,
+            ResponseTemplate::new(400).set_body_string(
+                r#"{"success":false,"code":-1006,"message":"Your order and symbol are not valid or already canceled."}"#,
+            ),
+        )
+        .await;
+        mount_order_state(&server, "FILLED", 0.3).await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let woo = mock_woo(&server);
+        let outcome = woo.replace_order(OrderId(7), new_order()).await.unwrap();
+
+        assert!(matches!(outcome, ReplaceOutcome::OriginalFilled(_)));
+    }
+
+    #[tokio::test]
+    async fn rejected_replacement_is_reported() {
+        let server = MockServer::start().await;
+
+        mount_cancel(
+            &server,
+            ResponseTemplate::new(200)
+                .set_body_string(r#"{"success":true,"status":"CANCEL_SENT"}"#),
+        )
+        .await;
+        mount_order_state(&server, "CANCELLED", 0.0).await;
+        Mock::given(method("POST"))
+            .and(path("/v1/order"))
+            .respond_with(ResponseTemplate::new(400).set_body_string(
+                r#"{"success":false,"code":-1004,"message":"Insufficient balance."}"#,
+            ))
+            .mount(&server)
+            .await;
+
+        let woo = mock_woo(&server);
+        let outcome = woo.replace_order(OrderId(7), new_order()).await.unwrap();
+
+        match outcome {
+            ReplaceOutcome::ReplacementRejected { cancelled, error } => {
+                assert_eq!(cancelled.status, OrderStatus::Cancelled);
+                assert_eq!(error.code, -1004);
+            }
+            other => panic!("expected a rejection, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn amends_price_changes_in_place() {
+        let server = MockServer::start().await;
+        mount_order_state(&server, "NEW", 0.0).await;
+        Mock::given(method("PUT"))
+            .and(path("/v3/order/7"))
+            .and(body_json(serde_json::json!({ "price": "9100" })))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{"success":true,"data":{"status":"EDIT_SENT"},"timestamp":1578565539808}"#,
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let woo = mock_woo(&server);
+        let amended = woo
+            .cancel_and_replace(OrderId(7), new_order())
+            .await
+            .unwrap();
+
+        assert_eq!(amended.order_id, OrderId(7));
+        assert_eq!(amended.order_price, Some(9100.0));
+        assert_eq!(amended.client_order_id, ClientOrderId::Numeric(42));
+
+        let requests = server.received_requests().await.unwrap();
+        let edit = requests
+            .iter()
+            .find(|r| r.method.as_str() == "PUT")
+            .unwrap();
+        let timestamp = edit.headers["x-api-timestamp"].to_str().unwrap();
+        let payload = format!(
+            "{timestamp}PUT/v3/order/7{}",
+            String::from_utf8_lossy(&edit.body)
+        );
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"secret").unwrap();
+        mac.update(payload.as_bytes());
+        assert_eq!(
+            edit.headers["x-api-signature"].to_str().unwrap(),
+            hex::encode(mac.finalize().into_bytes())
+        );
+    }
+
+    #[tokio::test]
+    async fn amendment_is_held_to_the_risk_limits() {
+        let server = MockServer::start().await;
+        mount_order_state(&server, "NEW", 0.0).await;
+        Mock::given(method("PUT"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let woo = mock_woo(&server).with_risk_limits(RiskLimits {
+            max_order_notional: Some(dec!(1000)),
+            ..RiskLimits::default()
+        });
+        let err = woo
+            .cancel_and_replace(OrderId(7), new_order())
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            err.downcast_ref::<WooError>(),
+            Some(WooError::RiskRejected { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn cancels_and_creates_when_more_than_price_and_size_change() {
+        let server = MockServer::start().await;
+        mount_order_state(&server, "NEW", 0.0).await;
+        mount_cancel(
+            &server,
+            ResponseTemplate::new(200)
+                .set_body_string(r#"{"success":true,"status":"CANCEL_SENT"}"#),
+        )
+        .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/order"))
+            .respond_with(ok(order_res(8)))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let woo = mock_woo(&server);
+        let post_only = WooOrder::post_only("SPOT_BTC_USDT", "BUY", dec!(9100), dec!(0.3));
+        let replacement = woo.cancel_and_replace(OrderId(7), post_only).await.unwrap();
+
+        assert_eq!(replacement.order_id, OrderId(8));
+    }
+
+    #[tokio::test]
+    async fn failed_create_after_cancel_is_a_partial_error() {
+        let server = MockServer::start().await;
+        mount_order_state(&server, "NEW", 0.0).await;
+        mount_cancel(
+            &server,
+            ResponseTemplate::new(200)
+                .set_body_string(r#"{"success":true,"status":"CANCEL_SENT"}"#),
+        )
+        .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/order"))
+            .respond_with(ResponseTemplate::new(400).set_body_string(
+                r#"{"success":false,"code":-1101,"message":"The risk exposure for client is too high."}"#,
+            ))
+            .mount(&server)
+            .await;
+
+        let woo = mock_woo(&server);
+        let post_only = WooOrder::post_only("SPOT_BTC_USDT", "BUY", dec!(9100), dec!(0.3));
+        let err = woo
+            .cancel_and_replace(OrderId(7), post_only)
+            .await
+            .unwrap_err();
+
+        match err.downcast_ref::<WooError>() {
+            Some(WooError::ReplacementFailed { order_id, source }) => {
+                assert_eq!(*order_id, OrderId(7));
+                assert!(matches!(**source, WooError::OrderRejected(_)));
+            }
+            other => panic!("expected a partial failure, got {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use crate::woo::Woo;
+use chrono::{DateTime, Utc};
+use reqwest::header::HeaderMap;
+use reqwest::Method;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// Headers whose values never make it into the log.
+const REDACTED_HEADERS: &[&str] = &[
+    "x-api-key",
+    "x-api-signature",
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+];
+
+/// One exchange with WOO as it went over the wire, see
+/// [`Woo::with_response_capture`].
+#[derive(Debug, Clone)]
+pub struct CapturedResponse {
+    pub at: DateTime<Utc>,
+    pub request_id: Uuid,
+    pub method: Method,
+    /// Path and query, e.g. `/v1/orders?page=2&size=500`.
+    pub path: String,
+    pub request_headers: Vec<(String, String)>,
+    pub status: u16,
+    pub response_headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+/// The last `capacity` responses, oldest first.
+#[derive(Debug)]
+pub(crate) struct ResponseLog {
+    capacity: usize,
+    responses: Mutex<VecDeque<CapturedResponse>>,
+}
+
+impl ResponseLog {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            responses: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    pub(crate) fn record(&self, response: CapturedResponse) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut responses = self.responses.lock().unwrap();
+        if responses.len() == self.capacity {
+            responses.pop_front();
+        }
+        responses.push_back(response);
+    }
+
+    fn snapshot(&self) -> Vec<CapturedResponse> {
+        self.responses.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+/// `headers` as name/value pairs with credentials replaced by `[REDACTED]`.
+pub(crate) fn redacted(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if REDACTED_HEADERS.contains(&name.as_str()) {
+                "[REDACTED]".to_string()
+            } else {
+                String::from_utf8_lossy(value.as_bytes()).into_owned()
+            };
+            (name.to_string(), value)
+        })
+        .collect()
+}
+
+impl Woo {
+    /// Keeps the raw headers and body of the last `capacity` responses,
+    /// readable through [`Woo::last_responses`]. Credentials are redacted, so
+    /// this is safe to leave on in staging. Shared by clones made after this
+    /// call.
+    pub fn with_response_capture(mut self, capacity: usize) -> Self {
+        self.responses = Some(Arc::new(ResponseLog::new(capacity)));
+        self
+    }
+
+    /// The captured responses, oldest first. Empty unless
+    /// [`Woo::with_response_capture`] was enabled.
+    pub fn last_responses(&self) -> Vec<CapturedResponse> {
+        self.responses
+            .as_ref()
+            .map(|log| log.snapshot())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::mock_woo;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn keeps_the_newest_responses_with_credentials_redacted() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("x-ratelimit-remaining", "9")
+                    .set_body_string(r#"{"success":true,"holding":{"BTC":1.5}}"#),
+            )
+            .mount(&server)
+            .await;
+
+        let woo = mock_woo(&server).with_response_capture(2);
+
+        for _ in 0..3 {
+            woo.get_holding().await.unwrap();
+        }
+        let responses = woo.last_responses();
+
+        assert_eq!(responses.len(), 2);
+        let last = &responses[1];
+        assert_eq!(last.status, 200);
+        assert_eq!(last.path, "/v1/client/holding?all=true");
+        assert!(last.body.contains(r#""BTC":1.5"#));
+        assert!(last
+            .response_headers
+            .contains(&("x-ratelimit-remaining".to_string(), "9".to_string())));
+        for (name, value) in &last.request_headers {
+            if name == "x-api-key" || name == "x-api-signature" {
+                assert_eq!(value, "[REDACTED]");
+            }
+        }
+        assert!(last
+            .request_headers
+            .iter()
+            .any(|(name, _)| name == "x-api-key"));
+    }
+}
@@ -0,0 +1,320 @@
+use crate::error::WooError;
+use crate::woo::Woo;
+use crate::woo_data_structs::WooOrder;
+use rust_decimal::Decimal;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Pre-trade limits checked by every order the client submits, before it is
+/// signed. Unset limits are not checked.
+#[derive(Debug, Clone, Default)]
+pub struct RiskLimits {
+    /// Largest quote value of a single order.
+    pub max_order_notional: Option<Decimal>,
+    /// Largest absolute position per symbol the order may leave behind, as
+    /// reported by the positions endpoint.
+    pub max_position: HashMap<String, Decimal>,
+    pub max_open_orders: Option<usize>,
+    /// Symbols orders may be sent for, any symbol if `None`.
+    pub allowed_symbols: Option<HashSet<String>>,
+    pub price_collar: Option<PriceCollar>,
+}
+
+/// Rejects limit prices too far from the orderbook mid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PriceCollar {
+    /// Largest allowed distance from the mid, as a fraction (0.05 is 5%).
+    pub max_deviation: Decimal,
+    /// How old a mid may be before the book is fetched again.
+    pub max_staleness: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RiskRule {
+    AllowedSymbols,
+    MaxOrderNotional,
+    PriceCollar,
+    MaxOpenOrders,
+    MaxPosition,
+}
+
+impl fmt::Display for RiskRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rule = match self {
+            RiskRule::AllowedSymbols => "allowed symbols",
+            RiskRule::MaxOrderNotional => "max order notional",
+            RiskRule::PriceCollar => "price collar",
+            RiskRule::MaxOpenOrders => "max open orders",
+            RiskRule::MaxPosition => "max position",
+        };
+        f.write_str(rule)
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct RiskGuard {
+    limits: RiskLimits,
+    mids: Mutex<HashMap<String, (Instant, Decimal)>>,
+}
+
+impl RiskGuard {
+    pub(crate) fn new(limits: RiskLimits) -> Self {
+        Self {
+            limits,
+            mids: Mutex::default(),
+        }
+    }
+}
+
+fn rejected(rule: RiskRule, detail: String) -> WooError {
+    WooError::RiskRejected { rule, detail }
+}
+
+impl Woo {
+    /// Enforces `limits` on every order sent from now on, by this client and
+    /// by clones made after this call.
+    pub fn with_risk_limits(mut self, limits: RiskLimits) -> Self {
+        self.risk = Some(std::sync::Arc::new(RiskGuard::new(limits)));
+        self
+    }
+
+    pub(crate) async fn check_risk(&self, order: &WooOrder) -> Result<(), WooError> {
+        let Some(guard) = &self.risk else {
+            return Ok(());
+        };
+        let limits = &guard.limits;
+
+        if let Some(allowed) = &limits.allowed_symbols {
+            if !allowed.contains(&order.symbol) {
+                return Err(rejected(
+                    RiskRule::AllowedSymbols,
+                    format!("{} is not an allowed symbol", order.symbol),
+                ));
+            }
+        }
+
+        let needs_reference = limits.price_collar.is_some()
+            || (limits.max_order_notional.is_some() && order.order_price.is_none());
+        let reference = if needs_reference {
+            Some(self.reference_price(guard, &order.symbol).await?)
+        } else {
+            None
+        };
+
+        if let Some(max_notional) = limits.max_order_notional {
+            let price = order.order_price.or(reference).unwrap_or_default();
+            let notional = match (order.order_quantity, order.order_amount) {
+                (Some(quantity), _) => quantity * price,
+                (None, Some(amount)) => amount,
+                (None, None) => Decimal::ZERO,
+            };
+            if notional > max_notional {
+                return Err(rejected(
+                    RiskRule::MaxOrderNotional,
+                    format!("notional {notional} above {max_notional}"),
+                ));
+            }
+        }
+
+        if let (Some(collar), Some(price), Some(mid)) =
+            (limits.price_collar, order.order_price, reference)
+        {
+            if !mid.is_zero() && ((price - mid) / mid).abs() > collar.max_deviation {
+                return Err(rejected(
+                    RiskRule::PriceCollar,
+                    format!(
+                        "price {price} more than {} away from mid {mid}",
+                        collar.max_deviation
+                    ),
+                ));
+            }
+        }
+
+        if let Some(max_open_orders) = limits.max_open_orders {
+            let open_orders = self.open_orders(None, None).await?.len();
+            if open_orders >= max_open_orders {
+                return Err(rejected(
+                    RiskRule::MaxOpenOrders,
+                    format!("{open_orders} orders already open"),
+                ));
+            }
+        }
+
+        if let Some(&max_position) = limits.max_position.get(&order.symbol) {
+            self.check_position(order, max_position, reference).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn check_position(
+        &self,
+        order: &WooOrder,
+        max_position: Decimal,
+        reference: Option<Decimal>,
+    ) -> Result<(), WooError> {
+        let positions = self.positions().await?;
+        let holding = positions
+            .positions
+            .iter()
+            .find(|position| position.symbol == order.symbol)
+            .map(|position| Decimal::try_from(position.holding).unwrap_or_default())
+            .unwrap_or_default();
+
+        let quantity = match (order.order_quantity, order.order_amount) {
+            (Some(quantity), _) => quantity,
+            (None, Some(amount)) => {
+                let price = match order.order_price.or(reference) {
+                    Some(price) => price,
+                    None => self.orderbook_mid(&order.symbol).await?,
+                };
+                if price.is_zero() {
+                    Decimal::ZERO
+                } else {
+                    amount / price
+                }
+            }
+            (None, None) => Decimal::ZERO,
+        };
+        let projected = if order.side.eq_ignore_ascii_case("SELL") {
+            holding - quantity
+        } else {
+            holding + quantity
+        };
+
+        if projected.abs() > max_position {
+            return Err(rejected(
+                RiskRule::MaxPosition,
+                format!(
+                    "position on {} would be {projected}, limit {max_position}",
+                    order.symbol
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+
+    async fn reference_price(&self, guard: &RiskGuard, symbol: &str) -> Result<Decimal, WooError> {
+        let max_staleness = guard
+            .limits
+            .price_collar
+            .map(|collar| collar.max_staleness)
+            .unwrap_or_default();
+
+        if let Some((fetched_at, mid)) = guard.mids.lock().unwrap().get(symbol) {
+            if fetched_at.elapsed() <= max_staleness {
+                return Ok(*mid);
+            }
+        }
+
+        let mid = self.orderbook_mid(symbol).await?;
+        guard
+            .mids
+            .lock()
+            .unwrap()
+            .insert(symbol.to_string(), (Instant::now(), mid));
+
+        Ok(mid)
+    }
+
+    async fn orderbook_mid(&self, symbol: &str) -> Result<Decimal, WooError> {
+        let book = self.orderbook(symbol, Some(1)).await?;
+
+        book.mid_price()
+            .ok_or_else(|| WooError::InvalidOrder(format!("{symbol} has an empty book")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mock_woo;
+    use crate::woo_data_structs::OrderSize;
+    use rust_decimal_macros::dec;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn guarded_woo(server: &MockServer, limits: RiskLimits) -> Woo {
+        Mock::given(method("GET"))
+            .and(path("/v1/public/orderbook/SPOT_BTC_USDT"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{"success":true,"asks":[{"price":101,"quantity":1}],"bids":[{"price":99,"quantity":1}],"timestamp":1578565539808}"#,
+            ))
+            .mount(server)
+            .await;
+        // nothing may reach the order endpoint
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(0)
+            .mount(server)
+            .await;
+
+        mock_woo(server).with_risk_limits(limits)
+    }
+
+    fn buy(price: Decimal, quantity: Decimal) -> WooOrder {
+        WooOrder::builder("SPOT_BTC_USDT", "BUY", "LIMIT")
+            .price(price)
+            .size(OrderSize::Quantity(quantity))
+            .build()
+    }
+
+    fn rule(err: WooError) -> RiskRule {
+        match err {
+            WooError::RiskRejected { rule, .. } => rule,
+            other => panic!("expected a risk rejection, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_symbols_outside_the_allow_list() {
+        let server = MockServer::start().await;
+        let limits = RiskLimits {
+            allowed_symbols: Some(HashSet::from(["PERP_BTC_USDT".to_string()])),
+            ..Default::default()
+        };
+        let woo = guarded_woo(&server, limits).await;
+
+        let err = woo.create_order(buy(dec!(100), dec!(1))).await.unwrap_err();
+        assert_eq!(rule(err), RiskRule::AllowedSymbols);
+    }
+
+    #[tokio::test]
+    async fn market_order_notional_uses_the_mid() {
+        let server = MockServer::start().await;
+        let limits = RiskLimits {
+            max_order_notional: Some(dec!(1000)),
+            ..Default::default()
+        };
+        let woo = guarded_woo(&server, limits).await;
+
+        let order = WooOrder::builder("SPOT_BTC_USDT", "BUY", "MARKET")
+            .size(OrderSize::Quantity(dec!(11)))
+            .build();
+        let err = woo.create_order(order).await.unwrap_err();
+        assert_eq!(rule(err), RiskRule::MaxOrderNotional);
+    }
+
+    #[tokio::test]
+    async fn price_collar_catches_fat_fingers() {
+        let server = MockServer::start().await;
+        let limits = RiskLimits {
+            price_collar: Some(PriceCollar {
+                max_deviation: dec!(0.05),
+                max_staleness: Duration::from_secs(1),
+            }),
+            ..Default::default()
+        };
+        let woo = guarded_woo(&server, limits).await;
+
+        let err = woo
+            .create_order(buy(dec!(1000), dec!(1)))
+            .await
+            .unwrap_err();
+        assert_eq!(rule(err), RiskRule::PriceCollar);
+    }
+}
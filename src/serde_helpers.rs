@@ -0,0 +1,376 @@
+use chrono::{DateTime, Utc};
+use serde::de::{self, Deserializer, Unexpected, Visitor};
+use std::fmt;
+
+/// Accepts `true`/`false`, `"true"`/`"false"` and `1`/`0`, since WOO encodes
+/// booleans differently depending on the endpoint.
+pub fn deserialize_flexible_bool<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_any(FlexibleBoolVisitor)
+}
+
+/// [`deserialize_flexible_bool`] for optional fields; pair it with
+/// `#[serde(default)]` so a missing field still becomes `None`.
+pub fn deserialize_flexible_bool_opt<'de, D>(deserializer: D) -> Result<Option<bool>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct OptVisitor;
+
+    impl<'de> Visitor<'de> for OptVisitor {
+        type Value = Option<bool>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("an optional boolean, \"true\"/\"false\" or 0/1")
+        }
+
+        fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            deserialize_flexible_bool(deserializer).map(Some)
+        }
+    }
+
+    deserializer.deserialize_option(OptVisitor)
+}
+
+struct FlexibleBoolVisitor;
+
+impl<'de> Visitor<'de> for FlexibleBoolVisitor {
+    type Value = bool;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a boolean, \"true\"/\"false\" or 0/1")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<bool, E> {
+        Ok(v)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<bool, E> {
+        match v {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(E::invalid_value(Unexpected::Unsigned(v), &self)),
+        }
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<bool, E> {
+        match v {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(E::invalid_value(Unexpected::Signed(v), &self)),
+        }
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<bool, E> {
+        match v {
+            "true" | "1" => Ok(true),
+            "false" | "0" => Ok(false),
+            _ => Err(E::invalid_value(Unexpected::Str(v), &self)),
+        }
+    }
+}
+
+/// Accepts seconds since the epoch as a float (`1578565539.808`) or as a
+/// string (`"1578565539.808"`), the two ways WOO sends timestamps, as well as
+/// RFC 3339 so serialized values read back.
+pub fn deserialize_woo_timestamp<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_any(WooTimestampVisitor)
+}
+
+struct WooTimestampVisitor;
+
+impl<'de> Visitor<'de> for WooTimestampVisitor {
+    type Value = DateTime<Utc>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("seconds since the epoch, as a number or a string")
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<DateTime<Utc>, E> {
+        // WOO timestamps have millisecond precision, rounding drops the float
+        // noise below it
+        DateTime::from_timestamp_millis((v * 1000.0).round() as i64)
+            .ok_or_else(|| E::invalid_value(Unexpected::Float(v), &self))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<DateTime<Utc>, E> {
+        i64::try_from(v)
+            .ok()
+            .and_then(|v| DateTime::from_timestamp(v, 0))
+            .ok_or_else(|| E::invalid_value(Unexpected::Unsigned(v), &self))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<DateTime<Utc>, E> {
+        DateTime::from_timestamp(v, 0).ok_or_else(|| E::invalid_value(Unexpected::Signed(v), &self))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<DateTime<Utc>, E> {
+        parse_woo_timestamp(v)
+            .or_else(|| {
+                DateTime::parse_from_rfc3339(v)
+                    .ok()
+                    .map(|time| time.with_timezone(&Utc))
+            })
+            .ok_or_else(|| E::invalid_value(Unexpected::Str(v), &self))
+    }
+}
+
+/// Parses WOO's `"1578565539.808"` seconds-since-epoch timestamps.
+pub(crate) fn parse_woo_timestamp(timestamp: &str) -> Option<DateTime<Utc>> {
+    let (seconds, fraction) = timestamp.split_once('.').unwrap_or((timestamp, ""));
+    let nanos = format!("{fraction:0<9}");
+
+    DateTime::from_timestamp(seconds.parse().ok()?, nanos.get(..9)?.parse().ok()?)
+}
+
+/// Numbers WOO sends either as JSON numbers or as strings (`123`, `"123"`,
+/// `"123.45"`), parsed with the target type's `FromStr`:
+///
+/// ```text
+/// #[serde(deserialize_with = "string_or_number::deserialize")]
+/// pub price: f64,
+/// #[serde(default, deserialize_with = "string_or_number::deserialize_option")]
+/// pub amount: Option<f64>,
+/// ```
+pub mod string_or_number {
+    use serde::de::{self, Deserializer, Visitor};
+    use std::fmt;
+    use std::marker::PhantomData;
+    use std::str::FromStr;
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        deserializer.deserialize_any(StringOrNumberVisitor(PhantomData))
+    }
+
+    /// [`deserialize`] for optional fields; pair it with `#[serde(default)]`
+    /// so a missing field still becomes `None`.
+    pub fn deserialize_option<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        deserializer.deserialize_option(OptionVisitor(PhantomData))
+    }
+
+    struct StringOrNumberVisitor<T>(PhantomData<T>);
+
+    impl<T> StringOrNumberVisitor<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        fn parse<E: de::Error>(v: &str) -> Result<T, E> {
+            v.trim()
+                .parse()
+                .map_err(|err| E::custom(format!("invalid number {v:?}: {err}")))
+        }
+    }
+
+    impl<T> Visitor<'_> for StringOrNumberVisitor<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        type Value = T;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a number or a string holding one")
+        }
+
+        fn visit_u64<E: de::Error>(self, v: u64) -> Result<T, E> {
+            Self::parse(&v.to_string())
+        }
+
+        fn visit_i64<E: de::Error>(self, v: i64) -> Result<T, E> {
+            Self::parse(&v.to_string())
+        }
+
+        fn visit_f64<E: de::Error>(self, v: f64) -> Result<T, E> {
+            Self::parse(&v.to_string())
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<T, E> {
+            Self::parse(v)
+        }
+    }
+
+    struct OptionVisitor<T>(PhantomData<T>);
+
+    impl<'de, T> Visitor<'de> for OptionVisitor<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        type Value = Option<T>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("an optional number or a string holding one")
+        }
+
+        fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            deserialize(deserializer).map(Some)
+        }
+    }
+}
+
+/// Decimals sent to WOO, as the plain digits `Decimal` displays (`9000.5`),
+/// whatever the serde features of `rust_decimal` another crate in the build
+/// turns on. Display never looks at the locale nor switches to an exponent,
+/// so what gets signed always has a dot:
+///
+/// ```text
+/// #[serde(serialize_with = "decimal_string::serialize")]
+/// pub amount: Decimal,
+/// #[serde(serialize_with = "decimal_string::serialize_option")]
+/// pub order_price: Option<Decimal>,
+/// ```
+pub mod decimal_string {
+    use rust_decimal::Decimal;
+    use serde::Serializer;
+
+    pub fn serialize<S: Serializer>(value: &Decimal, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub fn serialize_option<S: Serializer>(
+        value: &Option<Decimal>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => serializer.collect_str(value),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::woo_data_structs::{CancelOrderRes, SendOrderRes};
+    use serde::Deserialize;
+
+    #[test]
+    fn flexible_bool_accepts_all_representations() {
+        for success in [r#"true"#, r#""true""#, r#"1"#] {
+            let body = format!(r#"{{"success":{success},"status":"CANCEL_SENT"}}"#);
+            let res: CancelOrderRes = serde_json::from_str(&body).unwrap();
+            assert!(res.success, "{success} should decode to true");
+        }
+    }
+
+    #[test]
+    fn flexible_bool_rejects_garbage() {
+        let body = r#"{"success":"yes","status":"CANCEL_SENT"}"#;
+        assert!(serde_json::from_str::<CancelOrderRes>(body).is_err());
+    }
+
+    #[test]
+    fn optional_flexible_bool() {
+        for (reduce_only, expected) in [
+            (r#","reduce_only":"true""#, Some(true)),
+            (r#","reduce_only":0"#, Some(false)),
+            (r#","reduce_only":null"#, None),
+            ("", None),
+        ] {
+            let body = format!(
+                r#"{{"success":1,"timestamp":"1578565539.808","order_id":13,"order_type":"LIMIT","client_order_id":0{reduce_only}}}"#
+            );
+            let res: SendOrderRes = serde_json::from_str(&body).unwrap();
+            assert!(res.success);
+            assert_eq!(res.reduce_only, expected);
+        }
+    }
+
+    #[test]
+    fn timestamp_string_and_float_are_the_same_instant() {
+        #[derive(Deserialize)]
+        struct Timestamped {
+            #[serde(deserialize_with = "deserialize_woo_timestamp")]
+            created_time: DateTime<Utc>,
+        }
+
+        let from_string: Timestamped =
+            serde_json::from_str(r#"{"created_time":"1578565539.808"}"#).unwrap();
+        let from_float: Timestamped =
+            serde_json::from_str(r#"{"created_time":1578565539.808}"#).unwrap();
+
+        assert_eq!(from_string.created_time, from_float.created_time);
+        assert_eq!(
+            from_float.created_time.timestamp_millis(),
+            1_578_565_539_808
+        );
+
+        let rfc3339: Timestamped =
+            serde_json::from_str(r#"{"created_time":"2020-01-09T10:25:39.808Z"}"#).unwrap();
+        assert_eq!(rfc3339.created_time, from_float.created_time);
+    }
+
+    #[derive(Deserialize)]
+    struct Numbers {
+        #[serde(deserialize_with = "string_or_number::deserialize")]
+        price: f64,
+        #[serde(deserialize_with = "string_or_number::deserialize")]
+        order_id: u64,
+        #[serde(default, deserialize_with = "string_or_number::deserialize_option")]
+        amount: Option<f64>,
+    }
+
+    #[test]
+    fn string_or_number_accepts_every_representation() {
+        for body in [
+            r#"{"price":123,"order_id":7,"amount":null}"#,
+            r#"{"price":"123","order_id":"7"}"#,
+            r#"{"price":123.0,"order_id":7}"#,
+        ] {
+            let numbers: Numbers = serde_json::from_str(body).unwrap();
+            assert_eq!(numbers.price, 123.0, "{body}");
+            assert_eq!(numbers.order_id, 7, "{body}");
+            assert_eq!(numbers.amount, None, "{body}");
+        }
+
+        let numbers: Numbers =
+            serde_json::from_str(r#"{"price":"123.45","order_id":7,"amount":"0.5"}"#).unwrap();
+        assert_eq!(numbers.price, 123.45);
+        assert_eq!(numbers.amount, Some(0.5));
+    }
+
+    #[test]
+    fn string_or_number_rejects_garbage() {
+        assert!(serde_json::from_str::<Numbers>(r#"{"price":"abc","order_id":7}"#).is_err());
+        assert!(serde_json::from_str::<Numbers>(r#"{"price":1,"order_id":"1.5"}"#).is_err());
+        assert!(serde_json::from_str::<Numbers>(r#"{"price":true,"order_id":7}"#).is_err());
+    }
+}
@@ -0,0 +1,335 @@
+use crate::error::WooError;
+use crate::woo::Woo;
+use crate::woo_data_structs::CancelOrderRes;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// How far [`Woo::shutdown`] goes before returning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownPolicy {
+    /// Waits up to `deadline` for in-flight requests, leaving orders alone.
+    Drain { deadline: Duration },
+    /// Drains like [`ShutdownPolicy::Drain`], then cancels every open order
+    /// on every symbol and disarms the dead-man's switch.
+    CancelAll { deadline: Duration },
+}
+
+#[derive(Debug)]
+pub struct ShutdownReport {
+    /// Requests still running at the deadline, 0 if everything drained.
+    pub abandoned_requests: usize,
+    /// Orders still being checked or sent at the deadline, which may land
+    /// after the report, 0 if everything drained.
+    pub abandoned_orders: usize,
+    /// The cancel all response, `None` under [`ShutdownPolicy::Drain`].
+    pub cancelled: Option<CancelOrderRes>,
+}
+
+/// Requests currently on the wire, and orders being checked or sent, shared
+/// by every clone of a client.
+#[derive(Debug)]
+pub(crate) struct InFlight {
+    count: watch::Sender<usize>,
+    orders: watch::Sender<usize>,
+    closing: AtomicBool,
+}
+
+impl Default for InFlight {
+    fn default() -> Self {
+        Self {
+            count: watch::Sender::new(0),
+            orders: watch::Sender::new(0),
+            closing: AtomicBool::new(false),
+        }
+    }
+}
+
+impl InFlight {
+    /// Counts a request until the returned guard is dropped.
+    pub(crate) fn start(&self) -> InFlightGuard<'_> {
+        InFlightGuard::new(&self.count)
+    }
+
+    /// Counts an order from its first check until the returned guard is
+    /// dropped, so a shutdown between the lookups of its checks still waits
+    /// for it to be sent.
+    pub(crate) fn start_order(&self) -> InFlightGuard<'_> {
+        InFlightGuard::new(&self.orders)
+    }
+
+    pub(crate) fn is_closing(&self) -> bool {
+        self.closing.load(Ordering::Relaxed)
+    }
+
+    /// Waits until nothing is in flight or `deadline` passed, returning how
+    /// many requests and orders are left.
+    async fn drain(&self, deadline: Duration) -> (usize, usize) {
+        let mut orders = self.orders.subscribe();
+        let mut count = self.count.subscribe();
+        let _ = tokio::time::timeout(deadline, async {
+            let _ = orders.wait_for(|orders| *orders == 0).await;
+            let _ = count.wait_for(|count| *count == 0).await;
+        })
+        .await;
+        (*self.count.borrow(), *self.orders.borrow())
+    }
+}
+
+pub(crate) struct InFlightGuard<'a>(&'a watch::Sender<usize>);
+
+impl<'a> InFlightGuard<'a> {
+    fn new(count: &'a watch::Sender<usize>) -> Self {
+        count.send_modify(|count| *count += 1);
+        Self(count)
+    }
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.0.send_modify(|count| *count -= 1);
+    }
+}
+
+impl Woo {
+    /// Winds the client down for a process exit. New orders are refused from
+    /// here on, by every clone, with [`WooError::ShuttingDown`]; cancels and
+    /// reads still go through.
+    ///
+    /// Under [`ShutdownPolicy::CancelAll`] the switch is only disarmed once
+    /// the cancel succeeded. If it failed the error is returned and the
+    /// switch, if armed, stays armed to cancel the orders on its own.
+    pub async fn shutdown(self, policy: ShutdownPolicy) -> Result<ShutdownReport, WooError> {
+        self.in_flight.closing.store(true, Ordering::Relaxed);
+
+        let deadline = match policy {
+            ShutdownPolicy::Drain { deadline } | ShutdownPolicy::CancelAll { deadline } => deadline,
+        };
+        let (abandoned_requests, abandoned_orders) = self.in_flight.drain(deadline).await;
+        if abandoned_requests > 0 || abandoned_orders > 0 {
+            tracing::warn!(
+                abandoned_requests,
+                abandoned_orders,
+                "shutdown deadline passed with requests in flight"
+            );
+        }
+
+        let cancelled = match policy {
+            ShutdownPolicy::Drain { .. } => None,
+            ShutdownPolicy::CancelAll { .. } => {
+                let cancelled = self.cancel_all_pending().await?;
+                self.set_cancel_all_after(Duration::ZERO).await?;
+                Some(cancelled)
+            }
+        };
+
+        Ok(ShutdownReport {
+            abandoned_requests,
+            abandoned_orders,
+            cancelled,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{mock_woo, ok, order_res};
+    use crate::woo_data_structs::{CancelStatus, WooOrder};
+    use rust_decimal_macros::dec;
+    use wiremock::matchers::{body_string, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn order() -> WooOrder {
+        WooOrder::post_only("SPOT_BTC_USDT", "BUY", dec!(9000), dec!(0.1))
+    }
+
+    async fn mount_slow_order(server: &MockServer, delay: Duration) {
+        Mock::given(method("POST"))
+            .and(path("/v1/order"))
+            .respond_with(ok(order_res(9)).set_delay(delay))
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn drains_then_cancels_and_disarms() {
+        let server = MockServer::start().await;
+        mount_slow_order(&server, Duration::from_millis(200)).await;
+        Mock::given(method("DELETE"))
+            .and(path("/v3/orders/pending"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true, "data": { "status": "CANCEL_ALL_SENT" },
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/order/cancel_all_after"))
+            .and(body_string("trigger_after=0"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true, "data": { "expected_trigger_time": 0 },
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let woo = mock_woo(&server);
+        let pending = tokio::spawn({
+            let woo = woo.clone();
+            async move { woo.create_order(order()).await }
+        });
+        // let the order get on the wire before shutting down
+        while server.received_requests().await.unwrap().is_empty() {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        let straggler = woo.clone();
+
+        let report = woo
+            .shutdown(ShutdownPolicy::CancelAll {
+                deadline: Duration::from_secs(5),
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(report.abandoned_requests, 0);
+        assert_eq!(report.abandoned_orders, 0);
+        assert_eq!(
+            report.cancelled.unwrap().status,
+            CancelStatus::CancelAllSent
+        );
+        assert!(pending.await.unwrap().is_ok());
+        assert!(matches!(
+            straggler.create_order(order()).await,
+            Err(WooError::ShuttingDown)
+        ));
+
+        let paths: Vec<_> = server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .map(|request| request.url.path().to_string())
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                "/v1/order",
+                "/v3/orders/pending",
+                "/v1/order/cancel_all_after"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn gives_up_draining_at_the_deadline() {
+        let server = MockServer::start().await;
+        mount_slow_order(&server, Duration::from_secs(10)).await;
+
+        let woo = mock_woo(&server);
+        tokio::spawn({
+            let woo = woo.clone();
+            async move { woo.create_order(order()).await }
+        });
+        while server.received_requests().await.unwrap().is_empty() {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        let report = woo
+            .shutdown(ShutdownPolicy::Drain {
+                deadline: Duration::from_millis(50),
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(report.abandoned_requests, 1);
+        assert_eq!(report.abandoned_orders, 1);
+        assert!(report.cancelled.is_none());
+    }
+
+    #[tokio::test]
+    async fn waits_for_an_order_still_being_checked() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/client/info"))
+            .respond_with(
+                ok(serde_json::json!({
+                    "success": true,
+                    "application": {
+                        "application_id": "app", "account": "main",
+                        "account_mode": "FUTURES", "leverage": 5,
+                    },
+                }))
+                .set_delay(Duration::from_millis(100)),
+            )
+            .mount(&server)
+            .await;
+        mount_slow_order(&server, Duration::from_millis(100)).await;
+
+        let woo = mock_woo(&server);
+        let pending = tokio::spawn({
+            let woo = woo.clone();
+            async move {
+                let perp = WooOrder::post_only("PERP_BTC_USDT", "BUY", dec!(9000), dec!(0.1));
+                woo.create_order(perp).await
+            }
+        });
+        // shut down while the account mode is being looked up
+        while server.received_requests().await.unwrap().is_empty() {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        let report = woo
+            .shutdown(ShutdownPolicy::Drain {
+                deadline: Duration::from_secs(5),
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(report.abandoned_requests, 0);
+        assert_eq!(report.abandoned_orders, 0);
+        assert!(pending.is_finished());
+        assert!(pending.await.unwrap().is_ok());
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn reports_an_order_still_being_checked_at_the_deadline() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/client/info"))
+            .respond_with(
+                ok(serde_json::json!({
+                    "success": true,
+                    "application": {
+                        "application_id": "app", "account": "main",
+                        "account_mode": "FUTURES", "leverage": 5,
+                    },
+                }))
+                .set_delay(Duration::from_secs(10)),
+            )
+            .mount(&server)
+            .await;
+
+        let woo = mock_woo(&server);
+        tokio::spawn({
+            let woo = woo.clone();
+            async move {
+                let perp = WooOrder::post_only("PERP_BTC_USDT", "BUY", dec!(9000), dec!(0.1));
+                woo.create_order(perp).await
+            }
+        });
+        while server.received_requests().await.unwrap().is_empty() {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        let report = woo
+            .shutdown(ShutdownPolicy::Drain {
+                deadline: Duration::from_millis(50),
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(report.abandoned_orders, 1);
+    }
+}
@@ -0,0 +1,270 @@
+use crate::error::WooError;
+use crate::woo::Woo;
+use futures::future::BoxFuture;
+use hmac::{Hmac, Mac};
+use reqwest::header::HeaderValue;
+use secrecy::{ExposeSecret, SecretString};
+use sha2::Sha256;
+use std::sync::{Arc, RwLock};
+
+/// Computes the `x-api-signature` of a request, so the api secret can live
+/// elsewhere, e.g. in an HSM behind a local service. Set with
+/// [`WooBuilder::signer`](crate::builder::WooBuilder::signer); the default is
+/// [`HmacSigner`] over the api secret of the credentials.
+///
+/// `payload` is the exact string WOO expects signed: the sorted parameters
+/// and timestamp joined by `|` on `v1`, `{timestamp}{METHOD}{path}{body}` on
+/// `v3`. The signature comes back in lowercase hex. The future is boxed, an
+/// `async move` block in `Box::pin`, so the client can hold any
+/// `Arc<dyn Signer>`.
+pub trait Signer: Send + Sync {
+    fn sign<'a>(&'a self, payload: &'a [u8]) -> BoxFuture<'a, anyhow::Result<String>>;
+}
+
+/// HMAC-SHA256 in process, keyed with the api secret.
+pub struct HmacSigner {
+    secret: SecretString,
+}
+
+impl HmacSigner {
+    pub fn new(secret: SecretString) -> Self {
+        Self { secret }
+    }
+}
+
+impl Signer for HmacSigner {
+    fn sign<'a>(&'a self, payload: &'a [u8]) -> BoxFuture<'a, anyhow::Result<String>> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.secret.expose_secret().as_bytes())
+            .expect("HMAC failed");
+        mac.update(payload);
+        let signature = hex::encode(mac.finalize().into_bytes());
+
+        Box::pin(async move { Ok(signature) })
+    }
+}
+
+/// An api key and what signs for it, only ever replaced together.
+pub(crate) struct SigningKey {
+    pub(crate) api_key: HeaderValue,
+    signer: Arc<dyn Signer>,
+}
+
+impl SigningKey {
+    pub(crate) fn new(api_key: &str, signer: Arc<dyn Signer>) -> Result<Self, WooError> {
+        let mut api_key = HeaderValue::from_str(api_key)
+            .map_err(|_| WooError::Config("api key is not a valid header value".to_string()))?;
+        api_key.set_sensitive(true);
+
+        Ok(Self { api_key, signer })
+    }
+
+    pub(crate) async fn sign(&self, payload: &str) -> Result<String, WooError> {
+        self.signer
+            .sign(payload.as_bytes())
+            .await
+            .map_err(|err| WooError::Signing(err.into()))
+    }
+}
+
+/// The [`SigningKey`] new requests pick up. A request holds on to the one it
+/// started with, so rotating never pairs a key with another key's signature.
+pub(crate) struct ActiveKey {
+    current: RwLock<Arc<SigningKey>>,
+}
+
+impl ActiveKey {
+    pub(crate) fn new(key: SigningKey) -> Self {
+        Self {
+            current: RwLock::new(Arc::new(key)),
+        }
+    }
+
+    pub(crate) fn current(&self) -> Arc<SigningKey> {
+        self.current.read().unwrap().clone()
+    }
+
+    pub(crate) fn replace(&self, key: SigningKey) {
+        *self.current.write().unwrap() = Arc::new(key);
+    }
+}
+
+impl Woo {
+    /// Signs every request from now on with `api_key` and an HMAC over
+    /// `api_secret`, e.g. to move to a newly provisioned key while the old
+    /// one still works. A custom [`Signer`] is replaced as well. Requests
+    /// already signed go out and complete with the old key. Applies to every
+    /// clone of this client.
+    pub fn rotate_credentials(
+        &self,
+        api_key: impl AsRef<str>,
+        api_secret: impl Into<String>,
+    ) -> Result<(), WooError> {
+        let signer = HmacSigner::new(SecretString::from(api_secret.into()));
+        self.key
+            .replace(SigningKey::new(api_key.as_ref(), Arc::new(signer))?);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::Credentials;
+    use crate::error::WooError;
+    use crate::woo::{Environment, Woo};
+    use crate::woo_data_structs::WooOrder;
+    use rust_decimal_macros::dec;
+    use std::sync::Mutex;
+    use tokio::sync::Notify;
+    use url::Url;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// Stands in for a signing service: answers asynchronously, with a key
+    /// the client never sees.
+    #[derive(Default)]
+    struct RemoteSigner {
+        payloads: Mutex<Vec<String>>,
+        unavailable: bool,
+    }
+
+    impl Signer for RemoteSigner {
+        fn sign<'a>(&'a self, payload: &'a [u8]) -> BoxFuture<'a, anyhow::Result<String>> {
+            Box::pin(async move {
+                tokio::task::yield_now().await;
+                anyhow::ensure!(!self.unavailable, "signing service unavailable");
+                let payload = String::from_utf8(payload.to_vec())?;
+                self.payloads.lock().unwrap().push(payload.clone());
+                HmacSigner::new(SecretString::from("hsm-key".to_string()))
+                    .sign(payload.as_bytes())
+                    .await
+            })
+        }
+    }
+
+    /// Signs with `old-secret` once released, to hold a request mid-signing.
+    #[derive(Default)]
+    struct GatedSigner {
+        entered: Notify,
+        release: Notify,
+    }
+
+    impl Signer for GatedSigner {
+        fn sign<'a>(&'a self, payload: &'a [u8]) -> BoxFuture<'a, anyhow::Result<String>> {
+            Box::pin(async move {
+                self.entered.notify_one();
+                self.release.notified().await;
+                HmacSigner::new(SecretString::from("old-secret".to_string()))
+                    .sign(payload)
+                    .await
+            })
+        }
+    }
+
+    fn woo(server: &MockServer, signer: Arc<dyn Signer>) -> Woo {
+        Woo::builder(Environment::Staging, Credentials::new("key", ""))
+            .base_url(Url::parse(&server.uri()).unwrap())
+            .signer(signer)
+            .build()
+            .unwrap()
+    }
+
+    fn order() -> WooOrder {
+        WooOrder::limit("SPOT_BTC_USDT", "BUY", dec!(9000), dec!(1))
+    }
+
+    async fn accept_orders(server: &MockServer) {
+        Mock::given(method("POST"))
+            .and(path("/v1/order"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true, "timestamp": "1601471652.928", "order_id": 1,
+                "order_type": "LIMIT", "client_order_id": 0, "order_price": 9000,
+                "order_quantity": 1, "order_amount": null, "reduce_only": false,
+            })))
+            .mount(server)
+            .await;
+    }
+
+    async fn send_order(server: &MockServer, signer: Arc<RemoteSigner>) -> Result<(), WooError> {
+        woo(server, signer).create_order(order()).await.map(drop)
+    }
+
+    #[tokio::test]
+    async fn requests_carry_the_signature_of_the_configured_signer() {
+        let server = MockServer::start().await;
+        accept_orders(&server).await;
+        let signer = Arc::new(RemoteSigner::default());
+
+        send_order(&server, signer.clone()).await.unwrap();
+
+        let request = &server.received_requests().await.unwrap()[0];
+        let body = String::from_utf8(request.body.clone()).unwrap();
+        let timestamp = request.headers["x-api-timestamp"].to_str().unwrap();
+        assert_eq!(
+            *signer.payloads.lock().unwrap(),
+            [format!("{body}|{timestamp}")]
+        );
+        assert_eq!(
+            request.headers["x-api-signature"].to_str().unwrap(),
+            Woo::generate_hmac_sha256_signature(body, timestamp.parse().unwrap(), "hsm-key")
+        );
+    }
+
+    #[tokio::test]
+    async fn nothing_is_sent_when_signing_fails() {
+        let server = MockServer::start().await;
+        let signer = Arc::new(RemoteSigner {
+            unavailable: true,
+            ..RemoteSigner::default()
+        });
+
+        let err = send_order(&server, signer).await.unwrap_err();
+
+        assert!(matches!(err, WooError::Signing(_)));
+        assert!(err.to_string().contains("signing service unavailable"));
+        assert!(server.received_requests().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn requests_in_flight_keep_the_key_they_were_signed_for() {
+        let server = MockServer::start().await;
+        accept_orders(&server).await;
+        let signer = Arc::new(GatedSigner::default());
+        let woo = Woo::builder(Environment::Staging, Credentials::new("old-key", ""))
+            .base_url(Url::parse(&server.uri()).unwrap())
+            .signer(signer.clone())
+            .build()
+            .unwrap();
+
+        let in_flight = tokio::spawn({
+            let woo = woo.clone();
+            async move { woo.create_order(order()).await }
+        });
+        signer.entered.notified().await;
+        woo.rotate_credentials("new-key", "new-secret").unwrap();
+        woo.create_order(order()).await.unwrap();
+        signer.release.notify_one();
+        in_flight.await.unwrap().unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let keys: Vec<_> = requests
+            .iter()
+            .map(|request| request.headers["x-api-key"].to_str().unwrap())
+            .collect();
+        assert_eq!(keys, ["new-key", "old-key"]);
+        for (request, key) in requests.iter().zip(keys) {
+            let secret = if key == "old-key" {
+                "old-secret"
+            } else {
+                "new-secret"
+            };
+            let body = String::from_utf8(request.body.clone()).unwrap();
+            let timestamp = request.headers["x-api-timestamp"].to_str().unwrap();
+            assert_eq!(
+                request.headers["x-api-signature"].to_str().unwrap(),
+                Woo::generate_hmac_sha256_signature(body, timestamp.parse().unwrap(), secret)
+            );
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use crate::error::{WooError, WooErrorCode};
+use crate::latency::endpoint_key;
+use crate::woo::Woo;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// Outcomes of the requests to one endpoint, see [`Woo::stats`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EndpointStats {
+    pub successes: u64,
+    /// Errors WOO answered with, by code.
+    pub api_errors: HashMap<WooErrorCode, u64>,
+    /// Requests that never got a readable answer: transport and decode
+    /// errors.
+    pub other_failures: u64,
+}
+
+impl EndpointStats {
+    pub fn failures(&self) -> u64 {
+        self.api_errors.values().sum::<u64>() + self.other_failures
+    }
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    successes: AtomicU64,
+    api_errors: RwLock<HashMap<i64, AtomicU64>>,
+    other_failures: AtomicU64,
+}
+
+impl Counters {
+    fn api_error(&self, code: i64) {
+        if let Some(count) = self.api_errors.read().unwrap().get(&code) {
+            count.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        self.api_errors
+            .write()
+            .unwrap()
+            .entry(code)
+            .or_default()
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> EndpointStats {
+        EndpointStats {
+            successes: self.successes.load(Ordering::Relaxed),
+            api_errors: self
+                .api_errors
+                .read()
+                .unwrap()
+                .iter()
+                .map(|(code, count)| (WooErrorCode::from(*code), count.load(Ordering::Relaxed)))
+                .collect(),
+            other_failures: self.other_failures.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Per endpoint counts of request outcomes. Past the first request to an
+/// endpoint, or the first error with a given code, counting is a read lock
+/// and an atomic increment.
+#[derive(Debug, Default)]
+pub(crate) struct RequestStats {
+    endpoints: RwLock<HashMap<String, Arc<Counters>>>,
+}
+
+impl RequestStats {
+    pub(crate) fn record<R>(&self, path: &str, result: &Result<R, WooError>) {
+        let counters = self.counters(path);
+        match result {
+            Ok(_) => {
+                counters.successes.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(WooError::Api(err)) => counters.api_error(err.code),
+            Err(_) => {
+                counters.other_failures.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn counters(&self, path: &str) -> Arc<Counters> {
+        let endpoint = endpoint_key(path);
+        if let Some(counters) = self.endpoints.read().unwrap().get(&endpoint) {
+            return counters.clone();
+        }
+        self.endpoints
+            .write()
+            .unwrap()
+            .entry(endpoint)
+            .or_default()
+            .clone()
+    }
+
+    fn snapshot(&self) -> BTreeMap<String, EndpointStats> {
+        self.endpoints
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(endpoint, counters)| (endpoint.clone(), counters.snapshot()))
+            .collect()
+    }
+
+    fn reset(&self) {
+        self.endpoints.write().unwrap().clear();
+    }
+}
+
+impl Woo {
+    /// Successes and failures of every endpoint requested so far, keyed like
+    /// [`Woo::endpoint_latency`] (e.g. `v1/order` or `v1/order/{id}`), with
+    /// the failures broken down by WOO error code. Always on and shared by
+    /// every clone of this client.
+    pub fn stats(&self) -> BTreeMap<String, EndpointStats> {
+        self.stats.snapshot()
+    }
+
+    /// Starts counting from zero again.
+    pub fn reset_stats(&self) {
+        self.stats.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::mock_woo;
+    use crate::woo_data_structs::WooOrder;
+    use rust_decimal_macros::dec;
+    use wiremock::matchers::{body_string_contains, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn counts_outcomes_per_endpoint_and_error_code() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/order"))
+            .and(body_string_contains("order_quantity=1&"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true, "timestamp": "1601471652.928", "order_id": 1,
+                "order_type": "LIMIT", "client_order_id": 0, "order_price": 9000,
+                "order_quantity": 1, "order_amount": null, "reduce_only": false,
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/order"))
+            .and(body_string_contains("order_quantity=100"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                "success": false, "code": -1101, "message": "The risk exposure is too high",
+            })))
+            .mount(&server)
+            .await;
+        let woo = mock_woo(&server);
+        let order = |quantity| WooOrder::limit("SPOT_BTC_USDT", "BUY", dec!(9000), quantity);
+
+        woo.create_order(order(dec!(1))).await.unwrap();
+        for _ in 0..3 {
+            woo.create_order(order(dec!(100))).await.unwrap_err();
+        }
+
+        let stats = woo.stats();
+        let orders = &stats["v1/order"];
+        assert_eq!(orders.successes, 1);
+        assert_eq!(orders.api_errors[&WooErrorCode::RiskTooHigh], 3);
+        assert_eq!(orders.failures(), 3);
+
+        woo.reset_stats();
+        assert!(woo.stats().is_empty());
+    }
+}
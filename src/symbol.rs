@@ -0,0 +1,139 @@
+use crate::error::WooError;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Market {
+    Spot,
+    Perp,
+}
+
+impl Market {
+    fn prefix(self) -> &'static str {
+        match self {
+            Market::Spot => "SPOT",
+            Market::Perp => "PERP",
+        }
+    }
+}
+
+/// A WOO trading pair, e.g. `SPOT_BTC_USDT` or `PERP_1000PEPE_USDT`.
+///
+/// `Display`/`FromStr` use WOO's format, [`Symbol::from_display`] and
+/// [`Symbol::to_display`] convert from/to the `BTC/USDT` style most other
+/// tooling uses.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Symbol {
+    pub market: Market,
+    pub base: String,
+    pub quote: String,
+}
+
+impl Symbol {
+    pub fn new(market: Market, base: impl Into<String>, quote: impl Into<String>) -> Self {
+        Self {
+            market,
+            base: base.into().to_uppercase(),
+            quote: quote.into().to_uppercase(),
+        }
+    }
+
+    /// Parses `BTC/USDT`, `BTC-USDT` or `btc_usdt`. A ccxt style settlement
+    /// suffix (`BTC/USDT:USDT`) is ignored.
+    pub fn from_display(market: Market, display: &str) -> Result<Self, WooError> {
+        let pair = display.split(':').next().unwrap_or_default().trim();
+
+        let mut parts = pair.split(['/', '-', '_']);
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(base), Some(quote), None) if is_asset(base) && is_asset(quote) => {
+                Ok(Symbol::new(market, base, quote))
+            }
+            _ => Err(WooError::InvalidSymbol(display.to_string())),
+        }
+    }
+
+    pub fn to_display(&self, sep: char) -> String {
+        format!("{}{}{}", self.base, sep, self.quote)
+    }
+}
+
+fn is_asset(asset: &str) -> bool {
+    !asset.is_empty() && asset.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}_{}_{}", self.market.prefix(), self.base, self.quote)
+    }
+}
+
+impl FromStr for Symbol {
+    type Err = WooError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split('_');
+
+        let market = match parts.next() {
+            Some("SPOT") => Market::Spot,
+            Some("PERP") => Market::Perp,
+            _ => return Err(WooError::InvalidSymbol(s.to_string())),
+        };
+
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(base), Some(quote), None) if is_asset(base) && is_asset(quote) => {
+                Ok(Symbol::new(market, base, quote))
+            }
+            _ => Err(WooError::InvalidSymbol(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spot_from_display() {
+        for display in ["BTC/USDT", "BTC-USDT", "btc_usdt"] {
+            let symbol = Symbol::from_display(Market::Spot, display).unwrap();
+            assert_eq!(symbol.to_string(), "SPOT_BTC_USDT");
+        }
+    }
+
+    #[test]
+    fn perp_from_display() {
+        let symbol = Symbol::from_display(Market::Perp, "BTC/USDT:USDT").unwrap();
+        assert_eq!(symbol.to_string(), "PERP_BTC_USDT");
+
+        let symbol = Symbol::from_display(Market::Perp, "1000PEPE-USDT").unwrap();
+        assert_eq!(symbol.to_string(), "PERP_1000PEPE_USDT");
+    }
+
+    #[test]
+    fn to_display_round_trips() {
+        let spot: Symbol = "SPOT_ETH_USDC".parse().unwrap();
+        assert_eq!(spot.market, Market::Spot);
+        assert_eq!(spot.to_display('/'), "ETH/USDC");
+
+        let perp: Symbol = "PERP_1000PEPE_USDT".parse().unwrap();
+        assert_eq!(perp.market, Market::Perp);
+        assert_eq!(perp.base, "1000PEPE");
+        assert_eq!(perp.to_display('-'), "1000PEPE-USDT");
+
+        assert_eq!(
+            Symbol::from_display(Market::Perp, &perp.to_display('/')).unwrap(),
+            perp
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_symbols() {
+        for display in ["BTC", "BTC/USDT/ETH", "/USDT", "BTC/US DT"] {
+            assert!(Symbol::from_display(Market::Spot, display).is_err());
+        }
+
+        for woo in ["BTC_USDT", "FUT_BTC_USDT", "SPOT_BTC"] {
+            assert!(woo.parse::<Symbol>().is_err());
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use crate::error::WooError;
+use crate::woo::Woo;
+use crate::woo_data_structs::{ExchangeInfoRes, SymbolInfo, WooEnvelope, WooOrder};
+use reqwest::Method;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+pub(crate) const DEFAULT_SYMBOL_CACHE_TTL: Duration = Duration::from_secs(300);
+
+type Symbols = Arc<HashMap<String, SymbolInfo>>;
+
+/// Tick and lot sizes of every symbol, refetched from the exchange info
+/// endpoint once they are older than `ttl`.
+#[derive(Debug)]
+pub(crate) struct SymbolInfoCache {
+    ttl: Duration,
+    // held across the refetch so concurrent callers wait for one request
+    // instead of each sending their own
+    symbols: Mutex<Option<(Instant, Symbols)>>,
+}
+
+impl SymbolInfoCache {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            symbols: Mutex::new(None),
+        }
+    }
+}
+
+impl Woo {
+    /// Keeps symbol info for `ttl` instead of the default five minutes. The
+    /// cache is shared with clones made after this call.
+    pub fn with_symbol_cache(mut self, ttl: Duration) -> Self {
+        self.symbol_cache = Arc::new(SymbolInfoCache::new(ttl));
+        self
+    }
+
+    pub async fn get_exchange_info(&self) -> anyhow::Result<Vec<SymbolInfo>> {
+        Ok(self.exchange_info().await?)
+    }
+
+    /// Filters of `symbol`, served from the cache.
+    pub async fn get_symbol_info(&self, symbol: &str) -> anyhow::Result<SymbolInfo> {
+        Ok(self.symbol_info(symbol).await?)
+    }
+
+    /// Refetches symbol info now, regardless of its age.
+    pub async fn refresh_symbols(&self) -> anyhow::Result<()> {
+        let mut symbols = self.symbol_cache.symbols.lock().await;
+        *symbols = Some((Instant::now(), self.fetch_symbols().await?));
+        Ok(())
+    }
+
+    /// Checks `order` against its symbol's tick, lot and notional filters
+    /// before it costs a round trip to be rejected.
+    pub async fn validate_order(&self, order: &WooOrder) -> anyhow::Result<()> {
+        Ok(self.symbol_info(&order.symbol).await?.validate(order)?)
+    }
+
+    /// Rounds the price and quantity of `order` down to its symbol's tick and
+    /// lot size.
+    pub async fn snap_to(&self, mut order: WooOrder) -> anyhow::Result<WooOrder> {
+        let info = self.symbol_info(&order.symbol).await?;
+
+        order.order_price = order.order_price.map(|price| info.snap_price(price));
+        order.order_quantity = order
+            .order_quantity
+            .map(|quantity| info.snap_quantity(quantity));
+        order.visible_quantity = order
+            .visible_quantity
+            .map(|quantity| info.snap_quantity(quantity));
+
+        Ok(order)
+    }
+
+    pub(crate) async fn symbol_info(&self, symbol: &str) -> Result<SymbolInfo, WooError> {
+        let mut cached = self.symbol_cache.symbols.lock().await;
+
+        let symbols = match &*cached {
+            Some((fetched_at, symbols)) if fetched_at.elapsed() < self.symbol_cache.ttl => {
+                symbols.clone()
+            }
+            _ => {
+                let symbols = self.fetch_symbols().await?;
+                *cached = Some((Instant::now(), symbols.clone()));
+                symbols
+            }
+        };
+
+        symbols
+            .get(symbol)
+            .cloned()
+            .ok_or_else(|| WooError::InvalidSymbol(symbol.to_string()))
+    }
+
+    async fn exchange_info(&self) -> Result<Vec<SymbolInfo>, WooError> {
+        let res = self
+            .public_request::<_, WooEnvelope<ExchangeInfoRes>>(Method::GET, "v1/public/info", ())
+            .await?
+            .into_data()?;
+        Ok(res.rows)
+    }
+
+    async fn fetch_symbols(&self) -> Result<Symbols, WooError> {
+        let symbols = self
+            .exchange_info()
+            .await?
+            .into_iter()
+            .map(|info| (info.symbol.clone(), info))
+            .collect();
+
+        Ok(Arc::new(symbols))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, exchange_info, ok};
+    use crate::woo_data_structs::OrderSize;
+    use rust_decimal_macros::dec;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer};
+
+    async fn mock_woo(server: &MockServer) -> Woo {
+        Mock::given(method("GET"))
+            .and(path("/v1/public/info"))
+            .respond_with(ok(exchange_info()))
+            .expect(1)
+            .mount(server)
+            .await;
+
+        testing::mock_woo(server)
+    }
+
+    #[tokio::test]
+    async fn validations_within_ttl_fetch_exchange_info_once() {
+        let server = MockServer::start().await;
+        let woo = mock_woo(&server)
+            .await
+            .with_symbol_cache(Duration::from_secs(60));
+
+        let order = WooOrder::builder("SPOT_BTC_USDT", "BUY", "LIMIT")
+            .price(dec!(9000.01))
+            .size(OrderSize::Quantity(dec!(0.5)))
+            .build();
+        woo.validate_order(&order).await.unwrap();
+
+        let off_tick = WooOrder::builder("SPOT_BTC_USDT", "BUY", "LIMIT")
+            .price(dec!(9000.011))
+            .size(OrderSize::Quantity(dec!(0.5)))
+            .build();
+        let err = woo.clone().validate_order(&off_tick).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<WooError>(),
+            Some(WooError::InvalidOrder(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn snaps_price_and_quantity_down() {
+        let server = MockServer::start().await;
+        let woo = mock_woo(&server).await;
+
+        let order = WooOrder::builder("SPOT_BTC_USDT", "BUY", "LIMIT")
+            .price(dec!(9000.019))
+            .size(OrderSize::Quantity(dec!(0.12345)))
+            .build();
+        let snapped = woo.snap_to(order).await.unwrap();
+
+        assert_eq!(snapped.order_price, Some(dec!(9000.01)));
+        assert_eq!(snapped.order_quantity, Some(dec!(0.1234)));
+        woo.validate_order(&snapped).await.unwrap();
+    }
+}
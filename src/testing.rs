@@ -0,0 +1,103 @@
+//! Fixtures shared by the unit tests: a client pointed at a mock server and
+//! the response bodies most tests need, as WOO documents them.
+
+use crate::builder::Credentials;
+use crate::woo::{Environment, Woo};
+use serde_json::Value;
+use url::Url;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// A staging client with key `key` and secret `secret`, sending to `server`.
+pub(crate) fn mock_woo(server: &MockServer) -> Woo {
+    Woo::builder(Environment::Staging, Credentials::new("key", "secret"))
+        .base_url(Url::parse(&server.uri()).unwrap())
+        .public_data_url(Url::parse(&server.uri()).unwrap())
+        .build()
+        .unwrap()
+}
+
+/// `body` as a `200` response.
+pub(crate) fn ok(body: Value) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(body)
+}
+
+/// Answers every `verb` request to `endpoint` with `body`.
+pub(crate) async fn mount(server: &MockServer, verb: &str, endpoint: &str, body: Value) {
+    Mock::given(method(verb))
+        .and(path(endpoint))
+        .respond_with(ok(body))
+        .mount(server)
+        .await;
+}
+
+/// `base` with the top level `fields` replaced or added.
+pub(crate) fn with(mut base: Value, fields: Value) -> Value {
+    if let (Some(base), Value::Object(fields)) = (base.as_object_mut(), fields) {
+        base.extend(fields);
+    }
+    base
+}
+
+/// `POST v1/order` accepting a `LIMIT` order for 0.1 at 9000.
+pub(crate) fn order_res(order_id: u64) -> Value {
+    serde_json::json!({
+        "success": true, "timestamp": "1601471652.928", "order_id": order_id,
+        "order_type": "LIMIT", "client_order_id": 0, "order_price": 9000,
+        "order_quantity": 0.1, "order_amount": null, "reduce_only": false,
+    })
+}
+
+/// `GET v1/order/{order_id}` of a `SPOT_BTC_USDT` `LIMIT` buy for 0.1 at 9000.
+pub(crate) fn order_detail(order_id: u64, status: &str, executed: f64) -> Value {
+    serde_json::json!({
+        "success": true, "created_time": "1577349119.33", "side": "BUY",
+        "status": status, "symbol": "SPOT_BTC_USDT", "client_order_id": 0,
+        "reduce_only": false, "order_id": order_id, "order_tag": "default",
+        "type": "LIMIT", "price": 9000, "quantity": 0.1, "amount": null, "visible": 0.1,
+        "executed": executed, "total_fee": 0, "fee_asset": "BTC",
+        "average_executed_price": null,
+    })
+}
+
+/// A row of `v1/orders`, the same order as [`order_detail`].
+pub(crate) fn order_row(order_id: u64, status: &str) -> Value {
+    serde_json::json!({
+        "side": "BUY", "status": status, "symbol": "SPOT_BTC_USDT",
+        "client_order_id": null, "reduce_only": false, "order_id": order_id,
+        "order_tag": "default", "type": "LIMIT", "price": 9000, "quantity": 0.1,
+        "amount": null, "visible": 0.1, "executed": 0, "total_fee": 0,
+        "fee_asset": null, "total_rebate": null, "rebate_asset": null,
+        "created_time": "1577349119.33", "updated_time": "1577349119.33",
+        "average_executed_price": null, "position_side": "BOTH", "realized_pnl": null,
+    })
+}
+
+/// One page of a paginated `v1` endpoint.
+pub(crate) fn page(
+    rows: Vec<Value>,
+    total: usize,
+    records_per_page: usize,
+    current_page: u32,
+) -> Value {
+    serde_json::json!({
+        "success": true,
+        "meta": {
+            "total": total, "records_per_page": records_per_page, "current_page": current_page,
+        },
+        "rows": rows,
+    })
+}
+
+/// `v1/public/info` listing `SPOT_BTC_USDT`.
+pub(crate) fn exchange_info() -> Value {
+    serde_json::json!({
+        "success": true,
+        "rows": [{
+            "symbol": "SPOT_BTC_USDT", "quote_min": 0, "quote_max": 100000,
+            "quote_tick": 0.01, "base_min": 0.0001, "base_max": 20, "base_tick": 0.0001,
+            "min_notional": 10, "price_range": 0.99,
+            "created_time": "1575441595.65", "updated_time": "1575441595.65",
+        }],
+    })
+}
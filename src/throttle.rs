@@ -0,0 +1,332 @@
+use crate::error::WooError;
+use crate::woo::Woo;
+use crate::woo_data_structs::{OrderId, SendOrderRes, WooOrder};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::time::{self, Instant};
+
+/// What a [`QuoteThrottle`] keeps one order resting for.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QuoteKey {
+    pub symbol: String,
+    pub side: String,
+}
+
+impl QuoteKey {
+    pub fn new(symbol: impl Into<String>, side: impl Into<String>) -> Self {
+        Self {
+            symbol: symbol.into(),
+            side: side.into(),
+        }
+    }
+
+    fn of(order: &WooOrder) -> Self {
+        Self::new(&order.symbol, order.side.to_ascii_uppercase())
+    }
+}
+
+/// What became of an order given to [`QuoteThrottle::submit`].
+#[derive(Debug)]
+pub enum QuoteOutcome {
+    /// A later order for the same key came in before this one was due, and
+    /// was sent instead.
+    Superseded,
+    /// Placed, or put in place of the order resting for the key.
+    Sent(SendOrderRes),
+    /// Sending failed. [`QuoteThrottle::resting`] tells whether the order
+    /// resting before is still there.
+    Failed(anyhow::Error),
+}
+
+#[derive(Debug, Default)]
+struct Slot {
+    /// The latest order submitted and not sent yet, with whoever waits for it.
+    desired: Option<(WooOrder, oneshot::Sender<QuoteOutcome>)>,
+    resting: Option<SendOrderRes>,
+    /// Whether a task is sending, or waiting to send, for this key.
+    driven: bool,
+    last_sent: Option<Instant>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    woo: Woo,
+    min_interval: Duration,
+    slots: Mutex<HashMap<QuoteKey, Slot>>,
+}
+
+/// Coalesces bursts of re-quotes: per symbol and side, only the latest order
+/// submitted is acted on, at most once every `min_interval`, and the ones it
+/// overtook report [`QuoteOutcome::Superseded`].
+///
+/// Acting on an order means placing it when nothing rests for its key, and
+/// otherwise [`Woo::cancel_and_replace`] of the resting order, which amends
+/// it in place when only price and size change. There is never more than one
+/// modification in flight per key. A resting order found filled or
+/// cancelled is replaced by placing the new order afresh.
+#[derive(Debug, Clone)]
+pub struct QuoteThrottle {
+    inner: Arc<Inner>,
+}
+
+impl QuoteThrottle {
+    pub fn new(woo: Woo, min_interval: Duration) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                woo,
+                min_interval,
+                slots: Mutex::default(),
+            }),
+        }
+    }
+
+    /// Makes `order` the desired state of its symbol and side, and resolves
+    /// once it was sent or superseded. Sending carries on if this future is
+    /// dropped.
+    pub async fn submit(&self, order: WooOrder) -> QuoteOutcome {
+        let key = QuoteKey::of(&order);
+        let (reply, outcome) = oneshot::channel();
+
+        let start = {
+            let mut slots = self.inner.slots.lock().unwrap();
+            let slot = slots.entry(key.clone()).or_default();
+            if let Some((_, superseded)) = slot.desired.replace((order, reply)) {
+                let _ = superseded.send(QuoteOutcome::Superseded);
+            }
+            !std::mem::replace(&mut slot.driven, true)
+        };
+        if start {
+            tokio::spawn(self.inner.clone().drive(key));
+        }
+
+        outcome
+            .await
+            .unwrap_or_else(|_| QuoteOutcome::Failed(anyhow::anyhow!("quote throttle stopped")))
+    }
+
+    /// The order last sent for `symbol` and `side`, as far as the throttle
+    /// knows: it doesn't watch for fills.
+    pub fn resting(&self, symbol: &str, side: &str) -> Option<SendOrderRes> {
+        let key = QuoteKey::new(symbol, side.to_ascii_uppercase());
+        self.inner
+            .slots
+            .lock()
+            .unwrap()
+            .get(&key)
+            .and_then(|slot| slot.resting.clone())
+    }
+}
+
+impl Inner {
+    /// Sends the desired order of `key`, one at a time and `min_interval`
+    /// apart, until none is left.
+    async fn drive(self: Arc<Self>, key: QuoteKey) {
+        loop {
+            let due = {
+                let mut slots = self.slots.lock().unwrap();
+                let slot = slots.get_mut(&key).expect("driven keys have a slot");
+                if slot.desired.is_none() {
+                    slot.driven = false;
+                    return;
+                }
+                slot.last_sent.map(|at| at + self.min_interval)
+            };
+            if let Some(due) = due {
+                time::sleep_until(due).await;
+            }
+
+            // whatever came in while sleeping replaced what was there
+            let (order, reply, resting) = {
+                let mut slots = self.slots.lock().unwrap();
+                let slot = slots.get_mut(&key).expect("driven keys have a slot");
+                let (order, reply) = slot.desired.take().expect("only the driver takes");
+                let resting = slot.resting.as_ref().map(|resting| resting.order_id);
+                (order, reply, resting)
+            };
+
+            let result = self.send(&key, resting, order).await;
+
+            let outcome = {
+                let mut slots = self.slots.lock().unwrap();
+                let slot = slots.get_mut(&key).expect("driven keys have a slot");
+                slot.last_sent = Some(Instant::now());
+                match result {
+                    Ok(sent) => {
+                        slot.resting = Some(sent.clone());
+                        QuoteOutcome::Sent(sent)
+                    }
+                    Err(err) => {
+                        if let Some(WooError::ReplacementFailed { .. }) = err.downcast_ref() {
+                            slot.resting = None;
+                        }
+                        QuoteOutcome::Failed(err)
+                    }
+                }
+            };
+            let _ = reply.send(outcome);
+        }
+    }
+
+    async fn send(
+        &self,
+        key: &QuoteKey,
+        resting: Option<OrderId>,
+        order: WooOrder,
+    ) -> anyhow::Result<SendOrderRes> {
+        let Some(order_id) = resting else {
+            return Ok(self.woo.create_order(order).await?);
+        };
+
+        match self.woo.cancel_and_replace(order_id, order.clone()).await {
+            // filled or cancelled since, there is nothing left to replace
+            Err(err) if matches!(err.downcast_ref(), Some(WooError::OrderTerminal { .. })) => {
+                if let Some(slot) = self.slots.lock().unwrap().get_mut(key) {
+                    slot.resting = None;
+                }
+
+                Ok(self.woo.create_order(order).await?)
+            }
+            res => res,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, ok, order_detail, order_res, with};
+    use crate::woo_data_structs::OrderSize;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+    use wiremock::matchers::{body_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn bid(price: Decimal) -> WooOrder {
+        WooOrder::builder("SPOT_BTC_USDT", "BUY", "LIMIT")
+            .price(price)
+            .size(OrderSize::Quantity(dec!(0.3)))
+            .build()
+    }
+
+    async fn mock_woo(server: &MockServer, status: &str) -> Woo {
+        let quantity = serde_json::json!({ "order_quantity": 0.3, "quantity": 0.3 });
+        Mock::given(method("POST"))
+            .and(path("/v1/order"))
+            .respond_with(ok(with(order_res(7), quantity.clone())))
+            .mount(server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/order/7"))
+            .respond_with(ok(with(order_detail(7, status, 0.0), quantity)))
+            .mount(server)
+            .await;
+
+        testing::mock_woo(server)
+    }
+
+    #[tokio::test]
+    async fn burst_of_requotes_sends_only_the_latest() {
+        let server = MockServer::start().await;
+        let woo = mock_woo(&server, "NEW").await;
+        Mock::given(method("PUT"))
+            .and(path("/v3/order/7"))
+            .and(body_json(serde_json::json!({ "price": "9300" })))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{"success":true,"data":{"status":"EDIT_SENT"},"timestamp":1578565539808}"#,
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let throttle = QuoteThrottle::new(woo, Duration::from_millis(50));
+
+        let placed = throttle.submit(bid(dec!(9000))).await;
+        assert!(matches!(placed, QuoteOutcome::Sent(_)), "{placed:?}");
+
+        let (first, second, last) = tokio::join!(
+            throttle.submit(bid(dec!(9100))),
+            throttle.submit(bid(dec!(9200))),
+            throttle.submit(bid(dec!(9300))),
+        );
+        assert!(matches!(first, QuoteOutcome::Superseded));
+        assert!(matches!(second, QuoteOutcome::Superseded));
+        match last {
+            QuoteOutcome::Sent(sent) => assert_eq!(sent.order_price, Some(9300.0)),
+            other => panic!("expected the last quote to be sent, got {other:?}"),
+        }
+
+        let resting = throttle.resting("SPOT_BTC_USDT", "BUY").unwrap();
+        assert_eq!(resting.order_id, OrderId(7));
+        assert_eq!(resting.order_price, Some(9300.0));
+        assert!(throttle.resting("SPOT_BTC_USDT", "SELL").is_none());
+    }
+
+    #[tokio::test]
+    async fn filled_quote_is_placed_afresh() {
+        let server = MockServer::start().await;
+        let woo = mock_woo(&server, "FILLED").await;
+        let throttle = QuoteThrottle::new(woo, Duration::ZERO);
+
+        throttle.submit(bid(dec!(9000))).await;
+        let requote = throttle.submit(bid(dec!(9100))).await;
+
+        assert!(matches!(requote, QuoteOutcome::Sent(_)), "{requote:?}");
+        let requests = server.received_requests().await.unwrap();
+        let posts = requests.iter().filter(|r| r.method.as_str() == "POST");
+        assert_eq!(posts.count(), 2);
+        assert!(requests.iter().all(|r| r.method.as_str() != "DELETE"));
+    }
+
+    #[tokio::test]
+    async fn filled_quote_is_forgotten_when_placing_afresh_fails() {
+        let server = MockServer::start().await;
+        let quantity = serde_json::json!({ "order_quantity": 0.3 });
+        Mock::given(method("POST"))
+            .and(path("/v1/order"))
+            .respond_with(ok(with(order_res(7), quantity)))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/order"))
+            .respond_with(ResponseTemplate::new(400).set_body_string(
+                r#"{"success":false,"code":-1012,"message":"Insufficient balance."}"#,
+            ))
+            .mount(&server)
+            .await;
+        let woo = mock_woo(&server, "FILLED").await;
+        let throttle = QuoteThrottle::new(woo, Duration::ZERO);
+
+        throttle.submit(bid(dec!(9000))).await;
+        assert!(throttle.resting("SPOT_BTC_USDT", "BUY").is_some());
+        let requote = throttle.submit(bid(dec!(9100))).await;
+
+        assert!(matches!(requote, QuoteOutcome::Failed(_)), "{requote:?}");
+        assert!(throttle.resting("SPOT_BTC_USDT", "BUY").is_none());
+    }
+
+    #[tokio::test]
+    async fn rejected_amendment_keeps_the_resting_quote() {
+        let server = MockServer::start().await;
+        let woo = mock_woo(&server, "NEW").await;
+        Mock::given(method("PUT"))
+            .and(path("/v3/order/7"))
+            .respond_with(ResponseTemplate::new(400).set_body_string(
+                r#"{"success":false,"code":-1103,"message":"The order does not meet the price filter requirement."}"#,
+            ))
+            .mount(&server)
+            .await;
+        let throttle = QuoteThrottle::new(woo, Duration::ZERO);
+
+        throttle.submit(bid(dec!(9000))).await;
+        let requote = throttle.submit(bid(dec!(9100))).await;
+
+        assert!(matches!(requote, QuoteOutcome::Failed(_)), "{requote:?}");
+        let requests = server.received_requests().await.unwrap();
+        let posts = requests.iter().filter(|r| r.method.as_str() == "POST");
+        assert_eq!(posts.count(), 1);
+        let resting = throttle.resting("SPOT_BTC_USDT", "BUY").unwrap();
+        assert_eq!(resting.order_id, OrderId(7));
+    }
+}
@@ -0,0 +1,197 @@
+use crate::error::{WaitError, WooApiError, WooError};
+use crate::woo::Woo;
+use crate::woo_data_structs::{CancelOrder, CancelOrderRes, OrderDetail, OrderId};
+use reqwest::Method;
+use rust_decimal::Decimal;
+use std::time::Duration;
+use tokio::time::{self, Instant};
+
+/// What to do with the unfilled remainder when the deadline passes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CancelOnTimeout {
+    Yes,
+    No,
+}
+
+impl Woo {
+    /// Polls `order_id` every `poll_interval` until it is filled, cancelled or
+    /// rejected, giving up once `deadline` has elapsed.
+    ///
+    /// `on_progress` is called whenever the executed quantity changes. On
+    /// timeout the remainder is cancelled if asked to, and the order is read
+    /// once more so [`WaitError::TimedOut`] carries its state after the cancel;
+    /// nothing is polled after that.
+    pub async fn wait_for_terminal_state(
+        &self,
+        order_id: OrderId,
+        poll_interval: Duration,
+        deadline: Duration,
+        cancel_on_timeout: CancelOnTimeout,
+        mut on_progress: Option<&mut (dyn FnMut(&OrderDetail) + Send)>,
+    ) -> Result<OrderDetail, WaitError> {
+        let deadline = Instant::now() + deadline;
+        let mut executed = Decimal::ZERO;
+
+        loop {
+            let order = self.order_detail(order_id).await?;
+
+            if order.executed != executed {
+                executed = order.executed;
+                if let Some(on_progress) = on_progress.as_mut() {
+                    on_progress(&order);
+                }
+            }
+
+            if order.status.is_terminal() {
+                return Ok(order);
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(self.time_out(order, cancel_on_timeout).await);
+            }
+
+            time::sleep(poll_interval.min(deadline - now)).await;
+        }
+    }
+
+    async fn time_out(&self, order: OrderDetail, cancel_on_timeout: CancelOnTimeout) -> WaitError {
+        if cancel_on_timeout == CancelOnTimeout::No {
+            return WaitError::TimedOut {
+                last: Box::new(order),
+                cancel_sent: false,
+            };
+        }
+
+        let cancel = CancelOrder {
+            order_id: order.order_id,
+            symbol: order.symbol.clone(),
+        };
+
+        // the order may have filled or been cancelled since the last poll, in
+        // which case WOO no longer knows it as cancellable
+        let cancel_sent = match self
+            .signed_request::<_, CancelOrderRes>(Method::DELETE, "v1/order", &cancel)
+            .await
+        {
+            Ok(_) => true,
+            Err(WooError::Api(err)) if err.code == WooApiError::RESOURCE_NOT_FOUND => false,
+            Err(err) => return err.into(),
+        };
+
+        match self.order_detail(order.order_id).await {
+            Ok(last) => WaitError::TimedOut {
+                last: Box::new(last),
+                cancel_sent,
+            },
+            Err(err) => err.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{mock_woo, ok, order_detail, with};
+    use crate::woo_data_structs::OrderStatus;
+    use rust_decimal_macros::dec;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn order(status: &str, executed: f64) -> ResponseTemplate {
+        let detail = order_detail(7, status, executed);
+        ok(with(
+            detail,
+            serde_json::json!({ "quantity": 0.3, "visible": 0.3 }),
+        ))
+    }
+
+    #[tokio::test]
+    async fn waits_through_partial_fills() {
+        let server = MockServer::start().await;
+
+        for (status, executed) in [
+            ("NEW", 0.0),
+            ("PARTIAL_FILLED", 0.1),
+            ("PARTIAL_FILLED", 0.2),
+        ] {
+            Mock::given(method("GET"))
+                .and(path("/v1/order/7"))
+                .respond_with(order(status, executed))
+                .up_to_n_times(1)
+                .mount(&server)
+                .await;
+        }
+        Mock::given(method("GET"))
+            .and(path("/v1/order/7"))
+            .respond_with(order("FILLED", 0.3))
+            .mount(&server)
+            .await;
+
+        let woo = mock_woo(&server);
+        let mut progress = Vec::new();
+        let mut on_progress = |order: &OrderDetail| progress.push(order.executed);
+
+        let filled = woo
+            .wait_for_terminal_state(
+                OrderId(7),
+                Duration::from_millis(5),
+                Duration::from_secs(5),
+                CancelOnTimeout::Yes,
+                Some(&mut on_progress),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(filled.status, OrderStatus::Filled);
+        assert_eq!(progress, [dec!(0.1), dec!(0.2), dec!(0.3)]);
+    }
+
+    #[tokio::test]
+    async fn cancels_remainder_on_timeout() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("DELETE"))
+            .and(path("/v1/order"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(r#"{"success":true,"status":"CANCEL_SENT"}"#),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/order/7"))
+            .respond_with(order("PARTIAL_FILLED", 0.1))
+            .mount(&server)
+            .await;
+
+        let woo = mock_woo(&server);
+        let err = woo
+            .wait_for_terminal_state(
+                OrderId(7),
+                Duration::from_millis(5),
+                Duration::from_millis(30),
+                CancelOnTimeout::Yes,
+                None,
+            )
+            .await
+            .unwrap_err();
+
+        match err {
+            WaitError::TimedOut { last, cancel_sent } => {
+                assert!(cancel_sent);
+                assert_eq!(last.status, OrderStatus::PartialFilled);
+            }
+            other => panic!("expected a timeout, got {other:?}"),
+        }
+
+        // the only request after the cancel is the final read
+        let requests = server.received_requests().await.unwrap();
+        let cancel_at = requests
+            .iter()
+            .position(|request| request.method.as_str() == "DELETE")
+            .unwrap();
+        assert_eq!(requests.len() - cancel_at, 2);
+    }
+}
@@ -0,0 +1,424 @@
+use crate::error::WooError;
+use crate::serde_helpers::{decimal_string, deserialize_flexible_bool};
+use crate::woo::Woo;
+use futures::future::BoxFuture;
+use reqwest::Method;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+/// Short, fees change without notice.
+pub(crate) const DEFAULT_TOKEN_NETWORK_TTL: Duration = Duration::from_secs(30);
+
+// https://docs.woo.org/#token-withdraw
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct WithdrawRequest {
+    pub token: String,
+    pub network: String,
+    pub address: String,
+    /// Memo or tag, for the networks that need one.
+    pub extra: Option<String>,
+    #[serde(serialize_with = "decimal_string::serialize")]
+    pub amount: Decimal,
+    /// Highest network fee the caller accepts, checked against the current
+    /// fee before sending. Not sent to WOO.
+    #[serde(skip)]
+    pub max_fee: Option<Decimal>,
+}
+
+// https://docs.woo.org/#token-network
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TokenNetwork {
+    pub protocol: String,
+    pub network: String,
+    pub token: String,
+    pub name: String,
+    pub minimum_withdrawal: Decimal,
+    pub withdrawal_fee: Decimal,
+    #[serde(deserialize_with = "deserialize_flexible_bool")]
+    pub allow_deposit: bool,
+    #[serde(deserialize_with = "deserialize_flexible_bool")]
+    pub allow_withdraw: bool,
+}
+
+#[derive(Deserialize, Debug)]
+struct TokenNetworkRes {
+    rows: Vec<TokenNetwork>,
+}
+
+type TokenNetworks = Arc<Vec<TokenNetwork>>;
+
+/// Networks, minimums and fees of every token, refetched once older than
+/// `ttl`.
+#[derive(Debug)]
+pub(crate) struct TokenNetworkCache {
+    ttl: Duration,
+    // held across the refetch, like the symbol cache
+    networks: Mutex<Option<(Instant, TokenNetworks)>>,
+}
+
+impl TokenNetworkCache {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            networks: Mutex::new(None),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct WithdrawRes {
+    pub success: bool,
+    pub withdraw_id: String,
+}
+
+type Approver = dyn Fn(WithdrawRequest) -> BoxFuture<'static, bool> + Send + Sync;
+
+/// Checks every withdrawal before it is signed, see
+/// [`Woo::with_withdraw_policy`].
+#[derive(Clone, Default)]
+pub struct WithdrawPolicy {
+    /// `(token, network, address)` destinations withdrawals may go to,
+    /// compared exactly. Anything else is refused.
+    pub allowed: Vec<(String, String, String)>,
+    approver: Option<Arc<Approver>>,
+}
+
+impl fmt::Debug for WithdrawPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WithdrawPolicy")
+            .field("allowed", &self.allowed)
+            .field("approver", &self.approver.is_some())
+            .finish()
+    }
+}
+
+impl WithdrawPolicy {
+    pub fn new(allowed: Vec<(String, String, String)>) -> Self {
+        Self {
+            allowed,
+            approver: None,
+        }
+    }
+
+    /// Two-man rule: whitelisted withdrawals are only sent once `approve`
+    /// resolves to `true`, e.g. after a second person confirmed them on
+    /// another channel.
+    pub fn require_approval<F, Fut>(mut self, approve: F) -> Self
+    where
+        F: Fn(WithdrawRequest) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = bool> + Send + 'static,
+    {
+        self.approver = Some(Arc::new(move |request| Box::pin(approve(request))));
+        self
+    }
+
+    fn allows(&self, request: &WithdrawRequest) -> bool {
+        self.allowed.iter().any(|(token, network, address)| {
+            *token == request.token && *network == request.network && *address == request.address
+        })
+    }
+
+    fn check_whitelist(&self, request: &WithdrawRequest) -> Result<(), WooError> {
+        if !self.allows(request) {
+            return Err(WooError::WithdrawBlocked(format!(
+                "{} on {} to {} is not whitelisted",
+                request.token, request.network, request.address
+            )));
+        }
+
+        Ok(())
+    }
+
+    async fn check_approval(&self, request: &WithdrawRequest) -> Result<(), WooError> {
+        if let Some(approver) = &self.approver {
+            if !approver(request.clone()).await {
+                return Err(WooError::WithdrawBlocked(format!(
+                    "withdrawal of {} {} to {} was not approved",
+                    request.amount, request.token, request.address
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Woo {
+    /// Checks every [`Woo::withdraw`] against `policy` before it is signed.
+    /// Applies to clones made after this call.
+    pub fn with_withdraw_policy(mut self, policy: WithdrawPolicy) -> Self {
+        self.withdraw_policy = Some(Arc::new(policy));
+        self
+    }
+
+    /// Keeps token networks for `ttl` instead of the default 30 seconds.
+    /// The cache is shared with clones made after this call.
+    pub fn with_token_network_cache(mut self, ttl: Duration) -> Self {
+        self.token_networks = Arc::new(TokenNetworkCache::new(ttl));
+        self
+    }
+
+    /// Every network of every token, served from the cache.
+    pub async fn get_token_networks(&self) -> anyhow::Result<Vec<TokenNetwork>> {
+        Ok(self.token_networks().await?.to_vec())
+    }
+
+    /// Checks `request` against the current config of its token: the network
+    /// must exist and take withdrawals, the amount must reach the network's
+    /// minimum and the fee must not exceed `request.max_fee`.
+    pub async fn validate_withdraw(&self, request: &WithdrawRequest) -> Result<(), WooError> {
+        let networks = self.token_networks().await?;
+        let blocked = |detail: String| Err(WooError::WithdrawBlocked(detail));
+
+        let Some(network) = networks
+            .iter()
+            .find(|network| network.token == request.token && network.network == request.network)
+        else {
+            return blocked(format!(
+                "{} has no {} network",
+                request.token, request.network
+            ));
+        };
+        if !network.allow_withdraw {
+            return blocked(format!(
+                "{} withdrawals on {} are suspended",
+                request.token, request.network
+            ));
+        }
+        if request.amount < network.minimum_withdrawal {
+            return blocked(format!(
+                "{} is below the {} minimum withdrawal of {}",
+                request.amount, request.network, network.minimum_withdrawal
+            ));
+        }
+        if let Some(max_fee) = request.max_fee {
+            if network.withdrawal_fee > max_fee {
+                return blocked(format!(
+                    "the {} fee is {}, more than the {max_fee} accepted",
+                    request.network, network.withdrawal_fee
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Withdraws `request.amount` of `request.token` to an external address,
+    /// once it is whitelisted by the withdraw policy, if any, passed
+    /// [`Woo::validate_withdraw`] and, last, was approved. The approver is
+    /// only asked about withdrawals that will actually be sent.
+    pub async fn withdraw(&self, request: WithdrawRequest) -> Result<WithdrawRes, WooError> {
+        let policy = self.withdraw_policy.as_deref();
+        let checked = async {
+            if let Some(policy) = policy {
+                policy.check_whitelist(&request)?;
+            }
+            self.validate_withdraw(&request).await?;
+            if let Some(policy) = policy {
+                policy.check_approval(&request).await?;
+            }
+            Ok::<_, WooError>(())
+        };
+        if let Err(err) = checked.await {
+            tracing::warn!(%err, "withdrawal refused");
+            return Err(err);
+        }
+
+        self.signed_request(Method::POST, "v1/asset/withdraw", &request)
+            .await
+    }
+
+    async fn token_networks(&self) -> Result<TokenNetworks, WooError> {
+        let mut cached = self.token_networks.networks.lock().await;
+
+        match &*cached {
+            Some((fetched_at, networks)) if fetched_at.elapsed() < self.token_networks.ttl => {
+                Ok(networks.clone())
+            }
+            _ => {
+                let res: TokenNetworkRes = self
+                    .public_request(Method::GET, "v1/public/token_network", ())
+                    .await?;
+                let networks = Arc::new(res.rows);
+                *cached = Some((Instant::now(), networks.clone()));
+                Ok(networks)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use rust_decimal_macros::dec;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const ADDRESS: &str = "0x1234567890abcdef1234567890abcdef12345678";
+
+    fn request(address: &str) -> WithdrawRequest {
+        WithdrawRequest {
+            token: "USDT".to_string(),
+            network: "ETH".to_string(),
+            address: address.to_string(),
+            extra: None,
+            amount: dec!(100),
+            max_fee: None,
+        }
+    }
+
+    fn policy() -> WithdrawPolicy {
+        WithdrawPolicy::new(vec![(
+            "USDT".to_string(),
+            "ETH".to_string(),
+            ADDRESS.to_string(),
+        )])
+    }
+
+    async fn mock_woo(server: &MockServer, policy: WithdrawPolicy) -> Woo {
+        Mock::given(method("GET"))
+            .and(path("/v1/public/token_network"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "rows": [
+                    {
+                        "protocol": "ERC20", "network": "ETH", "token": "USDT",
+                        "name": "Tether USD", "minimum_withdrawal": 10,
+                        "withdrawal_fee": 2, "allow_deposit": 1, "allow_withdraw": 1,
+                    },
+                    {
+                        "protocol": "TRC20", "network": "TRON", "token": "USDT",
+                        "name": "Tether USD", "minimum_withdrawal": 1,
+                        "withdrawal_fee": 1, "allow_deposit": 1, "allow_withdraw": 0,
+                    },
+                ],
+            })))
+            .mount(server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/asset/withdraw"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(
+                serde_json::json!({ "success": true, "withdraw_id": "20200119145703654" }),
+            ))
+            .mount(server)
+            .await;
+
+        testing::mock_woo(server).with_withdraw_policy(policy)
+    }
+
+    #[tokio::test]
+    async fn refuses_destinations_off_the_whitelist() {
+        let server = MockServer::start().await;
+        let woo = mock_woo(&server, policy()).await;
+
+        let err = woo.withdraw(request("0xdeadbeef")).await.unwrap_err();
+
+        assert!(matches!(err, WooError::WithdrawBlocked(_)));
+        assert!(server.received_requests().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn sends_only_approved_withdrawals() {
+        let server = MockServer::start().await;
+        let approve = Arc::new(AtomicBool::new(false));
+        let policy = policy().require_approval({
+            let approve = approve.clone();
+            move |request| {
+                let approve = approve.load(Ordering::SeqCst);
+                async move { approve && request.amount <= dec!(1000) }
+            }
+        });
+        let woo = mock_woo(&server, policy).await;
+
+        let err = woo.withdraw(request(ADDRESS)).await.unwrap_err();
+        assert!(err.to_string().contains("was not approved"));
+        assert!(server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .all(|request| request.method != wiremock::http::Method::POST));
+
+        approve.store(true, Ordering::SeqCst);
+        let res = woo.withdraw(request(ADDRESS)).await.unwrap();
+
+        assert_eq!(res.withdraw_id, "20200119145703654");
+        let requests = server.received_requests().await.unwrap();
+        let sent = requests
+            .iter()
+            .find(|request| request.method == wiremock::http::Method::POST)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(sent.body.clone()).unwrap(),
+            format!("address={ADDRESS}&amount=100&network=ETH&token=USDT")
+        );
+    }
+
+    #[tokio::test]
+    async fn does_not_ask_approval_for_invalid_withdrawals() {
+        let server = MockServer::start().await;
+        let asked = Arc::new(AtomicBool::new(false));
+        let policy = policy().require_approval({
+            let asked = asked.clone();
+            move |_| {
+                asked.store(true, Ordering::SeqCst);
+                async { true }
+            }
+        });
+        let woo = mock_woo(&server, policy).await;
+        let mut dust = request(ADDRESS);
+        dust.amount = dec!(5);
+
+        let err = woo.withdraw(dust).await.unwrap_err();
+
+        assert!(err.to_string().contains("below the ETH minimum withdrawal"));
+        assert!(!asked.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn checks_network_minimum_and_fee_against_fresh_config() {
+        let server = MockServer::start().await;
+        let woo = mock_woo(&server, WithdrawPolicy::default()).await;
+        let blocked = |request: WithdrawRequest| {
+            let woo = woo.clone();
+            async move {
+                woo.validate_withdraw(&request)
+                    .await
+                    .unwrap_err()
+                    .to_string()
+            }
+        };
+
+        let mut unknown = request(ADDRESS);
+        unknown.network = "SOL".to_string();
+        assert!(blocked(unknown).await.contains("USDT has no SOL network"));
+        let mut suspended = request(ADDRESS);
+        suspended.network = "TRON".to_string();
+        assert!(blocked(suspended).await.contains("suspended"));
+        let mut dust = request(ADDRESS);
+        dust.amount = dec!(5);
+        assert!(blocked(dust)
+            .await
+            .contains("below the ETH minimum withdrawal of 10"));
+        let mut cheap = request(ADDRESS);
+        cheap.max_fee = Some(dec!(1.5));
+        assert!(blocked(cheap).await.contains("the ETH fee is 2"));
+
+        let mut accepted = request(ADDRESS);
+        accepted.max_fee = Some(dec!(2));
+        woo.validate_withdraw(&accepted).await.unwrap();
+
+        // one fetch, served from the cache since
+        let fetches = server.received_requests().await.unwrap().len();
+        assert_eq!(fetches, 1);
+    }
+}
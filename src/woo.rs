@@ -1,194 +1,1278 @@
-use crate::constants::{PROXY_IP, PROXY_URL, WOO_API_BASE_URL, WOO_API_BASE_URL_STAGING};
+use crate::account_mode::AccountModeCache;
+use crate::audit::{AuditLog, AuditOutcome, PendingAudit};
+use crate::builder::{Credentials, WooBuilder};
+use crate::client_order_id::ClientOrderIdGenerator;
+use crate::constants::MAX_PAGE_SIZE;
+use crate::env::{self, CredentialSource};
+use crate::error::{WooApiError, WooError, WooErrorCode};
+use crate::latency::{ClockTelemetry, LatencyTracker};
+use crate::proxy::{ProxyPool, ProxyStatus};
+use crate::public_cache::PublicCache;
+use crate::recording::RequestRecorder;
+use crate::registry::OrderRegistry;
+use crate::response_log::{self, CapturedResponse, ResponseLog};
+use crate::risk::RiskGuard;
+use crate::shutdown::{InFlight, InFlightGuard};
+use crate::signer::{ActiveKey, SigningKey};
+use crate::stats::RequestStats;
+use crate::symbol_cache::{SymbolInfoCache, DEFAULT_SYMBOL_CACHE_TTL};
+use crate::withdraw::{TokenNetworkCache, WithdrawPolicy, DEFAULT_TOKEN_NETWORK_TTL};
 use crate::woo_data_structs::{
-    CancelOrder, CancelOrderRes, GetOrder, GetOrderRes, SendOrderRes, WooOrder,
+    server_timestamp, AccountInfo, CancelAllAfter, CancelAllAfterRes, CancelAllOrders,
+    CancelAllPendingRes, CancelOrder, CancelOrderByClientId, CancelOrderRes, ClientOrderId,
+    FundingCountdown, FundingRate, GetOrder, GetOrderRes, GetTrades, HoldingRes, MarketImpact,
+    Meta, OrderDetail, OrderId, OrderSize, OrderType, Orderbook, PositionsRes, Row, SendOrderRes,
+    Side, Token, TokensRes, Trade, WooEnvelope, WooOrder,
 };
-use anyhow::Ok;
-use base64::prelude::BASE64_STANDARD;
-use base64::Engine;
-use dotenv::dotenv;
+use futures::stream::{self, Stream, TryStreamExt};
 use hmac::{Hmac, Mac};
-use reqwest::header::{self, HeaderMap, HeaderValue, AUTHORIZATION};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::Method;
+use rust_decimal::Decimal;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use sha2::Sha256;
-use std::borrow::Borrow;
 use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::Instrument;
 use url::Url;
+use uuid::Uuid;
 
-enum Environment {
+const OPEN_ORDERS_PAGE_SIZE: u32 = MAX_PAGE_SIZE;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Environment {
     Production,
     Staging,
 }
 
-struct Woo {
-    http_client: reqwest::Client,
-    base_url: Url,
-    api_secret: String,
+#[derive(Clone)]
+pub struct Woo {
+    pub(crate) http_client: reqwest::Client,
+    pub(crate) proxy_pool: Option<Arc<ProxyPool>>,
+    pub(crate) base_url: Url,
+    /// Host of the historical market data endpoints.
+    pub(crate) public_data_url: Url,
+    pub(crate) key: Arc<ActiveKey>,
+    pub(crate) symbol_cache: Arc<SymbolInfoCache>,
+    pub(crate) client_order_ids: Arc<ClientOrderIdGenerator>,
+    pub(crate) latency: Arc<LatencyTracker>,
+    pub(crate) risk: Option<Arc<RiskGuard>>,
+    pub(crate) responses: Option<Arc<ResponseLog>>,
+    /// Milliseconds a signed request stays valid after its timestamp.
+    pub(crate) recv_window: Option<u64>,
+    pub(crate) hedge_mode: bool,
+    pub(crate) account_mode: Arc<AccountModeCache>,
+    pub(crate) audit: Option<Arc<AuditLog>>,
+    pub(crate) in_flight: Arc<InFlight>,
+    pub(crate) telemetry: Option<Arc<ClockTelemetry>>,
+    pub(crate) registry: Option<Arc<OrderRegistry>>,
+    pub(crate) withdraw_policy: Option<Arc<WithdrawPolicy>>,
+    pub(crate) token_networks: Arc<TokenNetworkCache>,
+    pub(crate) stats: Arc<RequestStats>,
+    pub(crate) recorder: Option<Arc<RequestRecorder>>,
+    pub(crate) public_cache: Option<Arc<PublicCache>>,
+}
+
+impl fmt::Debug for Woo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Woo")
+            .field("base_url", &self.base_url.as_str())
+            .field("proxies", &self.proxy_status())
+            .field("api_secret", &"[REDACTED]")
+            .finish()
+    }
+}
+
+/// How the params of a signed request are encoded, which decides both what
+/// gets signed and how they are sent. Encoded once, so the bytes signed are
+/// the bytes sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum RequestBody {
+    /// `v1`: a sorted query string, signed as `{params}|{timestamp}` and sent
+    /// in the query of `GET` and `DELETE`, as a form body otherwise.
+    Form(String),
+    /// `v3`: a JSON body, signed as `{timestamp}{METHOD}{path}{body}`.
+    Json(String),
+}
+
+impl RequestBody {
+    pub(crate) fn json<B: Serialize>(body: &B) -> Result<Self, WooError> {
+        let body = serde_json::to_string(body)
+            .map_err(|err| WooError::Config(format!("failed to encode request body: {err}")))?;
+        Ok(RequestBody::Json(body))
+    }
+
+    pub(crate) fn encoded(&self) -> &str {
+        match self {
+            RequestBody::Form(payload) | RequestBody::Json(payload) => payload,
+        }
+    }
+
+    fn signing_payload(&self, timestamp: u64, method: &Method, path: &str) -> String {
+        match self {
+            RequestBody::Form(params) => format!("{params}|{timestamp}"),
+            RequestBody::Json(body) => format!("{timestamp}{method}{path}{body}"),
+        }
+    }
+
+    /// WOO reads `GET` and `DELETE` parameters from the query string, even
+    /// when the request could carry a body.
+    fn in_query(&self, method: &Method) -> bool {
+        matches!(self, RequestBody::Form(_))
+            && (*method == Method::GET || *method == Method::DELETE)
+    }
 }
 
 impl Woo {
-    fn new(environment: Environment) -> Self {
-        dotenv().ok();
-
-        let (base_url, api_key, api_secret) = match environment {
-            Environment::Production => (
-                Url::parse(WOO_API_BASE_URL).unwrap(),
-                dotenv::var("WOO_API_KEY").expect("woo api key missing in .env"),
-                dotenv::var("WOO_API_SECRET").expect("woo api secret missing in .env"),
+    /// Reads the credentials and, if `PROXY_URL` is set, the proxy from the
+    /// environment, see [`env::proxy_from_env`].
+    pub fn new(environment: Environment) -> Self {
+        Woo::from_credential_source(environment, CredentialSource::for_environment(environment))
+    }
+
+    /// [`Woo::new`] reading the api key and secret from custom variable names.
+    pub fn from_credential_source(environment: Environment, source: CredentialSource) -> Self {
+        let credentials = source.load().expect("woo credentials missing in .env");
+        let proxy = env::proxy_from_env().expect("invalid proxy settings in .env");
+
+        let mut builder = Woo::builder(environment, credentials);
+        if let Some(proxy) = proxy {
+            builder = builder.proxy(proxy);
+        }
+
+        builder.build().expect("failed to build woo client")
+    }
+
+    pub(crate) fn from_parts(
+        http_client: reqwest::Client,
+        proxy_pool: Option<Arc<ProxyPool>>,
+        base_url: Url,
+        public_data_url: Url,
+        key: SigningKey,
+    ) -> Self {
+        Woo {
+            http_client,
+            proxy_pool,
+            base_url,
+            public_data_url,
+            key: Arc::new(ActiveKey::new(key)),
+            symbol_cache: Arc::new(SymbolInfoCache::new(DEFAULT_SYMBOL_CACHE_TTL)),
+            client_order_ids: Arc::new(ClientOrderIdGenerator::new()),
+            latency: Arc::default(),
+            risk: None,
+            responses: None,
+            recv_window: None,
+            hedge_mode: false,
+            account_mode: Arc::default(),
+            audit: None,
+            in_flight: Arc::default(),
+            telemetry: None,
+            registry: None,
+            withdraw_policy: None,
+            token_networks: Arc::new(TokenNetworkCache::new(DEFAULT_TOKEN_NETWORK_TTL)),
+            stats: Arc::default(),
+            recorder: None,
+            public_cache: None,
+        }
+    }
+
+    /// Builds a client from explicit credentials, without reading `.env` or
+    /// any other process-global state, so several clients can coexist.
+    pub fn with_credentials(
+        environment: Environment,
+        credentials: Credentials,
+    ) -> Result<Self, WooError> {
+        Woo::builder(environment, credentials).build()
+    }
+
+    pub fn builder(environment: Environment, credentials: Credentials) -> WooBuilder {
+        WooBuilder::new(environment, credentials)
+    }
+
+    /// Builds a client on top of a caller-owned `reqwest::Client`, see
+    /// [`WooBuilder::http_client`] for how that interacts with proxies.
+    pub fn with_http_client(
+        environment: Environment,
+        credentials: Credentials,
+        http_client: reqwest::Client,
+    ) -> Result<Self, WooError> {
+        Woo::builder(environment, credentials)
+            .http_client(http_client)
+            .build()
+    }
+
+    /// Health of every configured proxy, in configuration order. Empty when
+    /// the client goes out directly.
+    pub fn proxy_status(&self) -> Vec<ProxyStatus> {
+        self.proxy_pool
+            .as_ref()
+            .map(|pool| pool.status())
+            .unwrap_or_default()
+    }
+
+    pub async fn create_order(&self, order: WooOrder) -> Result<SendOrderRes, WooError> {
+        self.place_order(&order).await
+    }
+
+    /// Buys `symbol` at market, for a quantity of base currency or, with
+    /// [`OrderSize::Amount`] on spot, for an amount of quote currency.
+    pub async fn market_buy(
+        &self,
+        symbol: impl Into<String>,
+        size: OrderSize,
+    ) -> Result<SendOrderRes, WooError> {
+        self.place_order(&WooOrder::market(symbol, Side::Buy.as_str(), size))
+            .await
+    }
+
+    /// Sells `quantity` of `symbol` at market. Sells can't be sized in
+    /// quote currency.
+    pub async fn market_sell(
+        &self,
+        symbol: impl Into<String>,
+        quantity: Decimal,
+    ) -> Result<SendOrderRes, WooError> {
+        let order = WooOrder::market(symbol, Side::Sell.as_str(), OrderSize::Quantity(quantity));
+        self.place_order(&order).await
+    }
+
+    pub async fn limit_buy(
+        &self,
+        symbol: impl Into<String>,
+        price: Decimal,
+        quantity: Decimal,
+    ) -> Result<SendOrderRes, WooError> {
+        self.place_order(&WooOrder::limit(
+            symbol,
+            Side::Buy.as_str(),
+            price,
+            quantity,
+        ))
+        .await
+    }
+
+    pub async fn limit_sell(
+        &self,
+        symbol: impl Into<String>,
+        price: Decimal,
+        quantity: Decimal,
+    ) -> Result<SendOrderRes, WooError> {
+        self.place_order(&WooOrder::limit(
+            symbol,
+            Side::Sell.as_str(),
+            price,
+            quantity,
+        ))
+        .await
+    }
+
+    /// Submits `order` once its fields fit its type and it passed
+    /// [`Woo::guard_order`].
+    pub(crate) async fn place_order(&self, order: &WooOrder) -> Result<SendOrderRes, WooError> {
+        let order_type = order.validate_type()?;
+        if let Some(client_order_id) = &order.client_order_id {
+            client_order_id.validate_v1()?;
+        }
+        order.validate_reduce_only(self.hedge_mode)?;
+        let _in_flight = self.guard_order(order).await?;
+
+        let res = match self
+            .signed_request::<_, SendOrderRes>(Method::POST, "v1/order", order)
+            .await
+        {
+            Err(WooError::Api(err))
+                if order_type == OrderType::PostOnly && err.is_post_only_rejection() =>
+            {
+                Err(WooError::PostOnlyRejected(err))
+            }
+            res => res.map_err(order_rejection),
+        };
+        if let (Some(registry), Ok(res)) = (&self.registry, &res) {
+            registry.submitted(res.order_id);
+        }
+
+        res
+    }
+
+    /// Refuses `order` while shutting down, or if it doesn't fit the account
+    /// mode or the configured risk limits. Every order the crate sends goes
+    /// through here, plain, algo or amended, and is counted in flight from
+    /// here until the guard returned is dropped.
+    pub(crate) async fn guard_order(
+        &self,
+        order: &WooOrder,
+    ) -> Result<InFlightGuard<'_>, WooError> {
+        let in_flight = self.in_flight.start_order();
+        if self.in_flight.is_closing() {
+            return Err(WooError::ShuttingDown);
+        }
+        self.check_account_mode(order).await?;
+        self.check_risk(order).await?;
+
+        Ok(in_flight)
+    }
+
+    pub async fn cancel_order(
+        &self,
+        cancel_order: CancelOrder,
+    ) -> Result<CancelOrderRes, WooError> {
+        self.signed_request(Method::DELETE, "v1/order", &cancel_order)
+            .await
+            .map_err(order_rejection)
+    }
+
+    pub async fn cancel_order_by_client_id(
+        &self,
+        cancel_order: CancelOrderByClientId,
+    ) -> Result<CancelOrderRes, WooError> {
+        cancel_order.client_order_id.validate_v1()?;
+        self.signed_request(Method::DELETE, "v1/client/order", &cancel_order)
+            .await
+            .map_err(order_rejection)
+    }
+
+    /// Looks an order up by the exchange-assigned `order_id`.
+    pub async fn get_order(&self, order_id: OrderId) -> anyhow::Result<OrderDetail> {
+        Ok(self.order_detail(order_id).await?)
+    }
+
+    pub(crate) async fn order_detail(&self, order_id: OrderId) -> Result<OrderDetail, WooError> {
+        let path = format!("v1/order/{order_id}");
+
+        let res = match self
+            .signed_request::<_, OrderDetail>(Method::GET, &path, BTreeMap::<String, String>::new())
+            .await
+        {
+            Err(WooError::Api(err)) if err.code == WooApiError::RESOURCE_NOT_FOUND => Err(
+                WooError::NotFound(format!("no order with order_id {order_id}")),
             ),
-            Environment::Staging => (
-                Url::parse(WOO_API_BASE_URL_STAGING).unwrap(),
-                dotenv::var("WOO_API_KEY_STAGING").expect("woo api staging key missing in .env"),
-                dotenv::var("WOO_API_SECRET_STAGING")
-                    .expect("woo api staging secret missing in .env"),
+            res => res,
+        };
+        if let (Some(registry), Ok(order)) = (&self.registry, &res) {
+            registry.observed(order.order_id, order.status);
+        }
+
+        res
+    }
+
+    /// Looks an order up by the `client_order_id` it was submitted with.
+    pub async fn get_order_by_client_id(
+        &self,
+        client_order_id: ClientOrderId,
+    ) -> anyhow::Result<Row> {
+        client_order_id.validate_v1()?;
+        let path = format!("v1/client/order/{client_order_id}");
+
+        match self
+            .signed_request(Method::GET, &path, BTreeMap::<String, String>::new())
+            .await
+        {
+            Err(WooError::Api(err)) if err.code == WooApiError::RESOURCE_NOT_FOUND => Err(
+                WooError::NotFound(format!("no order with client_order_id {client_order_id}"))
+                    .into(),
             ),
+            res => Ok(res?),
+        }
+    }
+
+    /// Cancels every pending order on `symbol`.
+    pub async fn cancel_all_orders(&self, symbol: &str) -> anyhow::Result<CancelOrderRes> {
+        let cancel_all = CancelAllOrders {
+            symbol: symbol.to_string(),
         };
 
-        let proxy_url: Url = Url::parse(PROXY_URL).unwrap();
+        Ok(self
+            .signed_request(Method::DELETE, "v1/orders", &cancel_all)
+            .await?)
+    }
 
-        let proxy_username = dotenv::var("PROXY_USERNAME").expect("proxy username missing in .env");
-        let proxy_password = dotenv::var("PROXY_PASSWORD").expect("proxy password missing in .env");
+    /// Cancels every pending order on every symbol, the "flatten everything"
+    /// button. `v1/orders` insists on a symbol, so this goes to the `v3`
+    /// cancel all pending endpoint, signed over an empty body.
+    pub async fn cancel_all_orders_global(&self) -> anyhow::Result<CancelOrderRes> {
+        Ok(self.cancel_all_pending().await?)
+    }
 
-        let proxy = reqwest::Proxy::all(proxy_url)
-            .expect("failed to create proxy")
-            .basic_auth(&proxy_username, &proxy_password);
+    pub(crate) async fn cancel_all_pending(&self) -> Result<CancelOrderRes, WooError> {
+        let res: WooEnvelope<CancelAllPendingRes> = self
+            .signed_body_request(
+                Method::DELETE,
+                "v3/orders/pending",
+                RequestBody::Json(String::new()),
+            )
+            .await?;
 
-        let mut default_headers = header::HeaderMap::new();
-        default_headers.insert("x-api-key", api_key.parse().unwrap());
+        Ok(CancelOrderRes {
+            success: res.success,
+            status: res.into_data()?.status,
+        })
+    }
 
-        let http_client = reqwest::Client::builder()
-            .proxy(proxy)
-            .default_headers(default_headers)
-            .build()
-            .unwrap();
+    /// Arms WOO's dead-man's switch: every open order is cancelled unless this
+    /// is called again within `trigger_after` (5s to 15min). A zero duration
+    /// disarms it.
+    pub async fn cancel_all_after(
+        &self,
+        trigger_after: Duration,
+    ) -> anyhow::Result<CancelAllAfterRes> {
+        Ok(self.set_cancel_all_after(trigger_after).await?)
+    }
 
-        Self {
-            http_client,
-            base_url,
-            api_secret,
+    pub(crate) async fn set_cancel_all_after(
+        &self,
+        trigger_after: Duration,
+    ) -> Result<CancelAllAfterRes, WooError> {
+        let cancel_all_after = CancelAllAfter {
+            trigger_after: trigger_after.as_millis() as u64,
+        };
+
+        self.signed_request::<_, WooEnvelope<_>>(
+            Method::POST,
+            "v1/order/cancel_all_after",
+            &cancel_all_after,
+        )
+        .await?
+        .into_data()
+    }
+
+    pub async fn get_orders(&self, get_order: GetOrder) -> anyhow::Result<GetOrderRes> {
+        Ok(self
+            .signed_request(Method::GET, "v1/orders", &get_order)
+            .await?)
+    }
+
+    /// Every open (`INCOMPLETE`) order, on `symbol` or on all symbols, across
+    /// however many pages WOO splits them into.
+    pub async fn get_open_orders(&self, symbol: Option<&str>) -> anyhow::Result<Vec<Row>> {
+        Ok(self.open_orders(symbol, None).await?)
+    }
+
+    /// How many orders are open on `symbol`, or on every symbol, to check
+    /// against WOO's open order limits before submitting. Reads the `total`
+    /// of a single row page instead of fetching every order.
+    pub async fn open_order_count(&self, symbol: Option<&str>) -> anyhow::Result<u32> {
+        let filter = GetOrder {
+            symbol: symbol.map(str::to_string),
+            size: Some(1),
+            status: Some("INCOMPLETE".to_string()),
+            ..Default::default()
+        };
+        let res: GetOrderRes = self
+            .signed_request(Method::GET, "v1/orders", &filter)
+            .await?;
+
+        Ok(res.meta.total)
+    }
+
+    /// Every order, open or not, submitted with `order_tag`.
+    pub async fn get_orders_by_tag(&self, order_tag: &str) -> anyhow::Result<Vec<Row>> {
+        let filter = GetOrder {
+            order_tag: Some(order_tag.to_string()),
+            ..Default::default()
+        };
+
+        Ok(self.get_orders_stream(filter).try_collect().await?)
+    }
+
+    pub(crate) async fn open_orders(
+        &self,
+        symbol: Option<&str>,
+        order_tag: Option<&str>,
+    ) -> Result<Vec<Row>, WooError> {
+        let filter = GetOrder {
+            symbol: symbol.map(str::to_string),
+            size: Some(OPEN_ORDERS_PAGE_SIZE),
+            order_tag: order_tag.map(str::to_string),
+            status: Some("INCOMPLETE".to_string()),
+            ..Default::default()
+        };
+
+        self.get_orders_stream(filter).try_collect().await
+    }
+
+    /// Every order matching `filter`, fetched a page at a time as the stream
+    /// is polled. `filter.page` is where the stream starts.
+    pub fn get_orders_stream(
+        &self,
+        filter: GetOrder,
+    ) -> impl Stream<Item = Result<Row, WooError>> + '_ {
+        let first_page = filter.page.unwrap_or(1);
+
+        self.paginate("v1/orders", first_page, move |page| GetOrder {
+            page: Some(page),
+            ..filter.clone()
+        })
+    }
+
+    /// Every trade matching `filter`, fetched a page at a time as the stream
+    /// is polled. `filter.page` is where the stream starts.
+    pub fn get_trades_stream(
+        &self,
+        filter: GetTrades,
+    ) -> impl Stream<Item = Result<Trade, WooError>> + '_ {
+        let first_page = filter.page.unwrap_or(1);
+
+        self.paginate("v1/client/trades", first_page, move |page| GetTrades {
+            page: Some(page),
+            ..filter.clone()
+        })
+    }
+
+    /// Walks a `{meta, rows}` paginated endpoint from `first_page` until
+    /// `meta.total` rows have been seen.
+    pub(crate) fn paginate<'a, Q, T>(
+        &'a self,
+        path: &'static str,
+        first_page: u32,
+        params: impl Fn(u32) -> Q + 'a,
+    ) -> impl Stream<Item = Result<T, WooError>> + 'a
+    where
+        Q: Serialize + 'a,
+        T: DeserializeOwned + 'a,
+    {
+        #[derive(Deserialize)]
+        struct Page<T> {
+            meta: Meta,
+            rows: Vec<T>,
         }
+
+        let params = Arc::new(params);
+
+        stream::try_unfold(Some(first_page), move |page| {
+            let params = params.clone();
+            async move {
+                let Some(page) = page else {
+                    return Ok(None);
+                };
+
+                let res: Page<T> = self.signed_request(Method::GET, path, params(page)).await?;
+
+                let exhausted =
+                    res.rows.is_empty() || page * res.meta.records_per_page >= res.meta.total;
+                let next = (!exhausted).then_some(page + 1);
+
+                Ok::<_, WooError>(Some((stream::iter(res.rows.into_iter().map(Ok)), next)))
+            }
+        })
+        .try_flatten()
     }
 
-    async fn create_order(&mut self, order: WooOrder) -> anyhow::Result<SendOrderRes> {
-        self.base_url.set_path("v1/order");
+    /// Balance of every token, including locked amounts.
+    pub async fn get_holding(&self) -> anyhow::Result<HoldingRes> {
+        let params = BTreeMap::from([("all", "true")]);
 
-        let timestamp = chrono::Utc::now().timestamp_millis();
+        Ok(self
+            .signed_request(Method::GET, "v1/client/holding", params)
+            .await?)
+    }
 
-        // this part is to handle the alphabetical order of the query string
-        // `url_encoded` is just an intermediate step
-        let url_encoded = serde_qs::to_string(&order)?;
-        let deserialized: BTreeMap<String, String> = serde_qs::from_str(&url_encoded)?;
+    pub async fn get_positions(&self) -> anyhow::Result<PositionsRes> {
+        Ok(self.positions().await?)
+    }
 
-        let req_builder = self
-            .http_client
-            .post(self.base_url.clone())
-            .header("x-api-timestamp", timestamp)
-            .header(
-                "x-api-signature",
-                Woo::generate_hmac_sha256_signature(
-                    Woo::generate_sorted_query_string(&order),
-                    timestamp as u64,
-                    self.api_secret.clone(),
-                ),
+    pub(crate) async fn positions(&self) -> Result<PositionsRes, WooError> {
+        let res: WooEnvelope<PositionsRes> = self
+            .signed_request(
+                Method::GET,
+                "v1/positions",
+                BTreeMap::<String, String>::new(),
             )
-            .form(&deserialized);
+            .await?;
+        let success = res.success;
 
-        Ok(req_builder.send().await?.json().await?)
+        Ok(PositionsRes {
+            success,
+            ..res.into_data()?
+        })
     }
 
-    async fn cancel_order(&mut self, cancel_order: CancelOrder) -> anyhow::Result<CancelOrderRes> {
-        self.base_url.set_path("v1/order");
+    pub async fn get_account_info(&self) -> anyhow::Result<AccountInfo> {
+        Ok(self.account_info().await?)
+    }
 
-        let timestamp = chrono::Utc::now().timestamp_millis();
+    /// Snapshot of the top `max_level` levels of each side of the book, all
+    /// of them if `None`.
+    pub async fn get_orderbook(
+        &self,
+        symbol: &str,
+        max_level: Option<u32>,
+    ) -> anyhow::Result<Orderbook> {
+        Ok(self.orderbook(symbol, max_level).await?)
+    }
 
-        // this part is to handle the alphabetical order of the query string
-        // `url_encoded` is just an intermediate step
-        let url_encoded = serde_qs::to_string(&cancel_order)?;
-        let deserialized: BTreeMap<String, String> = serde_qs::from_str(&url_encoded)?;
+    pub(crate) async fn orderbook(
+        &self,
+        symbol: &str,
+        max_level: Option<u32>,
+    ) -> Result<Orderbook, WooError> {
+        let path = format!("v1/public/orderbook/{symbol}");
+        let params: BTreeMap<_, _> = max_level
+            .map(|max_level| ("max_level", max_level))
+            .into_iter()
+            .collect();
+
+        self.public_request(Method::GET, &path, params).await
+    }
 
-        let req_builder = self
-            .http_client
-            .delete(self.base_url.clone())
-            .header("x-api-timestamp", timestamp)
-            .header(
-                "x-api-signature",
-                Woo::generate_hmac_sha256_signature(
-                    Woo::generate_sorted_query_string(&cancel_order),
-                    timestamp as u64,
-                    self.api_secret.clone(),
-                ),
-            )
-            .form(&deserialized);
+    /// Fetches the book of `symbol` and estimates what a market order for
+    /// `quantity` on `side` would fill at.
+    pub async fn estimate_market_impact(
+        &self,
+        symbol: &str,
+        side: Side,
+        quantity: Decimal,
+    ) -> anyhow::Result<MarketImpact> {
+        if quantity <= Decimal::ZERO {
+            return Err(WooError::InvalidOrder(format!(
+                "cannot estimate the impact of quantity {quantity}"
+            ))
+            .into());
+        }
+        let book = self.orderbook(symbol, None).await?;
+
+        Ok(MarketImpact {
+            mid: book.mid_price(),
+            estimate: book.vwap_for_quantity(side, quantity),
+        })
+    }
+
+    /// Every token WOO lists.
+    pub async fn get_tokens(&self) -> anyhow::Result<Vec<Token>> {
+        let res = self
+            .public_request::<_, WooEnvelope<TokensRes>>(Method::GET, "v1/public/token", ())
+            .await?
+            .into_data()?;
+
+        Ok(res.rows)
+    }
+
+    pub async fn get_funding_rate(&self, symbol: &str) -> anyhow::Result<FundingRate> {
+        let path = format!("v1/public/funding_rate/{symbol}");
 
-        Ok(req_builder.send().await?.json().await?)
+        Ok(self.public_request(Method::GET, &path, ()).await?)
     }
 
-    async fn get_orders(&mut self, get_order: GetOrder) -> anyhow::Result<GetOrderRes> {
-        self.base_url.set_path("v1/orders");
+    /// WOO's clock as of its `system_info` response, e.g. to debug signed
+    /// requests rejected for their timestamp.
+    pub async fn server_time(&self) -> anyhow::Result<chrono::DateTime<chrono::Utc>> {
+        let info: WooEnvelope<serde::de::IgnoredAny> = self
+            .public_request(Method::GET, "v1/public/system_info", ())
+            .await?;
+
+        info.timestamp
+            .ok_or_else(|| anyhow::anyhow!("system info carried no timestamp"))
+    }
+
+    /// The predicted funding rate of `symbol` and the seconds left until it
+    /// applies, measured against the server's clock as of the response rather
+    /// than the local one.
+    pub async fn funding_countdown(&self, symbol: &str) -> anyhow::Result<FundingCountdown> {
+        let rate = self.get_funding_rate(symbol).await?;
+        let server_time = chrono::DateTime::from_timestamp_millis(rate.timestamp)
+            .unwrap_or_else(chrono::Utc::now);
+
+        Ok(FundingCountdown::at(&rate, server_time))
+    }
+
+    /// Signs and sends a request to an arbitrary private endpoint.
+    ///
+    /// `params` go into the query string for `GET` and `DELETE` and into a
+    /// form body otherwise; either way the exact bytes sent are the ones that
+    /// get signed. This is the escape hatch for endpoints the crate doesn't
+    /// model yet.
+    pub async fn signed_request<Q, R>(
+        &self,
+        method: Method,
+        path: &str,
+        params: Q,
+    ) -> Result<R, WooError>
+    where
+        Q: Serialize,
+        R: DeserializeOwned,
+    {
+        let mut payload = Woo::generate_sorted_query_string(&params)?;
+        if let Some(recv_window) = self.recv_window {
+            payload = Woo::add_sorted_param(&payload, &format!("recv_window={recv_window}"));
+        }
+
+        self.signed_body_request(method, path, RequestBody::Form(payload))
+            .await
+    }
 
-        let timestamp = chrono::Utc::now().timestamp_millis();
+    /// Signs and sends a request to a `v3` endpoint. Those sign
+    /// `{timestamp}{METHOD}{path}{body}` over a JSON body rather than the
+    /// sorted query string of `v1`.
+    pub(crate) async fn signed_v3_request<B, R>(
+        &self,
+        method: Method,
+        path: &str,
+        body: &B,
+    ) -> Result<R, WooError>
+    where
+        B: Serialize,
+        R: DeserializeOwned,
+    {
+        self.signed_body_request(method, path, RequestBody::json(body)?)
+            .await
+    }
 
-        let sorted_qs = Woo::generate_sorted_query_string(&get_order);
+    /// Signs `body` the way its encoding calls for and sends it to `path`.
+    pub(crate) async fn signed_body_request<R>(
+        &self,
+        method: Method,
+        path: &str,
+        body: RequestBody,
+    ) -> Result<R, WooError>
+    where
+        R: DeserializeOwned,
+    {
+        let (req_builder, audit) = self
+            .signed_builder(method, self.endpoint(path), body)
+            .await?;
 
-        // this part is to handle the alphabetical order of the query string
-        // `url_encoded` is just an intermediate step
-        let url_encoded = serde_qs::to_string(&get_order)?;
-        let deserialized: BTreeMap<String, String> = serde_qs::from_str(&url_encoded)?;
+        self.send(req_builder, audit).await
+    }
 
+    /// A request to `url` carrying `body`, signed with the current key and
+    /// a fresh timestamp.
+    pub(crate) async fn signed_builder(
+        &self,
+        method: Method,
+        mut url: Url,
+        body: RequestBody,
+    ) -> Result<(reqwest::RequestBuilder, Option<PendingAudit>), WooError> {
+        let timestamp = self.signing_timestamp()?;
+        let key = self.key.current();
+        let signature = key
+            .sign(&body.signing_payload(timestamp, &method, url.path()))
+            .await?;
+
+        let audit = (self.audit.is_some() && method != Method::GET).then(|| PendingAudit {
+            timestamp,
+            method: method.clone(),
+            endpoint: url.path().to_string(),
+            payload: body.encoded().to_string(),
+            signature: signature.clone(),
+        });
+
+        let in_query = body.in_query(&method);
+        if in_query && !body.encoded().is_empty() {
+            url.set_query(Some(body.encoded()));
+        }
         let req_builder = self
             .http_client
-            .get(self.base_url.clone())
+            .request(method, url)
+            .header("x-api-key", key.api_key.clone())
             .header("x-api-timestamp", timestamp)
-            .header(
-                "x-api-signature",
-                Woo::generate_hmac_sha256_signature(
-                    sorted_qs,
-                    timestamp as u64,
-                    self.api_secret.clone(),
-                ),
+            .header("x-api-signature", signature);
+        let req_builder = match body {
+            _ if in_query => req_builder,
+            RequestBody::Form(payload) => req_builder
+                .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .body(payload),
+            RequestBody::Json(body) => req_builder
+                .header(CONTENT_TYPE, "application/json")
+                .body(body),
+        };
+
+        Ok((req_builder, audit))
+    }
+
+    /// Has WOO reject signed requests that arrive more than `recv_window_ms`
+    /// after their timestamp, narrowing the window in which a captured
+    /// request could be replayed. Applies to clones made after this call.
+    pub fn with_recv_window(mut self, recv_window_ms: u64) -> Self {
+        self.recv_window = Some(recv_window_ms);
+        self
+    }
+
+    /// Declares the account as being in hedge mode, so `reduce_only` orders
+    /// are checked for a matching `position_side` before they are sent.
+    pub fn with_hedge_mode(mut self, hedge_mode: bool) -> Self {
+        self.hedge_mode = hedge_mode;
+        self
+    }
+
+    /// Inserts `param` into an already sorted query string, keeping it sorted.
+    fn add_sorted_param(sorted_query_string: &str, param: &str) -> String {
+        let mut params: Vec<&str> = sorted_query_string
+            .split('&')
+            .filter(|param| !param.is_empty())
+            .collect();
+        params.push(param);
+        params.sort();
+
+        params.join("&")
+    }
+
+    /// Sends an unsigned request to a public endpoint, `params` going into the
+    /// query string.
+    pub async fn public_request<Q, R>(
+        &self,
+        method: Method,
+        path: &str,
+        params: Q,
+    ) -> Result<R, WooError>
+    where
+        Q: Serialize,
+        R: DeserializeOwned,
+    {
+        self.unsigned_request(method, self.endpoint(path), params)
+            .await
+    }
+
+    /// [`Woo::public_request`] to the historical market data host rather
+    /// than the trading one.
+    pub(crate) async fn public_data_request<Q, R>(
+        &self,
+        method: Method,
+        path: &str,
+        params: Q,
+    ) -> Result<R, WooError>
+    where
+        Q: Serialize,
+        R: DeserializeOwned,
+    {
+        let mut url = self.public_data_url.clone();
+        url.set_path(path);
+
+        self.unsigned_request(method, url, params).await
+    }
+
+    async fn unsigned_request<Q, R>(
+        &self,
+        method: Method,
+        url: Url,
+        params: Q,
+    ) -> Result<R, WooError>
+    where
+        Q: Serialize,
+        R: DeserializeOwned,
+    {
+        let ttl = self
+            .public_cache
+            .as_ref()
+            .and_then(|cache| cache.ttl(&method, url.path()));
+        let req_builder = self.http_client.request(method, url).query(&params);
+
+        let (Some(cache), Some(ttl)) = (&self.public_cache, ttl) else {
+            return self.send(req_builder, None).await;
+        };
+        let url = req_builder
+            .try_clone()
+            .and_then(|req_builder| req_builder.build().ok())
+            .map(|request| request.url().to_string());
+        let Some(url) = url else {
+            return self.send(req_builder, None).await;
+        };
+
+        let response = match cache.get(&url, ttl) {
+            Some(response) => response,
+            None => {
+                let response: serde_json::Value = self.send(req_builder, None).await?;
+                cache.put(url, response.clone());
+                response
+            }
+        };
+        Woo::decode_response(&response.to_string(), None)
+    }
+
+    /// [`Woo::signed_request`] for truly ad-hoc calls: untyped params in,
+    /// untyped JSON out.
+    pub async fn signed_json(
+        &self,
+        method: Method,
+        path: &str,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value, WooError> {
+        let params = match params {
+            serde_json::Value::Null => serde_json::Value::Object(Default::default()),
+            params => params,
+        };
+
+        self.signed_request(method, path, params).await
+    }
+
+    /// Signed `GET` of an endpoint the crate doesn't model; `params` (an
+    /// object, or `null` for none) go into the query string.
+    pub async fn signed_get_json(
+        &self,
+        path: &str,
+        params: serde_json::Value,
+    ) -> anyhow::Result<serde_json::Value> {
+        Ok(self.signed_json(Method::GET, path, params).await?)
+    }
+
+    /// Signed `POST` of an endpoint the crate doesn't model; `params` (an
+    /// object, or `null` for none) go into the form body.
+    pub async fn signed_post_json(
+        &self,
+        path: &str,
+        params: serde_json::Value,
+    ) -> anyhow::Result<serde_json::Value> {
+        Ok(self.signed_json(Method::POST, path, params).await?)
+    }
+
+    /// Milliseconds since the epoch by the server's clock, as far as the
+    /// clock telemetry could tell, to sign requests with.
+    pub(crate) fn signing_timestamp(&self) -> Result<u64, WooError> {
+        let offset_ms = self
+            .telemetry
+            .as_ref()
+            .map_or(0, |telemetry| telemetry.offset_ms());
+        Woo::checked_timestamp(chrono::Utc::now().timestamp_millis() + offset_ms)
+    }
+
+    /// `chrono` counts in `i64` and signatures in `u64`, this refuses to wrap
+    /// a pre-epoch timestamp into a huge one.
+    pub(crate) fn checked_timestamp(timestamp_ms: i64) -> Result<u64, WooError> {
+        u64::try_from(timestamp_ms).map_err(|_| WooError::InvalidTimestamp(timestamp_ms))
+    }
+
+    pub(crate) fn endpoint(&self, path: &str) -> Url {
+        let mut url = self.base_url.clone();
+        url.set_path(path);
+        url
+    }
+
+    pub(crate) async fn send<R>(
+        &self,
+        req_builder: reqwest::RequestBuilder,
+        audit: Option<PendingAudit>,
+    ) -> Result<R, WooError>
+    where
+        R: DeserializeOwned,
+    {
+        let _in_flight = self.in_flight.start();
+        let request_id = Uuid::new_v4();
+        let transport_error = |source| WooError::Transport {
+            source,
+            request_id: Some(request_id),
+        };
+
+        let request = req_builder
+            .header("x-request-id", request_id.to_string())
+            .build()
+            .map_err(transport_error)?;
+
+        if let Some(recorder) = &self.recorder {
+            recorder.record(&request, request_id);
+            if recorder.dry_run() {
+                return Err(WooError::DryRun(request_id));
+            }
+        }
+
+        let span = tracing::info_span!(
+            "woo_request",
+            method = %request.method(),
+            path = request.url().path(),
+            %request_id,
+        );
+
+        let path = request.url().path().to_string();
+        // only paid for while capture is on
+        let captured = self.responses.as_ref().map(|_| {
+            let url = request.url();
+            let path = match url.query() {
+                Some(query) => format!("{}?{query}", url.path()),
+                None => url.path().to_string(),
+            };
+            (
+                request.method().clone(),
+                path,
+                response_log::redacted(request.headers()),
             )
-            .query(&deserialized);
+        });
+
+        let result = async {
+            let sent_at = chrono::Utc::now();
+            let started = std::time::Instant::now();
+            let response = match &self.proxy_pool {
+                Some(pool) => pool.execute(request).await,
+                None => self.http_client.execute(request).await,
+            };
+            let round_trip = started.elapsed();
+            let response = match response {
+                Ok(response) => response,
+                Err(source) => {
+                    self.audit(audit, request_id, AuditOutcome::Unacknowledged);
+                    return Err(transport_error(source));
+                }
+            };
+
+            let status = response.status().as_u16();
+            let response_headers = captured
+                .as_ref()
+                .map(|_| response_log::redacted(response.headers()));
+            let body = match response.text().await {
+                Ok(body) => body,
+                Err(source) => {
+                    // the status line arrived, so the exchange did see it
+                    let outcome = AuditOutcome::Responded {
+                        status,
+                        api_code: None,
+                    };
+                    self.audit(audit, request_id, outcome);
+                    return Err(transport_error(source));
+                }
+            };
+            self.latency.record(&path, started.elapsed());
+            if let Some(telemetry) = &self.telemetry {
+                if let Some(server_time) = Woo::response_timestamp(&body) {
+                    telemetry.record(sent_at, round_trip, server_time);
+                }
+            }
+
+            if let (Some(log), Some((method, path, request_headers))) = (&self.responses, captured)
+            {
+                log.record(CapturedResponse {
+                    at: chrono::Utc::now(),
+                    request_id,
+                    method,
+                    path,
+                    request_headers,
+                    status,
+                    response_headers: response_headers.unwrap_or_default(),
+                    body: body.clone(),
+                });
+            }
+
+            let decoded = Woo::decode_response(&body, Some(request_id));
+            let api_code = match &decoded {
+                Err(WooError::Api(err)) => Some(err.code),
+                _ => None,
+            };
+            self.audit(
+                audit,
+                request_id,
+                AuditOutcome::Responded { status, api_code },
+            );
 
-        // let intermediate = req_builder.send().await?;
+            decoded
+        }
+        .instrument(span)
+        .await;
+        self.stats.record(&path, &result);
 
-        // let text = intermediate.text().await?;
+        result
+    }
 
-        // dbg!(&text);
+    fn audit(&self, pending: Option<PendingAudit>, request_id: Uuid, outcome: AuditOutcome) {
+        if let (Some(log), Some(pending)) = (&self.audit, pending) {
+            log.submit(pending, request_id, outcome);
+        }
+    }
 
-        // Ok(())
+    fn response_timestamp(body: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+        #[derive(Deserialize)]
+        struct Timestamped {
+            timestamp: Option<serde_json::Value>,
+        }
 
-        Ok(req_builder.send().await?.json().await?)
+        let timestamp = serde_json::from_str::<Timestamped>(body).ok()?.timestamp?;
+        server_timestamp(&timestamp)
     }
 
-    fn generate_sorted_query_string<P>(body: P) -> String
+    fn decode_response<R>(body: &str, request_id: Option<Uuid>) -> Result<R, WooError>
+    where
+        R: DeserializeOwned,
+    {
+        #[derive(Deserialize)]
+        struct ErrorEnvelope {
+            success: bool,
+            code: Option<i64>,
+            message: Option<String>,
+        }
+
+        // without a code it is still a failure, and worth the request id
+        if let Ok(ErrorEnvelope {
+            success: false,
+            code,
+            message,
+        }) = serde_json::from_str::<ErrorEnvelope>(body)
+        {
+            return Err(WooApiError {
+                code: code.unwrap_or(WooErrorCode::UnknownError.code()),
+                message: message.unwrap_or_else(|| "response reported success: false".to_string()),
+                request_id,
+            }
+            .into());
+        }
+
+        #[cfg(feature = "diagnostics")]
+        {
+            let deserializer = &mut serde_json::Deserializer::from_str(body);
+            serde_path_to_error::deserialize(deserializer).map_err(|err| WooError::Decode {
+                path: Some(err.path().to_string()),
+                source: err.into_inner(),
+                body: body.to_string(),
+                request_id,
+            })
+        }
+
+        #[cfg(not(feature = "diagnostics"))]
+        serde_json::from_str(body).map_err(|source| WooError::Decode {
+            source,
+            path: None,
+            body: body.to_string(),
+            request_id,
+        })
+    }
+
+    fn generate_sorted_query_string<P>(body: P) -> Result<String, serde_qs::Error>
     where
         P: Serialize,
     {
-        let unsorted_query_string =
-            serde_qs::to_string(&body).expect("fail to serialize to query string");
+        let unsorted_query_string = serde_qs::to_string(&body)?;
 
         let mut sorted_query_string = unsorted_query_string.split('&').collect::<Vec<&str>>();
         sorted_query_string.sort();
 
-        sorted_query_string.join("&")
+        Ok(sorted_query_string.join("&"))
     }
 
-    fn generate_hmac_sha256_signature(
+    /// The signature [`HmacSigner`](crate::signer::HmacSigner) computes for a
+    /// `v1` payload, to check requests against.
+    pub(crate) fn generate_hmac_sha256_signature(
         sorted_query_string: String,
         timestamp: u64,
-        secret_key: String,
+        secret_key: &str,
     ) -> String {
-        let concatted = format!("{}|{}", sorted_query_string, timestamp);
+        hex::encode(
+            Woo::signature_mac(&sorted_query_string, timestamp, secret_key)
+                .finalize()
+                .into_bytes(),
+        )
+    }
+
+    /// Hex HMAC of a `v3` payload, `{timestamp}{METHOD}{path}{body}`.
+    pub(crate) fn generate_v3_signature(payload: &str, secret_key: &str) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret_key.as_bytes()).expect("HMAC failed");
+        mac.update(payload.as_bytes());
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    fn signature_mac(payload: &str, timestamp: u64, secret_key: &str) -> Hmac<Sha256> {
+        let concatted = format!("{}|{}", payload, timestamp);
 
         let mut mac = Hmac::<Sha256>::new_from_slice(secret_key.as_bytes()).expect("HMAC failed");
         mac.update(concatted.as_bytes());
+        mac
+    }
 
-        hex::encode(mac.finalize().into_bytes())
+    /// Whether `signature` is what a private websocket `auth` request for
+    /// `timestamp` should carry, i.e. the hex HMAC of `|timestamp`. The
+    /// comparison runs in constant time.
+    pub fn verify_ws_auth(timestamp: u64, signature: &str, secret_key: &str) -> bool {
+        let Ok(signature) = hex::decode(signature) else {
+            return false;
+        };
+
+        Woo::signature_mac("", timestamp, secret_key)
+            .verify_slice(&signature)
+            .is_ok()
+    }
+}
+
+/// The exchange refusing an order request, as [`WooError::OrderRejected`].
+pub(crate) fn order_rejection(err: WooError) -> WooError {
+    match err {
+        WooError::Api(err) => WooError::OrderRejected(err),
+        err => err,
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::constants::WOO_API_BASE_URL;
+    use crate::woo_data_structs::CancelStatus;
+    use rust_decimal_macros::dec;
+    use wiremock::matchers::{any, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn mock_woo(server: &MockServer) -> Woo {
+        Woo::builder(
+            Environment::Staging,
+            Credentials::new("test-key", "QHKRXHPAW1MC9YGZMAT8YDJG2HPR"),
+        )
+        .base_url(Url::parse(&server.uri()).unwrap())
+        .build()
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn each_body_kind_is_signed_and_sent_consistently() {
+        const SECRET: &str = "QHKRXHPAW1MC9YGZMAT8YDJG2HPR";
+        let woo = Woo::builder(Environment::Staging, Credentials::new("test-key", SECRET))
+            .build()
+            .unwrap();
+        let signed = |method: Method, body: RequestBody| {
+            let woo = woo.clone();
+            async move {
+                let url = woo.endpoint("v1/order");
+                let (req_builder, _) = woo.signed_builder(method, url, body).await.unwrap();
+                req_builder.build().unwrap()
+            }
+        };
+        let header = |request: &reqwest::Request, name: &str| {
+            request
+                .headers()
+                .get(name)
+                .map(|value| value.to_str().unwrap().to_string())
+        };
+        let form = || RequestBody::Form("order_id=1&symbol=SPOT_BTC_USDT".to_string());
+
+        // v1 reads: the params ride in the query, there is no body to type
+        let get = signed(Method::GET, form()).await;
+        let timestamp: u64 = header(&get, "x-api-timestamp").unwrap().parse().unwrap();
+        assert_eq!(get.url().query(), Some("order_id=1&symbol=SPOT_BTC_USDT"));
+        assert!(get.body().is_none());
+        assert_eq!(header(&get, "content-type"), None);
+        assert_eq!(
+            header(&get, "x-api-signature").unwrap(),
+            Woo::generate_hmac_sha256_signature(form().encoded().to_string(), timestamp, SECRET)
+        );
+
+        // v1 writes: the same params as a form
+        let post = signed(Method::POST, form()).await;
+        let timestamp: u64 = header(&post, "x-api-timestamp").unwrap().parse().unwrap();
+        assert_eq!(post.url().query(), None);
+        assert_eq!(
+            post.body().unwrap().as_bytes(),
+            Some(form().encoded().as_bytes())
+        );
+        assert_eq!(
+            header(&post, "content-type").as_deref(),
+            Some("application/x-www-form-urlencoded")
+        );
+        assert_eq!(
+            header(&post, "x-api-signature").unwrap(),
+            Woo::generate_hmac_sha256_signature(form().encoded().to_string(), timestamp, SECRET)
+        );
+
+        // v3: JSON, signed together with the method and path, even on DELETE
+        let json = RequestBody::json(&serde_json::json!({ "symbol": "SPOT_BTC_USDT" })).unwrap();
+        let delete = signed(Method::DELETE, json.clone()).await;
+        let timestamp = header(&delete, "x-api-timestamp").unwrap();
+        assert_eq!(delete.url().query(), None);
+        assert_eq!(
+            delete.body().unwrap().as_bytes(),
+            Some(json.encoded().as_bytes())
+        );
+        assert_eq!(
+            header(&delete, "content-type").as_deref(),
+            Some("application/json")
+        );
+        let payload = format!("{timestamp}DELETE/v1/order{}", json.encoded());
+        assert_eq!(
+            header(&delete, "x-api-signature").unwrap(),
+            Woo::generate_v3_signature(&payload, SECRET)
+        );
+    }
 
     #[tokio::test]
     async fn get_woo_system_status() {
@@ -200,26 +1284,14 @@ mod tests {
         let status = body.status();
         assert_eq!(status.as_u16(), 200);
 
-        #[derive(Debug, Deserialize)]
-        struct WooSystemStatus {
-            success: bool,
-            data: Data,
-            timestamp: u64,
-        }
-
-        #[derive(Debug, Deserialize)]
-        struct Data {
-            status: u8,
-            msg: String,
-        }
-
-        let body: WooSystemStatus = body.json().await.expect("failed to parse json");
+        let body: WooEnvelope<serde_json::Value> = body.json().await.expect("failed to parse json");
         assert!(body.success);
+        assert!(body.timestamp.is_some());
     }
 
     #[tokio::test]
     async fn get_woo_history() {
-        dotenv().ok();
+        env::load_dotenv();
 
         let mut woo_url: Url = Url::parse(WOO_API_BASE_URL).unwrap();
         woo_url.set_path("v1/client/trades");
@@ -227,12 +1299,12 @@ mod tests {
         let woo_api_key = dotenv::var("WOO_API_KEY").unwrap();
         let woo_api_secret = dotenv::var("WOO_API_SECRET").unwrap();
 
-        let proxy_url: Url = Url::parse(PROXY_URL).unwrap();
+        let proxy_url: Url = Url::parse(&dotenv::var("PROXY_URL").unwrap()).unwrap();
 
         let proxy_username = dotenv::var("PROXY_USERNAME").unwrap();
         let proxy_password = dotenv::var("PROXY_PASSWORD").unwrap();
 
-        let timestamp = chrono::Utc::now().timestamp_millis();
+        let timestamp = Woo::checked_timestamp(chrono::Utc::now().timestamp_millis()).unwrap();
 
         let proxy = reqwest::Proxy::all(proxy_url.clone())
             .unwrap()
@@ -247,11 +1319,7 @@ mod tests {
             .header("x-api-timestamp", timestamp)
             .header(
                 "x-api-signature",
-                Woo::generate_hmac_sha256_signature(
-                    "".to_string(),
-                    timestamp as u64,
-                    woo_api_secret.to_string(),
-                ),
+                Woo::generate_hmac_sha256_signature("".to_string(), timestamp, &woo_api_secret),
             );
 
         let response = request.send().await.expect("failed to send request");
@@ -263,11 +1331,11 @@ mod tests {
 
     #[tokio::test]
     async fn send_order() {
-        let mut woo = Woo::new(super::Environment::Staging);
+        let woo = Woo::new(super::Environment::Staging);
 
         let order = WooOrder {
-            order_price: Some(1.0),
-            order_quantity: Some(2.0),
+            order_price: Some(dec!(1)),
+            order_quantity: Some(dec!(2)),
             order_type: "LIMIT".to_string(),
             side: "BUY".to_string(),
             symbol: "SPOT_ULP_USDT".to_string(),
@@ -277,6 +1345,7 @@ mod tests {
             reduce_only: None,
             visible_quantity: None,
             position_side: None,
+            time_in_force: None,
         };
 
         let order_created = woo.create_order(order).await.unwrap();
@@ -286,11 +1355,11 @@ mod tests {
 
     #[tokio::test]
     async fn cancel_order() {
-        let mut woo = Woo::new(super::Environment::Staging);
+        let woo = Woo::new(super::Environment::Staging);
 
         let order = WooOrder {
-            order_price: Some(1.0),
-            order_quantity: Some(2.0),
+            order_price: Some(dec!(1)),
+            order_quantity: Some(dec!(2)),
             order_type: "LIMIT".to_string(),
             side: "BUY".to_string(),
             symbol: "SPOT_ULP_USDT".to_string(),
@@ -300,6 +1369,7 @@ mod tests {
             reduce_only: None,
             visible_quantity: None,
             position_side: None,
+            time_in_force: None,
         };
 
         let order_created = woo.create_order(order).await.unwrap();
@@ -318,7 +1388,7 @@ mod tests {
 
     #[tokio::test]
     async fn get_orders() {
-        let mut woo = Woo::new(super::Environment::Staging);
+        let woo = Woo::new(super::Environment::Staging);
 
         let order = GetOrder {
             end_t: None,
@@ -336,11 +1406,686 @@ mod tests {
         let _orders = woo.get_orders(order).await.expect("failed to get orders");
     }
 
+    #[tokio::test]
+    async fn cancel_all_orders_global_signs_empty_payload() {
+        let server = MockServer::start().await;
+
+        // the exchange cancels resting orders on every symbol in one go
+        Mock::given(method("DELETE"))
+            .and(path("/v3/orders/pending"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{"success":true,"data":{"status":"CANCEL_ALL_SENT"},"timestamp":1578565539808}"#,
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let woo = mock_woo(&server);
+        let res = woo.cancel_all_orders_global().await.unwrap();
+        assert!(res.success);
+        assert_eq!(res.status, CancelStatus::CancelAllSent);
+
+        let requests = server.received_requests().await.unwrap();
+        let request = &requests[0];
+        assert_eq!(request.url.query(), None);
+        assert!(request.body.is_empty());
+
+        let timestamp: u64 = request.headers["x-api-timestamp"]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(
+            request.headers["x-api-signature"].to_str().unwrap(),
+            Woo::generate_v3_signature(
+                &format!("{timestamp}DELETE/v3/orders/pending"),
+                "QHKRXHPAW1MC9YGZMAT8YDJG2HPR",
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn delete_params_are_signed_as_sent_in_query() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("DELETE"))
+            .and(path("/v1/order"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(r#"{"success":true,"status":"CANCEL_SENT"}"#),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let woo = mock_woo(&server);
+        let cancel_order = CancelOrder {
+            order_id: OrderId(13),
+            symbol: "SPOT_BTC_USDT".to_string(),
+        };
+        assert!(woo.cancel_order(cancel_order).await.unwrap().success);
+
+        let requests = server.received_requests().await.unwrap();
+        let request = &requests[0];
+        let query = request.url.query().unwrap();
+        assert_eq!(query, "order_id=13&symbol=SPOT_BTC_USDT");
+        assert!(request.body.is_empty());
+
+        let timestamp: u64 = request.headers["x-api-timestamp"]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(
+            request.headers["x-api-signature"].to_str().unwrap(),
+            Woo::generate_hmac_sha256_signature(
+                query.to_string(),
+                timestamp,
+                "QHKRXHPAW1MC9YGZMAT8YDJG2HPR",
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn one_liners_send_the_matching_order() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/order"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{"success":true,"timestamp":"1578565539.808","order_id":9,"order_type":"MARKET","client_order_id":0}"#,
+            ))
+            .mount(&server)
+            .await;
+
+        let woo = mock_woo(&server);
+        woo.market_buy("SPOT_BTC_USDT", OrderSize::Amount(dec!(100)))
+            .await
+            .unwrap();
+        woo.market_sell("SPOT_BTC_USDT", dec!(0.01)).await.unwrap();
+        woo.limit_buy("SPOT_BTC_USDT", dec!(9000), dec!(0.1))
+            .await
+            .unwrap();
+        woo.limit_sell("SPOT_BTC_USDT", dec!(9100), dec!(0.2))
+            .await
+            .unwrap();
+
+        let bodies: Vec<_> = server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .map(|request| String::from_utf8(request.body.clone()).unwrap())
+            .collect();
+        assert_eq!(
+            bodies,
+            [
+                "order_amount=100&order_type=MARKET&side=BUY&symbol=SPOT_BTC_USDT",
+                "order_quantity=0.01&order_type=MARKET&side=SELL&symbol=SPOT_BTC_USDT",
+                "order_price=9000&order_quantity=0.1&order_type=LIMIT&side=BUY&symbol=SPOT_BTC_USDT",
+                "order_price=9100&order_quantity=0.2&order_type=LIMIT&side=SELL&symbol=SPOT_BTC_USDT",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn crossing_post_only_order_is_a_distinct_error() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/order"))
+            .respond_with(ResponseTemplate::new(400).set_body_string(
+                r#"{"success":false,"code":-1012,"message":"Post only order would take liquidity."}"#,
+            ))
+            .mount(&server)
+            .await;
+
+        let woo = mock_woo(&server);
+        let order = WooOrder::builder("SPOT_BTC_USDT", "BUY", "POST_ONLY")
+            .price(dec!(9001))
+            .size(OrderSize::Quantity(dec!(0.1)))
+            .build();
+        let err = woo.create_order(order).await.unwrap_err();
+
+        match err {
+            WooError::PostOnlyRejected(err) => assert_eq!(err.code, -1012),
+            other => panic!("expected PostOnlyRejected, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn rejected_orders_carry_code_and_message() {
+        for (code, message) in [
+            (-1101, "The risk exposure for client is too high."),
+            (-1102, "The order value (price * size) is too small."),
+            (
+                -1103,
+                "The order price is not following the tick size rule for the symbol.",
+            ),
+        ] {
+            let server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .and(path("/v1/order"))
+                .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                    "success": false, "code": code, "message": message,
+                })))
+                .mount(&server)
+                .await;
+
+            let order = WooOrder::builder("SPOT_BTC_USDT", "BUY", "LIMIT")
+                .price(dec!(9000.001))
+                .size(OrderSize::Quantity(dec!(0.1)))
+                .build();
+            let err = mock_woo(&server).create_order(order).await.unwrap_err();
+
+            match err {
+                WooError::OrderRejected(err) => {
+                    assert_eq!(err.code, code);
+                    assert_eq!(err.message, message);
+                }
+                other => panic!("expected OrderRejected, got {other:?}"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn cancelling_an_unknown_order_is_rejected() {
+        let server = MockServer::start().await;
+        Mock::given(method("DELETE"))
+            .and(path("/v1/order"))
+            .respond_with(ResponseTemplate::new(400).set_body_string(
+                r#"{"success":false,"code":-1006,"message":"Your order and symbol are not valid or already canceled."}"#,
+            ))
+            .mount(&server)
+            .await;
+
+        let cancel_order = CancelOrder {
+            order_id: OrderId(404),
+            symbol: "SPOT_BTC_USDT".to_string(),
+        };
+        let err = mock_woo(&server)
+            .cancel_order(cancel_order)
+            .await
+            .unwrap_err();
+
+        assert!(
+            matches!(err, WooError::OrderRejected(ref err) if err.error_code() == WooErrorCode::ResourceNotFound),
+            "{err:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn independent_clients_target_their_own_hosts() {
+        let (production, staging) = (MockServer::start().await, MockServer::start().await);
+        for server in [&production, &staging] {
+            Mock::given(method("GET"))
+                .and(path("/v1/client/info"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "success": true,
+                    "application": { "application_id": server.uri(), "account": "main", "leverage": 5 },
+                })))
+                .expect(1)
+                .mount(server)
+                .await;
+        }
+
+        let client = |environment, key, server: &MockServer| {
+            Woo::builder(environment, Credentials::new(key, "secret"))
+                .base_url(Url::parse(&server.uri()).unwrap())
+                .build()
+                .unwrap()
+        };
+        let production_woo = client(Environment::Production, "production-key", &production);
+        let staging_woo = client(Environment::Staging, "staging-key", &staging);
+
+        let (production_info, staging_info) = tokio::join!(
+            production_woo.get_account_info(),
+            staging_woo.get_account_info()
+        );
+        assert_eq!(production_info.unwrap().application_id, production.uri());
+        assert_eq!(staging_info.unwrap().application_id, staging.uri());
+
+        let received = staging.received_requests().await.unwrap();
+        assert_eq!(received[0].headers["x-api-key"], "staging-key");
+    }
+
+    #[test]
+    fn verifies_ws_auth_signature() {
+        let secret = "QHKRXHPAW1MC9YGZMAT8YDJG2HPR";
+        let signature = "68f50830e2ff5cee44cfd44844322870c0bc9693c0c9c87b8b72e76e0154d73e";
+
+        assert!(Woo::verify_ws_auth(1578565539808, signature, secret));
+
+        let mut tampered = signature.to_string().into_bytes();
+        tampered[0] = if tampered[0] == b'0' { b'1' } else { b'0' };
+        let tampered = String::from_utf8(tampered).unwrap();
+        assert!(!Woo::verify_ws_auth(1578565539808, &tampered, secret));
+        assert!(!Woo::verify_ws_auth(1578565539809, &tampered, secret));
+        assert!(!Woo::verify_ws_auth(1578565539808, "not hex", secret));
+    }
+
+    #[tokio::test]
+    async fn recv_window_is_signed_in_sorted_position() {
+        let server = MockServer::start().await;
+        Mock::given(method("DELETE"))
+            .and(path("/v1/order"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(r#"{"success":true,"status":"CANCEL_SENT"}"#),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/positions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true, "positions": [],
+            })))
+            .mount(&server)
+            .await;
+
+        let woo = mock_woo(&server).with_recv_window(5000);
+        woo.cancel_order(CancelOrder {
+            order_id: OrderId(13),
+            symbol: "SPOT_BTC_USDT".to_string(),
+        })
+        .await
+        .unwrap();
+        woo.get_positions().await.unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        for (request, expected) in requests.iter().zip([
+            "order_id=13&recv_window=5000&symbol=SPOT_BTC_USDT",
+            "recv_window=5000",
+        ]) {
+            let query = request.url.query().unwrap();
+            assert_eq!(query, expected);
+
+            let timestamp: u64 = request.headers["x-api-timestamp"]
+                .to_str()
+                .unwrap()
+                .parse()
+                .unwrap();
+            assert_eq!(
+                request.headers["x-api-signature"].to_str().unwrap(),
+                Woo::generate_hmac_sha256_signature(
+                    query.to_string(),
+                    timestamp,
+                    "QHKRXHPAW1MC9YGZMAT8YDJG2HPR",
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn debug_redacts_api_secret() {
+        let woo = Woo::builder(
+            Environment::Staging,
+            Credentials::new("test-key", "QHKRXHPAW1MC9YGZMAT8YDJG2HPR"),
+        )
+        .build()
+        .unwrap();
+
+        let debug = format!("{woo:?}");
+        assert!(!debug.contains("QHKRXHPAW1MC9YGZMAT8YDJG2HPR"));
+        assert!(debug.contains("[REDACTED]"));
+
+        let credentials = Credentials::new("test-key", "QHKRXHPAW1MC9YGZMAT8YDJG2HPR");
+        assert!(!format!("{credentials:?}").contains("QHKRXHPAW1MC9YGZMAT8YDJG2HPR"));
+    }
+
+    const CLIENT_ORDER_RES: &str = r#"{
+        "success": true,
+        "created_time": "1577349119.33",
+        "side": "SELL",
+        "status": "FILLED",
+        "symbol": "SPOT_BTC_USDT",
+        "client_order_id": 123,
+        "reduce_only": false,
+        "order_id": 1,
+        "order_tag": "default",
+        "type": "LIMIT",
+        "price": 123,
+        "quantity": 0.1,
+        "amount": null,
+        "visible": 0.1,
+        "executed": 0.1,
+        "total_fee": 0.00001,
+        "fee_asset": "BTC",
+        "total_rebate": null,
+        "rebate_asset": null,
+        "updated_time": "1577349119.45",
+        "average_executed_price": 123,
+        "position_side": "BOTH",
+        "realized_pnl": null
+    }"#;
+
+    #[tokio::test]
+    async fn get_order_by_client_id() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/v1/client/order/123"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(CLIENT_ORDER_RES))
+            .mount(&server)
+            .await;
+
+        let woo = mock_woo(&server);
+        let row = woo
+            .get_order_by_client_id(ClientOrderId::Numeric(123))
+            .await
+            .unwrap();
+
+        assert_eq!(row.client_order_id, Some(ClientOrderId::Numeric(123)));
+        assert_eq!(row.status, "FILLED");
+
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests[0].url.query(), None);
+    }
+
+    #[tokio::test]
+    async fn get_order_by_client_id_not_found() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/v1/client/order/404"))
+            .respond_with(ResponseTemplate::new(400).set_body_string(
+                r#"{"success":false,"code":-1006,"message":"Your order and symbol are not valid or already canceled."}"#,
+            ))
+            .mount(&server)
+            .await;
+
+        let woo = mock_woo(&server);
+        let err = woo
+            .get_order_by_client_id(ClientOrderId::Numeric(404))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            err.downcast_ref::<WooError>(),
+            Some(WooError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn text_client_order_ids_are_refused_on_v1() {
+        let server = MockServer::start().await;
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(500))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let woo = mock_woo(&server);
+        let client_order_id = ClientOrderId::text("3f2b8c1e-5d4a-4e6f-9a7b-0c1d2e3f4a5b").unwrap();
+        let order = WooOrder::builder("SPOT_BTC_USDT", "BUY", "LIMIT")
+            .client_order_id(client_order_id.clone())
+            .price(dec!(9000))
+            .size(OrderSize::Quantity(dec!(0.1)))
+            .build();
+
+        assert!(matches!(
+            woo.create_order(order).await,
+            Err(WooError::InvalidOrder(_))
+        ));
+        let err = woo
+            .get_order_by_client_id(client_order_id.clone())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<WooError>(),
+            Some(WooError::InvalidOrder(_))
+        ));
+        let cancel = CancelOrderByClientId {
+            client_order_id,
+            symbol: "SPOT_BTC_USDT".to_string(),
+        };
+        assert!(woo.cancel_order_by_client_id(cancel).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn get_open_orders_pages_through_every_order() {
+        let server = MockServer::start().await;
+
+        let row: serde_json::Value = serde_json::from_str(CLIENT_ORDER_RES).unwrap();
+        for current_page in 1..=3 {
+            let rows = if current_page < 3 {
+                vec![row.clone(), row.clone()]
+            } else {
+                vec![row.clone()]
+            };
+            Mock::given(method("GET"))
+                .and(path("/v1/orders"))
+                .and(query_param("symbol", "SPOT_BTC_USDT"))
+                .and(query_param("status", "INCOMPLETE"))
+                .and(query_param("page", current_page.to_string()))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "success": true,
+                    "meta": { "total": 5, "records_per_page": 2, "current_page": current_page },
+                    "rows": rows,
+                })))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+
+        let woo = mock_woo(&server);
+        let orders = woo.get_open_orders(Some("SPOT_BTC_USDT")).await.unwrap();
+        assert_eq!(orders.len(), 5);
+    }
+
+    #[tokio::test]
+    async fn open_order_count_reads_the_total_of_one_page() {
+        let server = MockServer::start().await;
+        let row: serde_json::Value = serde_json::from_str(CLIENT_ORDER_RES).unwrap();
+        Mock::given(method("GET"))
+            .and(path("/v1/orders"))
+            .and(query_param("status", "INCOMPLETE"))
+            .and(query_param("size", "1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "meta": { "total": 37, "records_per_page": 1, "current_page": 1 },
+                "rows": [row],
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let count = mock_woo(&server).open_order_count(None).await.unwrap();
+
+        assert_eq!(count, 37);
+    }
+
+    #[tokio::test]
+    async fn get_orders_by_tag_encodes_the_tag() {
+        let server = MockServer::start().await;
+
+        let row: serde_json::Value = serde_json::from_str(CLIENT_ORDER_RES).unwrap();
+        Mock::given(method("GET"))
+            .and(path("/v1/orders"))
+            .and(query_param("order_tag", "mm v2&beta"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "meta": { "total": 1, "records_per_page": 25, "current_page": 1 },
+                "rows": [row],
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let woo = mock_woo(&server);
+        let orders = woo.get_orders_by_tag("mm v2&beta").await.unwrap();
+        assert_eq!(orders.len(), 1);
+
+        let requests = server.received_requests().await.unwrap();
+        let query = requests[0].url.query().unwrap();
+        let timestamp: u64 = requests[0].headers["x-api-timestamp"]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(
+            requests[0].headers["x-api-signature"].to_str().unwrap(),
+            Woo::generate_hmac_sha256_signature(
+                query.to_string(),
+                timestamp,
+                "QHKRXHPAW1MC9YGZMAT8YDJG2HPR",
+            )
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "hits the live api"]
+    async fn created_order_is_found_by_tag() {
+        let woo = Woo::new(super::Environment::Staging);
+        let tag = format!("tag-test-{}", woo.next_client_order_id());
+
+        let order = WooOrder::builder("SPOT_ULP_USDT", "BUY", "LIMIT")
+            .price(dec!(1))
+            .size(OrderSize::Quantity(dec!(2)))
+            .order_tag(&tag)
+            .build();
+        let created = woo.create_order(order).await.unwrap();
+
+        let orders = woo.get_orders_by_tag(&tag).await.unwrap();
+        assert!(orders.iter().any(|row| row.order_id == created.order_id));
+
+        woo.cancel_order(CancelOrder {
+            order_id: created.order_id,
+            symbol: "SPOT_ULP_USDT".to_string(),
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn server_time_reads_the_system_info_timestamp() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/v1/public/system_info"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{"success":true,"data":{"status":0,"msg":"System is functioning properly."},"timestamp":"1578565539.808"}"#,
+            ))
+            .mount(&server)
+            .await;
+
+        let server_time = mock_woo(&server).server_time().await.unwrap();
+
+        assert_eq!(server_time.timestamp_millis(), 1578565539808);
+        // a timestamp read as the wrong unit would land decades away
+        let drift = chrono::Utc::now() - server_time;
+        assert!(drift > chrono::Duration::zero() && drift < chrono::Duration::days(365 * 20));
+    }
+
+    #[tokio::test]
+    async fn raw_json_helpers_reach_unmodeled_endpoints() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/v1/public/system_info"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{"success":true,"data":{"status":0,"msg":"System is functioning properly."}}"#,
+            ))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/asset/main_sub_transfer"))
+            .and(wiremock::matchers::body_string("amount=10&token=USDT"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"success":true,"id":1}"#))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let woo = mock_woo(&server);
+
+        let info = woo
+            .signed_get_json("v1/public/system_info", serde_json::Value::Null)
+            .await
+            .unwrap();
+        assert_eq!(info["success"], true);
+        assert_eq!(info["data"]["status"], 0);
+
+        let transfer = woo
+            .signed_post_json(
+                "v1/asset/main_sub_transfer",
+                serde_json::json!({ "token": "USDT", "amount": 10 }),
+            )
+            .await
+            .unwrap();
+        assert_eq!(transfer["id"], 1);
+    }
+
+    #[tokio::test]
+    async fn shared_http_client_keeps_keys_apart() {
+        let server = MockServer::start().await;
+
+        for key in ["key-a", "key-b"] {
+            Mock::given(method("DELETE"))
+                .and(path("/v3/orders/pending"))
+                .and(wiremock::matchers::header("x-api-key", key))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_string(r#"{"success":true,"data":{"status":"CANCEL_ALL_SENT"}}"#),
+                )
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+
+        let http_client = reqwest::Client::new();
+        let sub_accounts = ["key-a", "key-b"].map(|key| {
+            Woo::builder(Environment::Staging, Credentials::new(key, "secret"))
+                .base_url(Url::parse(&server.uri()).unwrap())
+                .http_client(http_client.clone())
+                .build()
+                .unwrap()
+        });
+
+        for woo in &sub_accounts {
+            assert!(woo.cancel_all_orders_global().await.unwrap().success);
+        }
+    }
+
+    #[cfg(feature = "diagnostics")]
+    #[test]
+    fn decode_error_names_offending_path() {
+        let row: serde_json::Value = serde_json::from_str(CLIENT_ORDER_RES).unwrap();
+        let mut malformed = row.clone();
+        malformed["price"] = serde_json::json!("not a number");
+
+        let body = serde_json::json!({
+            "success": true,
+            "meta": { "total": 2, "records_per_page": 25, "current_page": 1 },
+            "rows": [row, malformed],
+        })
+        .to_string();
+
+        let err = Woo::decode_response::<GetOrderRes>(&body, None).unwrap_err();
+        match &err {
+            WooError::Decode { path, .. } => assert_eq!(path.as_deref(), Some("rows[1].price")),
+            other => panic!("expected a decode error, got {other:?}"),
+        }
+        assert!(err.to_string().contains("at `rows[1].price`"));
+    }
+
+    #[test]
+    fn pre_epoch_timestamps_are_rejected() {
+        assert_eq!(
+            Woo::checked_timestamp(1578565539808).unwrap(),
+            1578565539808
+        );
+        assert_eq!(Woo::checked_timestamp(0).unwrap(), 0);
+        assert!(matches!(
+            Woo::checked_timestamp(-1),
+            Err(WooError::InvalidTimestamp(-1))
+        ));
+    }
+
     #[test]
     fn test_hash_order() {
         let order = WooOrder {
-            order_price: Some(9000.0),
-            order_quantity: Some(0.11),
+            order_price: Some(dec!(9000)),
+            order_quantity: Some(dec!(0.11)),
             order_type: "LIMIT".to_string(),
             side: "BUY".to_string(),
             symbol: "SPOT_BTC_USDT".to_string(),
@@ -350,14 +2095,15 @@ mod tests {
             reduce_only: None,
             visible_quantity: None,
             position_side: None,
+            time_in_force: None,
         };
 
-        let sorted_query_string = Woo::generate_sorted_query_string(&order);
+        let sorted_query_string = Woo::generate_sorted_query_string(&order).unwrap();
 
         let signature = Woo::generate_hmac_sha256_signature(
             sorted_query_string,
             1578565539808,
-            "QHKRXHPAW1MC9YGZMAT8YDJG2HPR".to_string(),
+            "QHKRXHPAW1MC9YGZMAT8YDJG2HPR",
         );
 
         assert_eq!(
@@ -366,11 +2112,57 @@ mod tests {
         );
     }
 
+    #[test]
+    fn reduce_only_is_signed_as_lowercase_bool() {
+        let order = WooOrder::builder("PERP_BTC_USDT", "SELL", "LIMIT")
+            .price(dec!(9000))
+            .size(OrderSize::Quantity(dec!(0.11)))
+            .reduce_only(true)
+            .build();
+
+        let sorted_query_string = Woo::generate_sorted_query_string(&order).unwrap();
+        assert_eq!(
+            sorted_query_string,
+            "order_price=9000&order_quantity=0.11&order_type=LIMIT&reduce_only=true&side=SELL&symbol=PERP_BTC_USDT"
+        );
+
+        let signature = Woo::generate_hmac_sha256_signature(
+            sorted_query_string,
+            1578565539808,
+            "QHKRXHPAW1MC9YGZMAT8YDJG2HPR",
+        );
+        assert_eq!(
+            signature,
+            "f9e3a10508374ee8b3c6f80bcb099291a0857280db46bec1ef6cdae4c27d0cdf"
+        );
+    }
+
+    #[test]
+    fn decode_response_surfaces_api_errors() {
+        let err = Woo::decode_response::<CancelOrderRes>(
+            r#"{"success":false,"code":-1006,"message":"Your order and symbol are not valid."}"#,
+            None,
+        )
+        .unwrap_err();
+
+        match err {
+            WooError::Api(api_error) => {
+                assert_eq!(api_error.code, -1006);
+                assert_eq!(api_error.message, "Your order and symbol are not valid.");
+            }
+            other => panic!("expected an api error, got {other:?}"),
+        }
+
+        let res: CancelOrderRes =
+            Woo::decode_response(r#"{"success":true,"status":"CANCEL_SENT"}"#, None).unwrap();
+        assert!(res.success);
+    }
+
     #[tokio::test]
     async fn test_proxy() {
-        dotenv().ok();
+        env::load_dotenv();
 
-        let proxy_url: Url = Url::parse(PROXY_URL).unwrap();
+        let proxy_url: Url = Url::parse(&dotenv::var("PROXY_URL").unwrap()).unwrap();
 
         let proxy_username = dotenv::var("PROXY_USERNAME").unwrap();
         let proxy_password = dotenv::var("PROXY_PASSWORD").unwrap();
@@ -396,6 +2188,6 @@ mod tests {
         }
 
         let ip: Ip = res.json().await.unwrap();
-        assert_eq!(ip.origin, PROXY_IP);
+        assert_eq!(ip.origin, dotenv::var("PROXY_IP").unwrap());
     }
 }
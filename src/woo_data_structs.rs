@@ -1,48 +1,744 @@
+use crate::account_mode::AccountMode;
+use crate::error::{WooApiError, WooError, WooErrorCode};
+use crate::serde_helpers::{
+    decimal_string, deserialize_flexible_bool, deserialize_flexible_bool_opt,
+    deserialize_woo_timestamp, string_or_number,
+};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::de::{DeserializeOwned, Deserializer, Error as _};
+use serde::ser::Serializer;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
 
-#[serde_with::skip_serializing_none]
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct WooOrder {
     pub symbol: String,
-    pub client_order_id: Option<u32>,
+    pub client_order_id: Option<ClientOrderId>,
     pub order_tag: Option<String>,
     pub order_type: String,
-    pub order_price: Option<f64>,
-    pub order_quantity: Option<f64>,
-    pub order_amount: Option<f64>,
+    pub order_price: Option<Decimal>,
+    pub order_quantity: Option<Decimal>,
+    pub order_amount: Option<Decimal>,
     pub reduce_only: Option<bool>,
-    pub visible_quantity: Option<f64>,
+    pub visible_quantity: Option<Decimal>,
     pub side: String,
     pub position_side: Option<String>,
+    /// How long a `LIMIT` order rests, folded into the `order_type` sent, see
+    /// [`WooOrder::wire_order_type`].
+    pub time_in_force: Option<TimeInForce>,
+}
+
+/// Sent with the `order_type` from [`WooOrder::wire_order_type`], failing to
+/// encode if the time in force doesn't fit the order type.
+impl Serialize for WooOrder {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[serde_with::skip_serializing_none]
+        #[derive(Serialize)]
+        struct Wire<'a> {
+            symbol: &'a str,
+            client_order_id: Option<&'a ClientOrderId>,
+            order_tag: Option<&'a str>,
+            order_type: &'a str,
+            #[serde(serialize_with = "decimal_string::serialize_option")]
+            order_price: Option<Decimal>,
+            #[serde(serialize_with = "decimal_string::serialize_option")]
+            order_quantity: Option<Decimal>,
+            #[serde(serialize_with = "decimal_string::serialize_option")]
+            order_amount: Option<Decimal>,
+            reduce_only: Option<bool>,
+            #[serde(serialize_with = "decimal_string::serialize_option")]
+            visible_quantity: Option<Decimal>,
+            side: &'a str,
+            position_side: Option<&'a str>,
+        }
+
+        Wire {
+            symbol: &self.symbol,
+            client_order_id: self.client_order_id.as_ref(),
+            order_tag: self.order_tag.as_deref(),
+            order_type: self
+                .wire_order_type()
+                .map_err(<S::Error as serde::ser::Error>::custom)?,
+            order_price: self.order_price,
+            order_quantity: self.order_quantity,
+            order_amount: self.order_amount,
+            reduce_only: self.reduce_only,
+            visible_quantity: self.visible_quantity,
+            side: &self.side,
+            position_side: self.position_side.as_deref(),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// How long an order stays on the book. WOO has no field for it and encodes
+/// it in the `order_type` instead.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TimeInForce {
+    /// Good till cancelled, the default of `LIMIT` orders.
+    Gtc,
+    /// Immediate or cancel, sent as an `IOC` order.
+    Ioc,
+    /// Fill or kill, sent as a `FOK` order.
+    Fok,
+}
+
+impl TimeInForce {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TimeInForce::Gtc => "GTC",
+            TimeInForce::Ioc => "IOC",
+            TimeInForce::Fok => "FOK",
+        }
+    }
+}
+
+/// The `order_type`s WOO accepts, see <https://docs.woo.org/#send-order>.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OrderType {
+    Limit,
+    Market,
+    /// Immediate or cancel, whatever doesn't fill at once is cancelled.
+    Ioc,
+    /// Fill or kill, fills completely at once or not at all.
+    Fok,
+    /// Cancelled instead of taking liquidity.
+    PostOnly,
+    /// Priced by the exchange at the best ask when accepted.
+    Ask,
+    /// Priced by the exchange at the best bid when accepted.
+    Bid,
+}
+
+impl OrderType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OrderType::Limit => "LIMIT",
+            OrderType::Market => "MARKET",
+            OrderType::Ioc => "IOC",
+            OrderType::Fok => "FOK",
+            OrderType::PostOnly => "POST_ONLY",
+            OrderType::Ask => "ASK",
+            OrderType::Bid => "BID",
+        }
+    }
+
+    /// Whether orders of this type carry an `order_price`; the others are
+    /// priced by the exchange and reject one.
+    pub fn takes_price(self) -> bool {
+        !matches!(self, OrderType::Market | OrderType::Ask | OrderType::Bid)
+    }
+}
+
+impl std::str::FromStr for OrderType {
+    type Err = WooError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "LIMIT" => OrderType::Limit,
+            "MARKET" => OrderType::Market,
+            "IOC" => OrderType::Ioc,
+            "FOK" => OrderType::Fok,
+            "POST_ONLY" => OrderType::PostOnly,
+            "ASK" => OrderType::Ask,
+            "BID" => OrderType::Bid,
+            _ => return Err(WooError::InvalidOrder(format!("unknown order_type {s:?}"))),
+        })
+    }
+}
+
+/// How big an order is: in base currency (`order_quantity`) or in quote
+/// currency (`order_amount`). WOO takes exactly one of the two.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderSize {
+    Quantity(Decimal),
+    Amount(Decimal),
 }
 
-#[derive(Serialize)]
+impl WooOrder {
+    pub fn builder(
+        symbol: impl Into<String>,
+        side: impl Into<String>,
+        order_type: impl Into<String>,
+    ) -> WooOrderBuilder {
+        WooOrderBuilder {
+            order: WooOrder {
+                symbol: symbol.into(),
+                client_order_id: None,
+                order_tag: None,
+                order_type: order_type.into(),
+                order_price: None,
+                order_quantity: None,
+                order_amount: None,
+                reduce_only: None,
+                visible_quantity: None,
+                side: side.into(),
+                position_side: None,
+                time_in_force: None,
+            },
+        }
+    }
+
+    /// A limit order showing only `visible_quantity` of `total_quantity` on the
+    /// book. Lot size alignment is checked by [`SymbolInfo::validate`].
+    pub fn iceberg(
+        symbol: impl Into<String>,
+        side: impl Into<String>,
+        price: Decimal,
+        total_quantity: Decimal,
+        visible_quantity: Decimal,
+    ) -> Result<WooOrder, WooError> {
+        if visible_quantity <= Decimal::ZERO || visible_quantity > total_quantity {
+            return Err(WooError::InvalidOrder(format!(
+                "visible quantity {visible_quantity} must be in (0, {total_quantity}]"
+            )));
+        }
+
+        Ok(WooOrder::builder(symbol, side, "LIMIT")
+            .price(price)
+            .size(OrderSize::Quantity(total_quantity))
+            .visible_quantity(visible_quantity)
+            .build())
+    }
+
+    /// A limit order for `quantity` at `price`.
+    pub fn limit(
+        symbol: impl Into<String>,
+        side: impl Into<String>,
+        price: Decimal,
+        quantity: Decimal,
+    ) -> WooOrder {
+        WooOrder::priced(symbol, side, OrderType::Limit, price, quantity)
+    }
+
+    /// A market order sized in base currency, or with [`OrderSize::Amount`]
+    /// in quote currency, which WOO only takes for spot buys.
+    pub fn market(symbol: impl Into<String>, side: impl Into<String>, size: OrderSize) -> WooOrder {
+        WooOrder::builder(symbol, side, OrderType::Market.as_str())
+            .size(size)
+            .build()
+    }
+
+    /// A spot market buy spending `amount` of quote currency, e.g. 100 USDT
+    /// rather than 0.01 BTC.
+    pub fn market_by_amount(symbol: impl Into<String>, amount: Decimal) -> WooOrder {
+        WooOrder::market(symbol, Side::Buy.as_str(), OrderSize::Amount(amount))
+    }
+
+    /// An immediate-or-cancel order for `quantity` at `price` or better.
+    pub fn ioc(
+        symbol: impl Into<String>,
+        side: impl Into<String>,
+        price: Decimal,
+        quantity: Decimal,
+    ) -> WooOrder {
+        WooOrder::priced(symbol, side, OrderType::Ioc, price, quantity)
+    }
+
+    /// A fill-or-kill order for `quantity` at `price` or better.
+    pub fn fok(
+        symbol: impl Into<String>,
+        side: impl Into<String>,
+        price: Decimal,
+        quantity: Decimal,
+    ) -> WooOrder {
+        WooOrder::priced(symbol, side, OrderType::Fok, price, quantity)
+    }
+
+    /// A limit order that is cancelled rather than taking liquidity.
+    pub fn post_only(
+        symbol: impl Into<String>,
+        side: impl Into<String>,
+        price: Decimal,
+        quantity: Decimal,
+    ) -> WooOrder {
+        WooOrder::priced(symbol, side, OrderType::PostOnly, price, quantity)
+    }
+
+    /// A buy joining the best bid, priced by the exchange when accepted.
+    pub fn pegged_bid(symbol: impl Into<String>, quantity: Decimal) -> WooOrder {
+        WooOrder::builder(symbol, "BUY", OrderType::Bid.as_str())
+            .size(OrderSize::Quantity(quantity))
+            .build()
+    }
+
+    /// A sell joining the best ask, priced by the exchange when accepted.
+    pub fn pegged_ask(symbol: impl Into<String>, quantity: Decimal) -> WooOrder {
+        WooOrder::builder(symbol, "SELL", OrderType::Ask.as_str())
+            .size(OrderSize::Quantity(quantity))
+            .build()
+    }
+
+    /// A priced order on `info.symbol` from prices and quantities kept as
+    /// `f64`. Each float is taken at its shortest decimal form, so `0.1` is
+    /// `0.1`, and must be a multiple of the symbol's tick; one that only is
+    /// after discarding float rounding noise, like `0.1 + 0.2`, is handled
+    /// as `noise` says.
+    pub fn from_f64_checked(
+        info: &SymbolInfo,
+        side: impl Into<String>,
+        order_type: OrderType,
+        price: f64,
+        quantity: f64,
+        noise: FloatNoise,
+    ) -> Result<WooOrder, WooError> {
+        let price = f64_on_tick(info, "price", price, info.quote_tick, noise)?;
+        let quantity = f64_on_tick(info, "quantity", quantity, info.base_tick, noise)?;
+
+        Ok(WooOrder::priced(
+            &info.symbol,
+            side,
+            order_type,
+            price,
+            quantity,
+        ))
+    }
+
+    fn priced(
+        symbol: impl Into<String>,
+        side: impl Into<String>,
+        order_type: OrderType,
+        price: Decimal,
+        quantity: Decimal,
+    ) -> WooOrder {
+        WooOrder::builder(symbol, side, order_type.as_str())
+            .price(price)
+            .size(OrderSize::Quantity(quantity))
+            .build()
+    }
+
+    /// Checks that the fields set fit the order type: exchange-priced types
+    /// (`MARKET`, `ASK`, `BID`) take no price and, when buying spot, may be
+    /// sized in quote currency, the others need a price and a quantity.
+    pub fn validate_type(&self) -> Result<OrderType, WooError> {
+        if let Some(client_order_id) = &self.client_order_id {
+            client_order_id.validate()?;
+        }
+        let order_type: OrderType = self.wire_order_type()?.parse()?;
+        let invalid = |detail: &str| {
+            Err(WooError::InvalidOrder(format!(
+                "{} order {detail}",
+                order_type.as_str()
+            )))
+        };
+
+        match (order_type.takes_price(), self.order_price) {
+            (true, None) => return invalid("requires order_price"),
+            (false, Some(_)) => return invalid("must not set order_price"),
+            _ => {}
+        }
+        match self.size() {
+            None => return invalid("needs exactly one of order_quantity and order_amount"),
+            Some(OrderSize::Amount(_)) if order_type.takes_price() => {
+                return invalid("must not set order_amount, size it with order_quantity")
+            }
+            Some(OrderSize::Amount(_))
+                if self.side != Side::Buy.as_str() || !self.symbol.starts_with("SPOT_") =>
+            {
+                return invalid("can only be sized with order_amount when buying SPOT_ symbols")
+            }
+            Some(_) => {}
+        }
+
+        Ok(order_type)
+    }
+
+    /// Checks `reduce_only` against the symbol and, for accounts in hedge
+    /// mode, against `position_side`: only perpetuals have positions to
+    /// reduce, and in hedge mode the order must name the position it closes.
+    pub fn validate_reduce_only(&self, hedge_mode: bool) -> Result<(), WooError> {
+        if self.reduce_only != Some(true) {
+            return Ok(());
+        }
+        let invalid = |detail: String| Err(WooError::InvalidOrder(format!("reduce_only {detail}")));
+
+        if !self.symbol.starts_with("PERP_") {
+            return invalid(format!(
+                "is only allowed on PERP_ symbols, not {}",
+                self.symbol
+            ));
+        }
+        if !hedge_mode {
+            return Ok(());
+        }
+
+        match (self.position_side.as_deref(), self.side.as_str()) {
+            (Some("LONG"), "SELL") | (Some("SHORT"), "BUY") => Ok(()),
+            (None, _) => invalid("in hedge mode requires position_side LONG or SHORT".to_string()),
+            (Some(position_side), side) => invalid(format!(
+                "{side} can not reduce a {position_side} position_side"
+            )),
+        }
+    }
+
+    /// The `order_type` sent to WOO: `order_type` with `time_in_force`
+    /// folded in, e.g. `LIMIT` and [`TimeInForce::Ioc`] become `IOC`. A time
+    /// in force only fits `LIMIT` orders, or the type it already names.
+    pub fn wire_order_type(&self) -> Result<&str, WooError> {
+        let Some(time_in_force) = self.time_in_force else {
+            return Ok(&self.order_type);
+        };
+
+        let order_type = match (self.order_type.as_str(), time_in_force) {
+            ("LIMIT" | "POST_ONLY", TimeInForce::Gtc) => return Ok(&self.order_type),
+            ("LIMIT" | "IOC", TimeInForce::Ioc) => OrderType::Ioc,
+            ("LIMIT" | "FOK", TimeInForce::Fok) => OrderType::Fok,
+            (order_type, time_in_force) => {
+                return Err(WooError::InvalidOrder(format!(
+                    "{order_type} order can not have time in force {}",
+                    time_in_force.as_str()
+                )))
+            }
+        };
+        Ok(order_type.as_str())
+    }
+
+    /// The order's size, `None` if neither or both size fields are set.
+    pub fn size(&self) -> Option<OrderSize> {
+        match (self.order_quantity, self.order_amount) {
+            (Some(quantity), None) => Some(OrderSize::Quantity(quantity)),
+            (None, Some(amount)) => Some(OrderSize::Amount(amount)),
+            _ => None,
+        }
+    }
+}
+
+/// Builds a [`WooOrder`] whose size is set through [`OrderSize`], so
+/// `order_quantity` and `order_amount` can't both be set.
+#[derive(Debug, Clone)]
+pub struct WooOrderBuilder {
+    order: WooOrder,
+}
+
+impl WooOrderBuilder {
+    pub fn size(mut self, size: OrderSize) -> Self {
+        let (quantity, amount) = match size {
+            OrderSize::Quantity(quantity) => (Some(quantity), None),
+            OrderSize::Amount(amount) => (None, Some(amount)),
+        };
+        self.order.order_quantity = quantity;
+        self.order.order_amount = amount;
+        self
+    }
+
+    pub fn price(mut self, price: Decimal) -> Self {
+        self.order.order_price = Some(price);
+        self
+    }
+
+    pub fn client_order_id(mut self, client_order_id: impl Into<ClientOrderId>) -> Self {
+        self.order.client_order_id = Some(client_order_id.into());
+        self
+    }
+
+    pub fn order_tag(mut self, order_tag: impl Into<String>) -> Self {
+        self.order.order_tag = Some(order_tag.into());
+        self
+    }
+
+    pub fn reduce_only(mut self, reduce_only: bool) -> Self {
+        self.order.reduce_only = Some(reduce_only);
+        self
+    }
+
+    pub fn visible_quantity(mut self, visible_quantity: Decimal) -> Self {
+        self.order.visible_quantity = Some(visible_quantity);
+        self
+    }
+
+    pub fn position_side(mut self, position_side: impl Into<String>) -> Self {
+        self.order.position_side = Some(position_side.into());
+        self
+    }
+
+    pub fn time_in_force(mut self, time_in_force: TimeInForce) -> Self {
+        self.order.time_in_force = Some(time_in_force);
+        self
+    }
+
+    pub fn build(self) -> WooOrder {
+        self.order
+    }
+}
+
+/// Exchange-assigned id of an order.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(transparent)]
+pub struct OrderId(pub u64);
+
+/// Longest text client order id WOO takes.
+pub const MAX_CLIENT_ORDER_ID_LEN: usize = 36;
+
+/// Caller-assigned id of an order, see [`WooOrderBuilder::client_order_id`].
+/// WOO reports `0` for orders placed without one.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(untagged)]
+pub enum ClientOrderId {
+    Numeric(u32),
+    /// Sent as a string, e.g. a UUID, to map orders to ids used elsewhere
+    /// without a lookup table. Only `v3` endpoints, like algo orders, take
+    /// them; `v1` ones refuse them before sending. See [`ClientOrderId::text`]
+    /// for what is taken.
+    Text(String),
+}
+
+impl ClientOrderId {
+    /// A text id of at most [`MAX_CLIENT_ORDER_ID_LEN`] ascii letters, digits,
+    /// `-` and `_`. A number, e.g. `"42"`, is the numeric id it reads back as.
+    pub fn text(client_order_id: impl Into<String>) -> Result<Self, WooError> {
+        let client_order_id = client_order_id.into();
+        if let Ok(numeric) = client_order_id.parse::<u32>() {
+            if numeric.to_string() == client_order_id {
+                return Ok(ClientOrderId::Numeric(numeric));
+            }
+        }
+
+        let client_order_id = ClientOrderId::Text(client_order_id);
+        client_order_id.validate()?;
+        Ok(client_order_id)
+    }
+
+    /// Refuses text ids, which `v1` endpoints don't take.
+    pub(crate) fn validate_v1(&self) -> Result<(), WooError> {
+        match self {
+            ClientOrderId::Numeric(_) => Ok(()),
+            ClientOrderId::Text(text) => Err(WooError::InvalidOrder(format!(
+                "client_order_id {text:?} is text, v1 endpoints only take numeric ones"
+            ))),
+        }
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), WooError> {
+        let ClientOrderId::Text(text) = self else {
+            return Ok(());
+        };
+
+        if text.is_empty() || text.len() > MAX_CLIENT_ORDER_ID_LEN {
+            return Err(WooError::InvalidOrder(format!(
+                "client_order_id {text:?} must be 1 to {MAX_CLIENT_ORDER_ID_LEN} characters long"
+            )));
+        }
+        if let Some(invalid) = text
+            .chars()
+            .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_')))
+        {
+            return Err(WooError::InvalidOrder(format!(
+                "client_order_id {text:?} must not contain {invalid:?}"
+            )));
+        }
+        // sent without quotes, it would read back as a different id
+        if text.chars().all(|c| c.is_ascii_digit()) {
+            return Err(WooError::InvalidOrder(format!(
+                "client_order_id {text:?} is all digits, use ClientOrderId::Numeric"
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for ClientOrderId {
+    fn default() -> Self {
+        ClientOrderId::Numeric(0)
+    }
+}
+
+impl fmt::Display for OrderId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl fmt::Display for ClientOrderId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientOrderId::Numeric(client_order_id) => client_order_id.fmt(f),
+            ClientOrderId::Text(client_order_id) => client_order_id.fmt(f),
+        }
+    }
+}
+
+impl From<u64> for OrderId {
+    fn from(order_id: u64) -> Self {
+        OrderId(order_id)
+    }
+}
+
+impl From<u32> for ClientOrderId {
+    fn from(client_order_id: u32) -> Self {
+        ClientOrderId::Numeric(client_order_id)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
 pub struct CancelOrder {
-    pub order_id: u32,
+    pub order_id: OrderId,
     pub symbol: String,
 }
 
-#[derive(Deserialize)]
-pub struct CancelOrderRes {
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct CancelOrderByClientId {
+    pub client_order_id: ClientOrderId,
+    pub symbol: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CancelAllOrders {
+    pub symbol: String,
+}
+
+/// The `{ "success": ..., "data": ..., "timestamp": ... }` wrapper around WOO
+/// responses. `T` is read from `data` when the response has one, and from the
+/// whole object when the payload sits at the top level as on most `v1`
+/// endpoints, so flattened structs may keep their own `success` field.
+#[derive(Debug, Clone)]
+pub struct WooEnvelope<T> {
     pub success: bool,
+    pub data: T,
+    pub timestamp: Option<DateTime<Utc>>,
+}
+
+impl<T> WooEnvelope<T> {
+    /// `data`, or an error if WOO reported `success: false`. Rejections that
+    /// carry an error code never get this far, see [`WooError::Api`].
+    pub fn into_data(self) -> Result<T, WooError> {
+        if !self.success {
+            return Err(WooError::Api(WooApiError {
+                code: WooErrorCode::UnknownError.code(),
+                message: "response reported success: false".to_string(),
+                request_id: None,
+            }));
+        }
+
+        Ok(self.data)
+    }
+}
+
+impl<'de, T: DeserializeOwned> Deserialize<'de> for WooEnvelope<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut value = serde_json::Value::deserialize(deserializer)?;
+        let object = value
+            .as_object_mut()
+            .ok_or_else(|| D::Error::custom("expected a json object"))?;
+
+        let success = match object.get("success") {
+            Some(success) => deserialize_flexible_bool(success).map_err(D::Error::custom)?,
+            None => return Err(D::Error::missing_field("success")),
+        };
+        let timestamp = object.get("timestamp").and_then(server_timestamp);
+        let data = object.remove("data").unwrap_or(value);
+
+        Ok(WooEnvelope {
+            success,
+            data: T::deserialize(data).map_err(D::Error::custom)?,
+            timestamp,
+        })
+    }
+}
+
+/// Reads the `timestamp` of a response, in seconds on most endpoints.
+pub(crate) fn server_timestamp(timestamp: &serde_json::Value) -> Option<DateTime<Utc>> {
+    match timestamp {
+        // the newer endpoints send milliseconds
+        serde_json::Value::Number(number)
+            if number.as_i64().is_some_and(|ms| ms > 100_000_000_000) =>
+        {
+            number.as_i64().and_then(DateTime::from_timestamp_millis)
+        }
+        timestamp => deserialize_woo_timestamp(timestamp).ok(),
+    }
+}
+
+/// An optional [`server_timestamp`], in seconds or milliseconds.
+fn deserialize_server_timestamp<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<serde_json::Value>::deserialize(deserializer)? {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(timestamp) => server_timestamp(&timestamp)
+            .map(Some)
+            .ok_or_else(|| D::Error::custom(format!("invalid timestamp {timestamp}"))),
+    }
+}
+
+// https://docs.woo.org/#edit-order
+#[serde_with::skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EditOrder {
+    pub price: Option<Decimal>,
+    pub quantity: Option<Decimal>,
+}
+
+/// `data` of `DELETE v3/orders/pending`.
+#[derive(Debug, Deserialize)]
+pub struct CancelAllPendingRes {
+    pub status: CancelStatus,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EditOrderRes {
+    /// `EDIT_SENT` once accepted.
     pub status: String,
 }
-#[derive(Deserialize)]
+
+#[derive(Debug, Serialize)]
+pub struct CancelAllAfter {
+    /// Milliseconds, 0 to disarm.
+    pub trigger_after: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CancelAllAfterRes {
+    /// When WOO will cancel everything unless re-armed, in milliseconds since
+    /// the epoch; 0 once disarmed.
+    pub expected_trigger_time: i64,
+}
+
+/// What WOO did with a cancel request. The cancel is only sent to the
+/// matching engine at this point, the order may still fill meanwhile.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CancelStatus {
+    CancelSent,
+    CancelAllSent,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CancelOrderRes {
+    #[serde(deserialize_with = "deserialize_flexible_bool")]
+    pub success: bool,
+    pub status: CancelStatus,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct SendOrderRes {
+    #[serde(deserialize_with = "deserialize_flexible_bool")]
     pub success: bool,
-    pub timestamp: String,
-    pub order_id: u32,
+    #[serde(deserialize_with = "deserialize_woo_timestamp")]
+    pub timestamp: DateTime<Utc>,
+    pub order_id: OrderId,
     pub order_type: String,
-    pub client_order_id: u32,
+    pub client_order_id: ClientOrderId,
+    #[serde(default, deserialize_with = "string_or_number::deserialize_option")]
     pub order_price: Option<f64>,
+    #[serde(default, deserialize_with = "string_or_number::deserialize_option")]
     pub order_quantity: Option<f64>,
+    #[serde(default, deserialize_with = "string_or_number::deserialize_option")]
     pub order_amount: Option<f64>,
+    #[serde(default, deserialize_with = "deserialize_flexible_bool_opt")]
     pub reduce_only: Option<bool>,
 }
 
 // https://docs.woo.org/#get-orders
 #[serde_with::skip_serializing_none]
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct GetOrder {
     pub symbol: Option<String>,
     pub side: Option<String>,
@@ -56,42 +752,1411 @@ pub struct GetOrder {
     pub page: Option<u32>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct GetOrderRes {
     pub success: bool,
     pub meta: Meta,
     pub rows: Vec<Row>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl GetOrderRes {
+    pub fn rows(&self) -> &[Row] {
+        &self.rows
+    }
+}
+
+impl IntoIterator for GetOrderRes {
+    type Item = Row;
+    type IntoIter = std::vec::IntoIter<Row>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.rows.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a GetOrderRes {
+    type Item = &'a Row;
+    type IntoIter = std::slice::Iter<'a, Row>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.rows.iter()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Meta {
     pub total: u32,
     pub records_per_page: u32,
     pub current_page: u32,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Row {
-    side: String,
-    status: String,
-    symbol: String,
-    client_order_id: Option<u32>,
-    reduce_only: bool,
-    order_id: u32,
-    order_tag: String,
-    r#type: String,
-    price: f64,
-    quantity: f64,
-    amount: Option<f64>,
-    visible: f64,
-    executed: f64,
-    total_fee: f64,
-    fee_asset: Option<String>,
-    total_rebate: Option<f64>,
-    rebate_asset: Option<String>,
-    created_time: String,
-    updated_time: String,
-    average_executed_price: Option<f64>,
-    position_side: String,
-    realized_pnl: Option<f64>,
+    pub side: String,
+    pub status: String,
+    pub symbol: String,
+    pub client_order_id: Option<ClientOrderId>,
+    #[serde(deserialize_with = "deserialize_flexible_bool")]
+    pub reduce_only: bool,
+    pub order_id: OrderId,
+    pub order_tag: String,
+    pub r#type: String,
+    #[serde(deserialize_with = "string_or_number::deserialize")]
+    pub price: Decimal,
+    #[serde(deserialize_with = "string_or_number::deserialize")]
+    pub quantity: Decimal,
+    #[serde(default, deserialize_with = "string_or_number::deserialize_option")]
+    pub amount: Option<Decimal>,
+    #[serde(deserialize_with = "string_or_number::deserialize")]
+    pub visible: Decimal,
+    #[serde(deserialize_with = "string_or_number::deserialize")]
+    pub executed: Decimal,
+    #[serde(deserialize_with = "string_or_number::deserialize")]
+    pub total_fee: Decimal,
+    pub fee_asset: Option<String>,
+    #[serde(default, deserialize_with = "string_or_number::deserialize_option")]
+    pub total_rebate: Option<Decimal>,
+    pub rebate_asset: Option<String>,
+    #[serde(deserialize_with = "deserialize_woo_timestamp")]
+    pub created_time: DateTime<Utc>,
+    #[serde(deserialize_with = "deserialize_woo_timestamp")]
+    pub updated_time: DateTime<Utc>,
+    #[serde(default, deserialize_with = "string_or_number::deserialize_option")]
+    pub average_executed_price: Option<Decimal>,
+    pub position_side: String,
+    #[serde(default, deserialize_with = "string_or_number::deserialize_option")]
+    pub realized_pnl: Option<Decimal>,
+}
+
+impl Row {
+    /// How `v1/orders` lists the order of `detail`, for the exchanges kept in
+    /// memory.
+    pub(crate) fn from_detail(detail: &OrderDetail, reduce_only: bool) -> Row {
+        Row {
+            side: detail.side.clone(),
+            status: detail.status.as_str().to_string(),
+            symbol: detail.symbol.clone(),
+            client_order_id: detail.client_order_id.clone(),
+            reduce_only,
+            order_id: detail.order_id,
+            order_tag: detail
+                .order_tag
+                .clone()
+                .unwrap_or_else(|| "default".to_string()),
+            r#type: detail.r#type.clone(),
+            price: detail.price.unwrap_or_default(),
+            quantity: detail.quantity.unwrap_or_default(),
+            amount: detail.amount,
+            visible: detail.quantity.unwrap_or_default(),
+            executed: detail.executed,
+            total_fee: detail.total_fee,
+            fee_asset: detail.fee_asset.clone(),
+            total_rebate: None,
+            rebate_asset: None,
+            created_time: detail.created_time,
+            updated_time: detail.created_time,
+            average_executed_price: detail.average_executed_price,
+            position_side: "BOTH".to_string(),
+            realized_pnl: None,
+        }
+    }
+
+    /// `status` as an [`OrderStatus`], [`OrderStatus::Unknown`] for statuses
+    /// this crate doesn't know.
+    pub fn order_status(&self) -> OrderStatus {
+        OrderStatus::deserialize(
+            serde::de::value::StrDeserializer::<serde::de::value::Error>::new(&self.status),
+        )
+        .unwrap_or(OrderStatus::Unknown)
+    }
+
+    /// Quantity still resting on the book.
+    pub fn remaining(&self) -> Decimal {
+        (self.quantity - self.executed).max(Decimal::ZERO)
+    }
+
+    /// Executed share of the quantity, between 0 and 1. Zero for orders with
+    /// no quantity, e.g. market orders sized by amount.
+    pub fn fill_ratio(&self) -> Decimal {
+        if self.quantity.is_zero() {
+            return Decimal::ZERO;
+        }
+        (self.executed / self.quantity).min(Decimal::ONE)
+    }
+
+    pub fn is_fully_filled(&self) -> bool {
+        !self.quantity.is_zero() && self.remaining().is_zero()
+    }
+
+    /// An order equivalent to this one for its [`Row::remaining`] quantity,
+    /// e.g. to place it again after cancelling it. The client order id isn't
+    /// carried over, WOO rejects reusing one. Fails for orders filled or
+    /// rejected and orders with nothing left.
+    pub fn as_new_order(&self) -> anyhow::Result<WooOrder> {
+        if matches!(
+            self.order_status(),
+            OrderStatus::Filled | OrderStatus::Rejected | OrderStatus::Completed
+        ) {
+            anyhow::bail!(
+                "order {} is {}, nothing to resubmit",
+                self.order_id,
+                self.status
+            );
+        }
+        let remaining = self.remaining();
+        if remaining.is_zero() {
+            anyhow::bail!("order {} has no quantity left to resubmit", self.order_id);
+        }
+
+        let order_type: OrderType = self.r#type.parse()?;
+        let mut order = WooOrder::builder(&self.symbol, &self.side, order_type.as_str())
+            .size(OrderSize::Quantity(remaining));
+        if order_type.takes_price() {
+            order = order.price(self.price);
+        }
+        if self.reduce_only {
+            order = order.reduce_only(true);
+        }
+        if self.position_side != "BOTH" {
+            order = order.position_side(&self.position_side);
+        }
+        let mut order = order.build();
+        if self.order_tag != "default" {
+            order.order_tag = Some(self.order_tag.clone());
+        }
+        // iceberg orders show no more than they used to
+        if self.visible < self.quantity {
+            order.visible_quantity = Some(self.visible.min(remaining));
+        }
+
+        Ok(order)
+    }
+}
+
+// https://docs.woo.org/#get-trades
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct GetTrades {
+    pub symbol: Option<String>,
+    pub order_tag: Option<String>,
+    /// Milliseconds since the epoch.
+    pub start_t: Option<i64>,
+    pub end_t: Option<i64>,
+    pub page: Option<u32>,
+    pub size: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetTradesRes {
+    pub success: bool,
+    pub meta: Meta,
+    pub rows: Vec<Trade>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Trade {
+    pub id: u64,
+    pub symbol: String,
+    pub order_id: OrderId,
+    pub side: String,
+    pub executed_price: Decimal,
+    pub executed_quantity: Decimal,
+    pub fee: Decimal,
+    pub fee_asset: String,
+    pub executed_timestamp: String,
+    #[serde(deserialize_with = "deserialize_flexible_bool")]
+    pub is_maker: bool,
+}
+
+/// Lifecycle state of an order as WOO reports it.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OrderStatus {
+    New,
+    PartialFilled,
+    Filled,
+    Cancelled,
+    Rejected,
+    Incomplete,
+    Completed,
+    #[serde(other)]
+    Unknown,
+}
+
+impl OrderStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OrderStatus::New => "NEW",
+            OrderStatus::PartialFilled => "PARTIAL_FILLED",
+            OrderStatus::Filled => "FILLED",
+            OrderStatus::Cancelled => "CANCELLED",
+            OrderStatus::Rejected => "REJECTED",
+            OrderStatus::Incomplete => "INCOMPLETE",
+            OrderStatus::Completed => "COMPLETED",
+            OrderStatus::Unknown => "UNKNOWN",
+        }
+    }
+
+    /// Whether the order can no longer change. `PartialFilled` is not: the
+    /// remainder is still resting on the book.
+    pub fn is_terminal(self) -> bool {
+        matches!(
+            self,
+            OrderStatus::Filled | OrderStatus::Cancelled | OrderStatus::Rejected
+        )
+    }
+}
+
+// https://docs.woo.org/#get-order
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OrderDetail {
+    pub symbol: String,
+    pub side: String,
+    pub status: OrderStatus,
+    pub order_id: OrderId,
+    pub client_order_id: Option<ClientOrderId>,
+    pub order_tag: Option<String>,
+    pub r#type: String,
+    #[serde(default, deserialize_with = "string_or_number::deserialize_option")]
+    pub price: Option<Decimal>,
+    #[serde(default, deserialize_with = "string_or_number::deserialize_option")]
+    pub quantity: Option<Decimal>,
+    #[serde(default, deserialize_with = "string_or_number::deserialize_option")]
+    pub amount: Option<Decimal>,
+    #[serde(deserialize_with = "string_or_number::deserialize")]
+    pub executed: Decimal,
+    #[serde(default, deserialize_with = "string_or_number::deserialize_option")]
+    pub average_executed_price: Option<Decimal>,
+    #[serde(deserialize_with = "string_or_number::deserialize")]
+    pub total_fee: Decimal,
+    pub fee_asset: Option<String>,
+    #[serde(deserialize_with = "deserialize_woo_timestamp")]
+    pub created_time: DateTime<Utc>,
+}
+
+// https://docs.woo.org/#get-predicted-funding-rate-for-one-market-public
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FundingRate {
+    pub symbol: String,
+    #[serde(deserialize_with = "string_or_number::deserialize")]
+    pub est_funding_rate: f64,
+    pub est_funding_rate_timestamp: i64,
+    #[serde(deserialize_with = "string_or_number::deserialize")]
+    pub last_funding_rate: f64,
+    pub last_funding_rate_timestamp: i64,
+    /// Milliseconds since the epoch.
+    pub next_funding_time: i64,
+    /// Server time the response was produced at, in milliseconds.
+    pub timestamp: i64,
+}
+
+/// The predicted funding rate and how long until it is applied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FundingCountdown {
+    pub symbol: String,
+    pub est_funding_rate: f64,
+    pub next_funding_time: DateTime<Utc>,
+    /// Zero once `next_funding_time` has passed.
+    pub seconds_until_next: i64,
+}
+
+impl FundingCountdown {
+    pub fn at(rate: &FundingRate, now: DateTime<Utc>) -> Self {
+        let next_funding_time =
+            DateTime::from_timestamp_millis(rate.next_funding_time).unwrap_or(now);
+
+        Self {
+            symbol: rate.symbol.clone(),
+            est_funding_rate: rate.est_funding_rate,
+            next_funding_time,
+            seconds_until_next: (next_funding_time - now).num_seconds().max(0),
+        }
+    }
+}
+
+// https://docs.woo.org/#exchange-information
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SymbolInfo {
+    pub symbol: String,
+    pub quote_min: Decimal,
+    pub quote_max: Decimal,
+    pub quote_tick: Decimal,
+    pub base_min: Decimal,
+    pub base_max: Decimal,
+    pub base_tick: Decimal,
+    pub min_notional: Decimal,
+    pub price_range: Option<Decimal>,
+}
+
+impl SymbolInfo {
+    /// Rounds `price` down to the symbol's price tick.
+    pub fn snap_price(&self, price: Decimal) -> Decimal {
+        snap_down(price, self.quote_tick)
+    }
+
+    /// Rounds `quantity` down to the symbol's lot size.
+    pub fn snap_quantity(&self, quantity: Decimal) -> Decimal {
+        snap_down(quantity, self.base_tick)
+    }
+
+    /// Checks `order` against the tick, lot and notional filters WOO would
+    /// reject it for.
+    pub fn validate(&self, order: &WooOrder) -> Result<(), WooError> {
+        let invalid = |msg: String| Err(WooError::InvalidOrder(format!("{}: {msg}", self.symbol)));
+
+        if let Some(price) = order.order_price {
+            if price < self.quote_min || price > self.quote_max {
+                return invalid(format!(
+                    "price {price} outside [{}, {}]",
+                    self.quote_min, self.quote_max
+                ));
+            }
+            if self.snap_price(price) != price {
+                return invalid(format!(
+                    "price {price} not a multiple of {}",
+                    self.quote_tick
+                ));
+            }
+        }
+
+        if let Some(quantity) = order.order_quantity {
+            if quantity < self.base_min || quantity > self.base_max {
+                return invalid(format!(
+                    "quantity {quantity} outside [{}, {}]",
+                    self.base_min, self.base_max
+                ));
+            }
+            if self.snap_quantity(quantity) != quantity {
+                return invalid(format!(
+                    "quantity {quantity} not a multiple of {}",
+                    self.base_tick
+                ));
+            }
+            if let Some(price) = order.order_price {
+                if price * quantity < self.min_notional {
+                    return invalid(format!(
+                        "notional {} below {}",
+                        price * quantity,
+                        self.min_notional
+                    ));
+                }
+            }
+        }
+
+        if let Some(visible) = order.visible_quantity {
+            if self.snap_quantity(visible) != visible {
+                return invalid(format!(
+                    "visible quantity {visible} not a multiple of {}",
+                    self.base_tick
+                ));
+            }
+            if order
+                .order_quantity
+                .is_some_and(|quantity| visible > quantity)
+            {
+                return invalid(format!(
+                    "visible quantity {visible} above the order quantity"
+                ));
+            }
+        }
+
+        if let Some(amount) = order.order_amount {
+            if amount < self.min_notional {
+                return invalid(format!("amount {amount} below {}", self.min_notional));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// What [`WooOrder::from_f64_checked`] does with a float that lands on a tick
+/// only up to rounding noise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloatNoise {
+    /// Log a warning and use the tick it is noise around.
+    Warn,
+    /// Fail with [`WooError::InvalidOrder`].
+    Reject,
+}
+
+fn f64_on_tick(
+    info: &SymbolInfo,
+    field: &str,
+    value: f64,
+    tick: Decimal,
+    noise: FloatNoise,
+) -> Result<Decimal, WooError> {
+    let invalid = |msg: String| WooError::InvalidOrder(format!("{}: {msg}", info.symbol));
+    let decimal: Decimal = value
+        .to_string()
+        .parse()
+        .map_err(|_| invalid(format!("{field} {value} has no decimal form")))?;
+    if tick.is_zero() {
+        return Ok(decimal);
+    }
+
+    let steps = decimal / tick;
+    let whole = steps.round();
+    if steps == whole {
+        return Ok(decimal.normalize());
+    }
+    // this close to a whole number of ticks is float noise, not a value
+    // between ticks
+    if (steps - whole).abs() > Decimal::new(1, 9) {
+        return Err(invalid(format!("{field} {value} not a multiple of {tick}")));
+    }
+
+    let snapped = (whole * tick).normalize();
+    match noise {
+        FloatNoise::Warn => {
+            tracing::warn!(symbol = %info.symbol, field, value, %snapped, "f64 off tick by rounding noise");
+            Ok(snapped)
+        }
+        FloatNoise::Reject => Err(invalid(format!(
+            "{field} {value} is {snapped} plus float rounding noise"
+        ))),
+    }
+}
+
+fn snap_down(value: Decimal, tick: Decimal) -> Decimal {
+    if tick.is_zero() {
+        return value;
+    }
+    ((value / tick).floor() * tick).normalize()
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ExchangeInfoRes {
+    pub rows: Vec<SymbolInfo>,
+}
+
+// https://docs.woo.org/#available-token-public
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Token {
+    pub token: String,
+    pub fullname: String,
+    pub decimals: u32,
+    /// The token balances of this one are held in, usually itself.
+    #[serde(default)]
+    pub balance_token: Option<String>,
+    #[serde(deserialize_with = "deserialize_woo_timestamp")]
+    pub created_time: DateTime<Utc>,
+    #[serde(deserialize_with = "deserialize_woo_timestamp")]
+    pub updated_time: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TokensRes {
+    pub rows: Vec<Token>,
+}
+
+// https://docs.woo.org/#orderbook-snapshot-public
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Orderbook {
+    /// Best (lowest) ask first.
+    pub asks: Vec<BookLevel>,
+    /// Best (highest) bid first.
+    pub bids: Vec<BookLevel>,
+    pub timestamp: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BookLevel {
+    pub price: Decimal,
+    pub quantity: Decimal,
+}
+
+/// Side of an order; a `Buy` takes from the asks, a `Sell` from the bids.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+impl Side {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Side::Buy => "BUY",
+            Side::Sell => "SELL",
+        }
+    }
+}
+
+/// What a market order of some quantity would cost against a book snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VwapEstimate {
+    Filled {
+        vwap: Decimal,
+        /// Price of the deepest level touched.
+        worst_price: Decimal,
+        levels: usize,
+    },
+    /// The snapshot holds less than the quantity; `available` is all of it,
+    /// across `levels` levels. Nothing is extrapolated past the last level.
+    Exhausted { levels: usize, available: Decimal },
+    /// The quantity was zero or negative, there is nothing to fill.
+    NothingToFill,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarketImpact {
+    pub mid: Option<Decimal>,
+    pub estimate: VwapEstimate,
+}
+
+impl MarketImpact {
+    /// How far the average fill is from the mid, as a fraction of the mid.
+    /// `None` for an exhausted book or a one-sided one.
+    pub fn slippage(&self) -> Option<Decimal> {
+        match (self.estimate, self.mid) {
+            (VwapEstimate::Filled { vwap, .. }, Some(mid)) if !mid.is_zero() => {
+                Some(((vwap - mid) / mid).abs())
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Orderbook {
+    pub fn best_ask(&self) -> Option<BookLevel> {
+        self.asks.first().copied()
+    }
+
+    pub fn best_bid(&self) -> Option<BookLevel> {
+        self.bids.first().copied()
+    }
+
+    pub fn mid_price(&self) -> Option<Decimal> {
+        Some((self.best_bid()?.price + self.best_ask()?.price) / Decimal::TWO)
+    }
+
+    /// Best ask minus best bid, negative for a crossed book.
+    pub fn spread(&self) -> Option<Decimal> {
+        Some(self.best_ask()?.price - self.best_bid()?.price)
+    }
+
+    /// The levels an order on `side` takes liquidity from, best first.
+    fn levels_against(&self, side: Side) -> &[BookLevel] {
+        match side {
+            Side::Buy => &self.asks,
+            Side::Sell => &self.bids,
+        }
+    }
+
+    /// Average price a market order for `quantity` on `side` would fill at.
+    pub fn vwap_for_quantity(&self, side: Side, quantity: Decimal) -> VwapEstimate {
+        if quantity <= Decimal::ZERO {
+            return VwapEstimate::NothingToFill;
+        }
+        let levels = self.levels_against(side);
+        let mut remaining = quantity;
+        let mut notional = Decimal::ZERO;
+
+        for (index, level) in levels.iter().enumerate() {
+            let take = remaining.min(level.quantity);
+            notional += take * level.price;
+            remaining -= take;
+
+            if remaining.is_zero() {
+                return VwapEstimate::Filled {
+                    vwap: notional / quantity,
+                    worst_price: level.price,
+                    levels: index + 1,
+                };
+            }
+        }
+
+        VwapEstimate::Exhausted {
+            levels: levels.len(),
+            available: quantity - remaining,
+        }
+    }
+
+    /// Quantity an order on `side` could take without filling worse than
+    /// `price_limit`.
+    pub fn quantity_available_within(&self, side: Side, price_limit: Decimal) -> Decimal {
+        self.levels_against(side)
+            .iter()
+            .take_while(|level| match side {
+                Side::Buy => level.price <= price_limit,
+                Side::Sell => level.price >= price_limit,
+            })
+            .map(|level| level.quantity)
+            .sum()
+    }
+}
+
+// https://docs.woo.org/#get-current-holding
+#[derive(Serialize, Deserialize, Debug)]
+pub struct HoldingRes {
+    pub success: bool,
+    pub holding: HashMap<String, f64>,
+}
+
+// https://docs.woo.org/#get-all-position-info
+//
+// Unlike the rest of `v1`, positions come in camelCase and under `data`, as
+// on `v3`. The snake_case aliases read the flat responses WOO sent before.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct PositionsRes {
+    /// Only sent on flat responses, see [`WooEnvelope`] for the others.
+    #[serde(default)]
+    pub success: bool,
+    #[serde(
+        default,
+        alias = "free_collateral",
+        deserialize_with = "string_or_number::deserialize_option"
+    )]
+    pub free_collateral: Option<f64>,
+    #[serde(
+        default,
+        alias = "total_collateral",
+        deserialize_with = "string_or_number::deserialize_option"
+    )]
+    pub total_collateral: Option<f64>,
+    pub positions: Vec<Position>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct Position {
+    pub symbol: String,
+    #[serde(deserialize_with = "string_or_number::deserialize")]
+    pub holding: f64,
+    #[serde(
+        default,
+        alias = "pending_long_qty",
+        deserialize_with = "string_or_number::deserialize_option"
+    )]
+    pub pending_long_qty: Option<f64>,
+    #[serde(
+        default,
+        alias = "pending_short_qty",
+        deserialize_with = "string_or_number::deserialize_option"
+    )]
+    pub pending_short_qty: Option<f64>,
+    #[serde(
+        alias = "average_open_price",
+        deserialize_with = "string_or_number::deserialize"
+    )]
+    pub average_open_price: f64,
+    #[serde(
+        alias = "mark_price",
+        deserialize_with = "string_or_number::deserialize"
+    )]
+    pub mark_price: f64,
+    #[serde(
+        default,
+        alias = "est_liq_price",
+        deserialize_with = "string_or_number::deserialize_option"
+    )]
+    pub est_liq_price: Option<f64>,
+    /// Sent in milliseconds under `data`, in seconds on the flat shape.
+    #[serde(default, deserialize_with = "deserialize_server_timestamp")]
+    pub timestamp: Option<DateTime<Utc>>,
+}
+
+impl Position {
+    pub fn unrealized_pnl(&self) -> f64 {
+        (self.mark_price - self.average_open_price) * self.holding
+    }
+}
+
+// https://docs.woo.org/#get-account-information
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AccountInfoRes {
+    pub application: AccountInfo,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AccountInfo {
+    pub application_id: String,
+    pub account: String,
+    pub alias: Option<String>,
+    pub account_mode: Option<AccountMode>,
+    #[serde(default, deserialize_with = "string_or_number::deserialize_option")]
+    pub leverage: Option<f64>,
+    #[serde(default, deserialize_with = "string_or_number::deserialize_option")]
+    pub taker_fee_rate: Option<f64>,
+    #[serde(default, deserialize_with = "string_or_number::deserialize_option")]
+    pub maker_fee_rate: Option<f64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn quantity_size_serializes_to_order_quantity() {
+        let order = WooOrder::builder("SPOT_BTC_USDT", "BUY", "LIMIT")
+            .price(dec!(9000))
+            .size(OrderSize::Quantity(dec!(0.11)))
+            .build();
+
+        let qs = serde_qs::to_string(&order).unwrap();
+        assert!(qs.contains("order_quantity=0.11"));
+        assert!(!qs.contains("order_amount"));
+        assert_eq!(order.size(), Some(OrderSize::Quantity(dec!(0.11))));
+    }
+
+    #[test]
+    fn amount_size_serializes_to_order_amount() {
+        let order = WooOrder::builder("SPOT_BTC_USDT", "BUY", "MARKET")
+            .size(OrderSize::Quantity(dec!(0.11)))
+            .size(OrderSize::Amount(dec!(1000)))
+            .build();
+
+        let qs = serde_qs::to_string(&order).unwrap();
+        assert!(qs.contains("order_amount=1000"));
+        assert!(!qs.contains("order_quantity"));
+        assert_eq!(order.size(), Some(OrderSize::Amount(dec!(1000))));
+    }
+
+    #[test]
+    fn time_in_force_is_sent_as_the_order_type() {
+        let limit = |time_in_force| {
+            WooOrder::builder("SPOT_BTC_USDT", "BUY", "LIMIT")
+                .price(dec!(9000))
+                .size(OrderSize::Quantity(dec!(0.1)))
+                .time_in_force(time_in_force)
+                .build()
+        };
+
+        for (time_in_force, order_type) in [
+            (TimeInForce::Gtc, "LIMIT"),
+            (TimeInForce::Ioc, "IOC"),
+            (TimeInForce::Fok, "FOK"),
+        ] {
+            let order = limit(time_in_force);
+            let qs = serde_qs::to_string(&order).unwrap();
+            assert!(qs.contains(&format!("order_type={order_type}&")), "{qs}");
+            assert!(!qs.contains("time_in_force"));
+            assert_eq!(order.validate_type().unwrap().as_str(), order_type);
+        }
+
+        let mut market = WooOrder::market("SPOT_BTC_USDT", "BUY", OrderSize::Quantity(dec!(1)));
+        market.time_in_force = Some(TimeInForce::Fok);
+        assert!(market
+            .validate_type()
+            .unwrap_err()
+            .to_string()
+            .contains("MARKET order can not have time in force FOK"));
+        assert!(serde_qs::to_string(&market).is_err());
+    }
+
+    #[test]
+    fn decimals_are_signed_with_a_dot_whatever_the_locale() {
+        // std formatting never reads the locale, so there is none to switch:
+        // a comma locale is simulated by what it would have produced instead
+        let order = WooOrder::builder("SPOT_BTC_USDT", "BUY", "LIMIT")
+            .price(dec!(9000.5))
+            .size(OrderSize::Quantity(dec!(0.00000001)))
+            .visible_quantity(dec!(1000000))
+            .build();
+
+        let qs = serde_qs::to_string(&order).unwrap();
+        assert!(qs.contains("order_price=9000.5&"), "{qs}");
+        assert!(!qs.contains("9000,5") && !qs.contains("9000%2C5"), "{qs}");
+        // neither an exponent nor a thousands separator
+        assert!(qs.contains("order_quantity=0.00000001&"), "{qs}");
+        assert!(qs.contains("visible_quantity=1000000&"), "{qs}");
+
+        let json = serde_json::to_string(&order).unwrap();
+        assert!(json.contains(r#""order_price":"9000.5""#), "{json}");
+    }
+
+    #[test]
+    fn f64_prices_are_checked_against_the_tick() {
+        let info = SymbolInfo {
+            symbol: "SPOT_BTC_USDT".to_string(),
+            quote_min: dec!(0.01),
+            quote_max: dec!(100000),
+            quote_tick: dec!(0.01),
+            base_min: dec!(0.0001),
+            base_max: dec!(20),
+            base_tick: dec!(0.0001),
+            min_notional: dec!(0.01),
+            price_range: None,
+        };
+        let order = |price, noise| {
+            WooOrder::from_f64_checked(&info, "BUY", OrderType::Limit, price, 0.1, noise)
+        };
+
+        let clean = order(0.1, FloatNoise::Reject).unwrap();
+        assert_eq!(clean.order_price, Some(dec!(0.1)));
+        assert_eq!(clean.order_quantity, Some(dec!(0.1)));
+        assert!(info.validate(&clean).is_ok());
+
+        let noisy = 0.1 + 0.2;
+        assert_ne!(noisy, 0.3);
+        let err = order(noisy, FloatNoise::Reject).unwrap_err();
+        assert!(err.to_string().contains("is 0.3 plus float rounding noise"));
+        let warned = order(noisy, FloatNoise::Warn).unwrap();
+        assert_eq!(warned.order_price, Some(dec!(0.3)));
+
+        let err = order(0.305, FloatNoise::Warn).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("price 0.305 not a multiple of 0.01"));
+    }
+
+    #[test]
+    fn amount_sized_order_response_has_no_quantity() {
+        let res: SendOrderRes = serde_json::from_str(
+            r#"{"success":true,"timestamp":"1578565539.808","order_id":9,"order_type":"MARKET","client_order_id":0,"order_amount":100}"#,
+        )
+        .unwrap();
+
+        assert_eq!(res.order_quantity, None);
+        assert_eq!(res.order_amount, Some(100.0));
+    }
+
+    fn funding_rate(next_funding_time: i64) -> FundingRate {
+        FundingRate {
+            symbol: "PERP_BTC_USDT".to_string(),
+            est_funding_rate: 0.0001,
+            est_funding_rate_timestamp: 1_683_880_020_000,
+            last_funding_rate: 0.0002,
+            last_funding_rate_timestamp: 1_683_878_400_000,
+            next_funding_time,
+            timestamp: 1_683_880_020_000,
+        }
+    }
+
+    #[test]
+    fn funding_countdown_measures_from_the_given_clock() {
+        let now = DateTime::from_timestamp_millis(1_683_880_020_500).unwrap();
+        let countdown = FundingCountdown::at(&funding_rate(1_683_907_200_000), now);
+
+        assert_eq!(countdown.seconds_until_next, 27_179);
+        assert_eq!(countdown.est_funding_rate, 0.0001);
+    }
+
+    #[test]
+    fn funding_countdown_bottoms_out_at_zero() {
+        let now = DateTime::from_timestamp_millis(1_683_907_260_000).unwrap();
+        let countdown = FundingCountdown::at(&funding_rate(1_683_907_200_000), now);
+
+        assert_eq!(countdown.seconds_until_next, 0);
+    }
+
+    fn row(quantity: Decimal, executed: Decimal) -> Row {
+        Row {
+            side: "BUY".to_string(),
+            status: "PARTIAL_FILLED".to_string(),
+            symbol: "SPOT_BTC_USDT".to_string(),
+            client_order_id: None,
+            reduce_only: false,
+            order_id: OrderId(1),
+            order_tag: "default".to_string(),
+            r#type: "LIMIT".to_string(),
+            price: dec!(9000),
+            quantity,
+            amount: None,
+            visible: quantity,
+            executed,
+            total_fee: Decimal::ZERO,
+            fee_asset: None,
+            total_rebate: None,
+            rebate_asset: None,
+            created_time: DateTime::from_timestamp_millis(1_577_349_119_330).unwrap(),
+            updated_time: DateTime::from_timestamp_millis(1_577_349_119_330).unwrap(),
+            average_executed_price: None,
+            position_side: "BOTH".to_string(),
+            realized_pnl: None,
+        }
+    }
+
+    #[test]
+    fn responses_round_trip_through_serde() {
+        let mut original = row(dec!(1), dec!(0.4));
+        original.client_order_id = Some(ClientOrderId::Text("desk-1".to_string()));
+        let orders = GetOrderRes {
+            success: true,
+            meta: Meta {
+                total: 1,
+                records_per_page: 25,
+                current_page: 1,
+            },
+            rows: vec![original.clone()],
+        };
+        let json = serde_json::to_string(&orders).unwrap();
+        assert_eq!(serde_json::from_str::<GetOrderRes>(&json).unwrap(), orders);
+        assert_eq!(original.order_status(), OrderStatus::PartialFilled);
+
+        let sent: SendOrderRes = serde_json::from_str(
+            r#"{"success":true,"timestamp":"1601471652.928","order_id":13,"order_type":"LIMIT","client_order_id":0,"order_price":9000,"order_quantity":0.1,"order_amount":null,"reduce_only":false}"#,
+        )
+        .unwrap();
+        let json = serde_json::to_string(&sent).unwrap();
+        assert_eq!(serde_json::from_str::<SendOrderRes>(&json).unwrap(), sent);
+
+        let cancelled: CancelOrderRes =
+            serde_json::from_str(r#"{"success":true,"status":"CANCEL_SENT"}"#).unwrap();
+        assert_eq!(cancelled.status, CancelStatus::CancelSent);
+        let json = serde_json::to_string(&cancelled).unwrap();
+        assert_eq!(
+            serde_json::from_str::<CancelOrderRes>(&json).unwrap(),
+            cancelled
+        );
+    }
+
+    #[test]
+    fn text_client_order_ids_are_checked_locally() {
+        assert!(ClientOrderId::text("3f2b8c1e-5d4a-4e6f-9a7b-0c1d2e3f4a5b").is_ok());
+        assert!(ClientOrderId::text("")
+            .unwrap_err()
+            .to_string()
+            .contains("1 to 36 characters"));
+        assert!(ClientOrderId::text("a".repeat(37)).is_err());
+        assert!(ClientOrderId::text("desk/1")
+            .unwrap_err()
+            .to_string()
+            .contains("must not contain '/'"));
+
+        let mut order = WooOrder::limit("SPOT_BTC_USDT", "BUY", dec!(9000), dec!(1));
+        order.client_order_id = Some(ClientOrderId::Text("desk 1".to_string()));
+        assert!(order.validate_type().is_err());
+        order.client_order_id = Some(ClientOrderId::Text("0042".to_string()));
+        assert!(order.validate_type().is_err());
+        assert_eq!(
+            ClientOrderId::text("42").unwrap(),
+            ClientOrderId::Numeric(42)
+        );
+        assert!(ClientOrderId::text("0042").is_err());
+
+        let numeric: ClientOrderId = serde_json::from_str("42").unwrap();
+        assert_eq!(numeric, ClientOrderId::Numeric(42));
+        let text: ClientOrderId = serde_json::from_str(r#""desk-1""#).unwrap();
+        assert_eq!(text, ClientOrderId::Text("desk-1".to_string()));
+    }
+
+    #[test]
+    fn partially_filled_row_resubmits_what_is_left() {
+        let mut partial = row(dec!(1), dec!(0.4));
+        partial.order_tag = "mm".to_string();
+
+        let order = partial.as_new_order().unwrap();
+        assert_eq!(order.symbol, "SPOT_BTC_USDT");
+        assert_eq!(order.side, "BUY");
+        assert_eq!(order.order_type, "LIMIT");
+        assert_eq!(order.order_price, Some(dec!(9000)));
+        assert_eq!(order.order_quantity, Some(partial.remaining()));
+        assert_eq!(order.order_quantity, Some(dec!(0.6)));
+        assert_eq!(order.order_tag.as_deref(), Some("mm"));
+        assert_eq!(order.position_side, None);
+
+        let mut filled = row(dec!(1), dec!(1));
+        filled.status = "FILLED".to_string();
+        assert!(filled
+            .as_new_order()
+            .unwrap_err()
+            .to_string()
+            .contains("is FILLED"));
+    }
+
+    #[test]
+    fn order_type_validation_names_the_field() {
+        let err = |order: WooOrder| order.validate_type().unwrap_err().to_string();
+
+        let mut pegged = WooOrder::pegged_ask("SPOT_BTC_USDT", dec!(1));
+        assert_eq!(pegged.validate_type().unwrap(), OrderType::Ask);
+        pegged.order_price = Some(dec!(9000));
+        assert!(err(pegged).contains("ASK order must not set order_price"));
+
+        let mut ioc = WooOrder::ioc("SPOT_BTC_USDT", "BUY", dec!(9000), dec!(1));
+        assert_eq!(ioc.validate_type().unwrap(), OrderType::Ioc);
+        ioc.order_price = None;
+        assert!(err(ioc).contains("IOC order requires order_price"));
+
+        let quote_sized_limit = WooOrder::builder("SPOT_BTC_USDT", "BUY", "LIMIT")
+            .price(dec!(9000))
+            .size(OrderSize::Amount(dec!(100)))
+            .build();
+        assert!(err(quote_sized_limit).contains("must not set order_amount"));
+
+        let quote_sized_market = WooOrder::market_by_amount("SPOT_BTC_USDT", dec!(100));
+        assert_eq!(quote_sized_market.side, "BUY");
+        assert!(quote_sized_market.validate_type().is_ok());
+
+        let quote_sized_sell =
+            WooOrder::market("SPOT_BTC_USDT", "SELL", OrderSize::Amount(dec!(100)));
+        assert!(err(quote_sized_sell).contains("only be sized with order_amount when buying SPOT_"));
+        let quote_sized_perp = WooOrder::market_by_amount("PERP_BTC_USDT", dec!(100));
+        assert!(err(quote_sized_perp).contains("only be sized with order_amount when buying SPOT_"));
+
+        let mut unsized_market =
+            WooOrder::market("SPOT_BTC_USDT", "BUY", OrderSize::Quantity(dec!(1)));
+        unsized_market.order_quantity = None;
+        assert!(
+            err(unsized_market.clone()).contains("exactly one of order_quantity and order_amount")
+        );
+        unsized_market.order_quantity = Some(dec!(1));
+        unsized_market.order_amount = Some(dec!(100));
+        assert!(err(unsized_market).contains("exactly one of order_quantity and order_amount"));
+
+        let unknown = WooOrder::builder("SPOT_BTC_USDT", "BUY", "STOP")
+            .size(OrderSize::Quantity(dec!(1)))
+            .build();
+        assert!(err(unknown).contains("unknown order_type \"STOP\""));
+    }
+
+    #[test]
+    fn reduce_only_needs_a_perp_and_in_hedge_mode_a_position_side() {
+        let close_long = |symbol: &str| {
+            WooOrder::builder(symbol, "SELL", "MARKET")
+                .size(OrderSize::Quantity(dec!(1)))
+                .reduce_only(true)
+                .build()
+        };
+
+        let spot = close_long("SPOT_BTC_USDT").validate_reduce_only(false);
+        assert!(spot
+            .unwrap_err()
+            .to_string()
+            .contains("only allowed on PERP_"));
+
+        let mut perp = close_long("PERP_BTC_USDT");
+        assert!(perp.validate_reduce_only(false).is_ok());
+        let err = perp.validate_reduce_only(true).unwrap_err();
+        assert!(err.to_string().contains("requires position_side"));
+
+        perp.position_side = Some("LONG".to_string());
+        assert!(perp.validate_reduce_only(true).is_ok());
+        perp.position_side = Some("SHORT".to_string());
+        let err = perp.validate_reduce_only(true).unwrap_err();
+        assert!(err.to_string().contains("SELL can not reduce a SHORT"));
+
+        let mut spot = close_long("SPOT_BTC_USDT");
+        spot.reduce_only = Some(false);
+        assert!(spot.validate_reduce_only(true).is_ok());
+    }
+
+    #[test]
+    fn iterates_order_rows() {
+        let mut second = row(dec!(1), dec!(1));
+        second.order_id = OrderId(2);
+        let res = GetOrderRes {
+            success: true,
+            meta: Meta {
+                total: 2,
+                records_per_page: 25,
+                current_page: 1,
+            },
+            rows: vec![row(dec!(0.3), dec!(0.1)), second],
+        };
+
+        let mut borrowed = Vec::new();
+        for order in &res {
+            borrowed.push(order.order_id);
+        }
+        assert_eq!(borrowed, [OrderId(1), OrderId(2)]);
+        assert_eq!(res.rows().len(), 2);
+
+        let owned: Vec<Row> = res.into_iter().collect();
+        assert!(owned[1].is_fully_filled());
+    }
+
+    #[test]
+    fn envelope_reads_nested_and_flattened_payloads() {
+        #[derive(Deserialize)]
+        struct SystemStatus {
+            status: u8,
+        }
+
+        let nested: WooEnvelope<SystemStatus> = serde_json::from_str(
+            r#"{"success":true,"data":{"status":0,"msg":"System is functioning properly."},"timestamp":1702989203989}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            nested.timestamp.unwrap().timestamp_millis(),
+            1_702_989_203_989
+        );
+        assert_eq!(nested.into_data().unwrap().status, 0);
+
+        let flattened: WooEnvelope<HoldingRes> =
+            serde_json::from_str(r#"{"success":true,"holding":{"BTC":1.5,"USDT":1000.0}}"#)
+                .unwrap();
+        assert!(flattened.timestamp.is_none());
+        let holding = flattened.into_data().unwrap();
+        assert!(holding.success);
+        assert_eq!(holding.holding["BTC"], 1.5);
+
+        let account: WooEnvelope<AccountInfoRes> = serde_json::from_str(
+            r#"{"success":"true","application":{"application_id":"app","account":"main"},"timestamp":"1601471652.928"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            account.timestamp.unwrap().timestamp_millis(),
+            1_601_471_652_928
+        );
+        assert_eq!(account.into_data().unwrap().application.account, "main");
+    }
+
+    #[test]
+    fn envelope_without_success_is_an_error() {
+        let failed: WooEnvelope<serde_json::Value> =
+            serde_json::from_str(r#"{"success":false}"#).unwrap();
+        assert!(matches!(failed.into_data(), Err(WooError::Api(_))));
+
+        assert!(serde_json::from_str::<WooEnvelope<serde_json::Value>>(r#"{"data":{}}"#).is_err());
+    }
+
+    #[test]
+    fn order_ids_dedup_in_sets() {
+        let ids: std::collections::HashSet<OrderId> =
+            [OrderId(1), OrderId(2), OrderId(1), 2.into()]
+                .into_iter()
+                .collect();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&OrderId(1)));
+
+        let cancel = |order_id| CancelOrder {
+            order_id: OrderId(order_id),
+            symbol: "SPOT_BTC_USDT".to_string(),
+        };
+        let cancels: std::collections::HashSet<_> = [cancel(1), cancel(1), cancel(2)].into();
+        assert_eq!(cancels.len(), 2);
+
+        assert_eq!(serde_json::to_string(&OrderId(7)).unwrap(), "7");
+    }
+
+    #[test]
+    fn partial_fill_progress() {
+        let row = row(dec!(0.3), dec!(0.1));
+
+        assert_eq!(row.remaining(), dec!(0.2));
+        assert_eq!(row.fill_ratio().round_dp(4), dec!(0.3333));
+        assert!(!row.is_fully_filled());
+    }
+
+    #[test]
+    fn full_fill_progress() {
+        let row = row(dec!(0.3), dec!(0.3));
+
+        assert_eq!(row.remaining(), dec!(0));
+        assert_eq!(row.fill_ratio(), dec!(1));
+        assert!(row.is_fully_filled());
+    }
+
+    #[test]
+    fn zero_quantity_progress() {
+        let row = row(dec!(0), dec!(0));
+
+        assert_eq!(row.remaining(), dec!(0));
+        assert_eq!(row.fill_ratio(), dec!(0));
+        assert!(!row.is_fully_filled());
+    }
+
+    #[test]
+    fn iceberg_rejects_visible_above_total() {
+        let order =
+            WooOrder::iceberg("SPOT_BTC_USDT", "BUY", dec!(9000), dec!(1), dec!(0.1)).unwrap();
+        assert_eq!(order.visible_quantity, Some(dec!(0.1)));
+        assert_eq!(order.size(), Some(OrderSize::Quantity(dec!(1))));
+
+        let err = WooOrder::iceberg("SPOT_BTC_USDT", "BUY", dec!(9000), dec!(1), dec!(2));
+        assert!(matches!(err, Err(WooError::InvalidOrder(_))));
+    }
+
+    fn book() -> Orderbook {
+        let level = |price, quantity| BookLevel { price, quantity };
+        Orderbook {
+            asks: vec![level(dec!(101), dec!(1)), level(dec!(102), dec!(2))],
+            bids: vec![level(dec!(99), dec!(1)), level(dec!(98), dec!(3))],
+            timestamp: 1_578_565_539_808,
+        }
+    }
+
+    #[test]
+    fn mid_and_spread_of_the_touch() {
+        let book = book();
+
+        assert_eq!(book.best_bid().unwrap().price, dec!(99));
+        assert_eq!(book.best_ask().unwrap().price, dec!(101));
+        assert_eq!(book.mid_price(), Some(dec!(100)));
+        assert_eq!(book.spread(), Some(dec!(2)));
+    }
+
+    #[test]
+    fn one_sided_book_has_no_mid_or_spread() {
+        let mut book = book();
+        book.asks.clear();
+
+        assert_eq!(book.best_ask(), None);
+        assert!(book.best_bid().is_some());
+        assert_eq!(book.mid_price(), None);
+        assert_eq!(book.spread(), None);
+
+        book.bids.clear();
+        assert_eq!(book.best_bid(), None);
+        assert_eq!(book.mid_price(), None);
+    }
+
+    #[test]
+    fn vwap_walks_levels() {
+        let estimate = book().vwap_for_quantity(Side::Buy, dec!(2));
+
+        assert_eq!(
+            estimate,
+            VwapEstimate::Filled {
+                vwap: dec!(101.5),
+                worst_price: dec!(102),
+                levels: 2,
+            }
+        );
+        assert_eq!(
+            book().quantity_available_within(Side::Sell, dec!(98.5)),
+            dec!(1)
+        );
+        assert_eq!(
+            book().quantity_available_within(Side::Buy, dec!(102)),
+            dec!(3)
+        );
+    }
+
+    #[test]
+    fn vwap_of_nothing_is_not_a_division_by_zero() {
+        assert_eq!(
+            book().vwap_for_quantity(Side::Buy, Decimal::ZERO),
+            VwapEstimate::NothingToFill
+        );
+        assert_eq!(
+            book().vwap_for_quantity(Side::Sell, dec!(-1)),
+            VwapEstimate::NothingToFill
+        );
+    }
+
+    #[test]
+    fn vwap_reports_exhausted_book() {
+        let estimate = book().vwap_for_quantity(Side::Sell, dec!(10));
+
+        assert_eq!(
+            estimate,
+            VwapEstimate::Exhausted {
+                levels: 2,
+                available: dec!(4),
+            }
+        );
+    }
+
+    // Responses as WOO sends them, one per endpoint, so a field renamed on
+    // either side fails here rather than reading as `None` in production.
+    #[test]
+    fn captured_v1_responses_decode_field_by_field() {
+        let orders: GetOrderRes = serde_json::from_str(concat!(
+            r#"{"success":true,"meta":{"total":31,"records_per_page":25,"current_page":1},"#,
+            r#""rows":[{"side":"SELL","status":"PARTIAL_FILLED","symbol":"SPOT_BTC_USDT","#,
+            r#""client_order_id":123,"reduce_only":false,"order_id":8197,"order_tag":"default","#,
+            r#""type":"LIMIT","price":30851,"quantity":0.002,"amount":null,"visible":0.002,"#,
+            r#""executed":0.001,"total_fee":0.0123,"fee_asset":"USDT","total_rebate":null,"#,
+            r#""rebate_asset":null,"created_time":"1575014255.089","#,
+            r#""updated_time":"1575014255.910","average_executed_price":30851,"#,
+            r#""position_side":"BOTH","realized_pnl":null}]}"#,
+        ))
+        .unwrap();
+        let row = &orders.rows[0];
+        assert_eq!(orders.meta.records_per_page, 25);
+        assert_eq!(row.client_order_id, Some(ClientOrderId::Numeric(123)));
+        assert_eq!(row.fee_asset.as_deref(), Some("USDT"));
+        assert_eq!(row.average_executed_price, Some(dec!(30851)));
+        assert_eq!(row.updated_time.timestamp_millis(), 1575014255910);
+
+        let order: OrderDetail = serde_json::from_str(concat!(
+            r#"{"success":true,"created_time":"1577349119.33","side":"SELL","status":"FILLED","#,
+            r#""symbol":"SPOT_BTC_USDT","client_order_id":0,"reduce_only":false,"order_id":1,"#,
+            r#""order_tag":"default","type":"LIMIT","price":123,"quantity":0.1,"amount":null,"#,
+            r#""visible":0.1,"executed":0.1,"total_fee":0.00123,"fee_asset":"USDT","#,
+            r#""total_rebate":null,"rebate_asset":null,"average_executed_price":123,"#,
+            r#""realized_pnl":null,"positionSide":"BOTH","Transactions":[]}"#,
+        ))
+        .unwrap();
+        assert_eq!(order.status, OrderStatus::Filled);
+        assert_eq!(order.average_executed_price, Some(dec!(123)));
+        assert_eq!(order.order_tag.as_deref(), Some("default"));
+
+        let trades: GetTradesRes = serde_json::from_str(concat!(
+            r#"{"success":true,"meta":{"total":1,"records_per_page":25,"current_page":1},"#,
+            r#""rows":[{"id":5,"symbol":"SPOT_BTC_USDT","order_id":211,"order_tag":"default","#,
+            r#""executed_price":10892.84,"executed_quantity":0.002,"is_maker":0,"side":"SELL","#,
+            r#""fee":0,"fee_asset":"USDT","executed_timestamp":"1566264290.250"}]}"#,
+        ))
+        .unwrap();
+        assert_eq!(trades.rows[0].executed_price, dec!(10892.84));
+        assert!(!trades.rows[0].is_maker);
+
+        let rate: FundingRate = serde_json::from_str(concat!(
+            r#"{"success":true,"timestamp":1681069199002,"symbol":"PERP_BTC_USDT","#,
+            r#""est_funding_rate":-0.00001392,"est_funding_rate_timestamp":1681069199002,"#,
+            r#""last_funding_rate":-0.00001262,"last_funding_rate_timestamp":1681066800000,"#,
+            r#""next_funding_time":1681070400000,"last_funding_rate_interval":1,"#,
+            r#""est_funding_rate_interval":1}"#,
+        ))
+        .unwrap();
+        assert_eq!(rate.next_funding_time, 1681070400000);
+
+        let info: ExchangeInfoRes = serde_json::from_str(concat!(
+            r#"{"success":true,"rows":[{"created_time":"1575441595.65","#,
+            r#""updated_time":"1575441595.65","symbol":"SPOT_BTC_USDT","quote_min":100,"#,
+            r#""quote_max":100000,"quote_tick":0.01,"base_min":0.0001,"base_max":20,"#,
+            r#""base_tick":0.0001,"min_notional":0.02,"price_range":0.99,"price_scope":null,"#,
+            r#""precisions":[1,10,100,500,1000,10000]}]}"#,
+        ))
+        .unwrap();
+        assert_eq!(info.rows[0].price_range, Some(dec!(0.99)));
+
+        let account: AccountInfoRes = serde_json::from_str(concat!(
+            r#"{"success":true,"application":{"#,
+            r#""application_id":"8935820a-6600-4c2c-9bc3-f017d89aa173","#,
+            r#""account":"CLIENT_ACCOUNT_01","alias":"CLIENT_ACCOUNT_01","#,
+            r#""account_mode":"FUTURES","leverage":5,"taker_fee_rate":0.0005,"#,
+            r#""maker_fee_rate":0.0002,"futures_leverage":5,"otpauth":false}}"#,
+        ))
+        .unwrap();
+        assert_eq!(account.application.account_mode, Some(AccountMode::Futures));
+        assert_eq!(account.application.maker_fee_rate, Some(0.0002));
+    }
+
+    #[test]
+    fn positions_read_camel_case_under_data_and_the_old_flat_shape() {
+        let current: WooEnvelope<PositionsRes> = serde_json::from_str(concat!(
+            r#"{"success":true,"data":{"positions":[{"symbol":"PERP_BTC_USDT","holding":0.1,"#,
+            r#""pendingLongQty":0,"pendingShortQty":0.05,"settlePrice":26900,"#,
+            r#""averageOpenPrice":26800,"pnl24H":0,"fee24H":0.0048,"markPrice":27000,"#,
+            r#""estLiqPrice":21000,"timestamp":1685154032762}]},"timestamp":1685154032763}"#,
+        ))
+        .unwrap();
+        let position = &current.data.positions[0];
+        assert_eq!(position.pending_short_qty, Some(0.05));
+        assert_eq!(position.average_open_price, 26800.0);
+        assert_eq!(position.est_liq_price, Some(21000.0));
+
+        let flat: PositionsRes = serde_json::from_str(concat!(
+            r#"{"success":true,"free_collateral":1000,"total_collateral":1500,"#,
+            r#""positions":[{"symbol":"PERP_BTC_USDT","holding":0.1,"pending_long_qty":0,"#,
+            r#""pending_short_qty":0.05,"average_open_price":26800,"mark_price":27000,"#,
+            r#""est_liq_price":21000,"timestamp":"1685154032.762"}]}"#,
+        ))
+        .unwrap();
+        assert_eq!(flat.free_collateral, Some(1000.0));
+        assert_eq!(flat.positions[0].pending_short_qty, Some(0.05));
+        assert_eq!(flat.positions[0].mark_price, position.mark_price);
+
+        assert_eq!(
+            position.timestamp.unwrap().timestamp_millis(),
+            1685154032762
+        );
+        assert_eq!(flat.positions[0].timestamp, position.timestamp);
+
+        // serialized as the crate always did, and read back
+        let serialized = serde_json::to_value(&flat).unwrap();
+        assert_eq!(serialized["free_collateral"], 1000.0);
+        assert_eq!(serialized["positions"][0]["average_open_price"], 26800.0);
+        let read_back: PositionsRes = serde_json::from_value(serialized).unwrap();
+        assert_eq!(read_back.positions[0].timestamp, position.timestamp);
+    }
 }
@@ -0,0 +1,277 @@
+use crate::constants::{WOO_WS_PUBLIC_URL, WOO_WS_PUBLIC_URL_STAGING};
+use crate::error::WooError;
+use crate::woo::Environment;
+use futures::stream::{self, Stream};
+use futures::{SinkExt, StreamExt};
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use url::Url;
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Where to connect, what to subscribe to and how hard to try to stay
+/// connected.
+#[derive(Debug, Clone)]
+pub struct WsConfig {
+    url: Url,
+    topics: Vec<String>,
+    max_reconnect_attempts: Option<u32>,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl WsConfig {
+    pub fn new(environment: Environment, application_id: &str) -> Self {
+        let base = match environment {
+            Environment::Production => WOO_WS_PUBLIC_URL,
+            Environment::Staging => WOO_WS_PUBLIC_URL_STAGING,
+        };
+
+        WsConfig::with_url(Url::parse(&format!("{base}/{application_id}")).unwrap())
+    }
+
+    /// Connects to `url` as is, e.g. a gateway or a test server.
+    pub fn with_url(url: Url) -> Self {
+        Self {
+            url,
+            topics: Vec::new(),
+            max_reconnect_attempts: None,
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+        }
+    }
+
+    /// Subscribes to `topic`, e.g. `SPOT_BTC_USDT@orderbook`, on every
+    /// connection.
+    pub fn subscribe(mut self, topic: impl Into<String>) -> Self {
+        self.topics.push(topic.into());
+        self
+    }
+
+    /// Gives up after `attempts` failed connection attempts in a row. Retries
+    /// forever by default.
+    pub fn max_reconnect_attempts(mut self, attempts: u32) -> Self {
+        self.max_reconnect_attempts = Some(attempts);
+        self
+    }
+
+    /// Waits `initial` after the first failed attempt, doubling up to `max`.
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    fn backoff_after(&self, failed_attempts: u32) -> Duration {
+        let factor = 2u32.saturating_pow(failed_attempts.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WsMessage {
+    /// A push on a subscribed topic.
+    Data {
+        topic: String,
+        ts: i64,
+        data: serde_json::Value,
+    },
+    /// Anything else WOO sent, e.g. subscription acknowledgements.
+    Event(serde_json::Value),
+    /// The connection dropped and was re-established with every subscription
+    /// replayed. Updates in between were missed, so local state built from
+    /// the stream should be resynced over REST.
+    Reconnected,
+}
+
+#[derive(Deserialize)]
+struct Push {
+    topic: String,
+    ts: i64,
+    data: serde_json::Value,
+}
+
+pub struct WooWsPublic;
+
+impl WooWsPublic {
+    /// Streams every message of `config`'s topics, reconnecting with backoff
+    /// whenever the connection drops. Ends with an error once
+    /// [`WsConfig::max_reconnect_attempts`] attempts in a row have failed.
+    pub fn reconnecting(config: WsConfig) -> impl Stream<Item = Result<WsMessage, WooError>> {
+        let state = Reconnecting {
+            config,
+            socket: None,
+            failed_attempts: 0,
+            connected_before: false,
+            done: false,
+        };
+
+        stream::unfold(state, |mut state| async move {
+            let message = state.next_message().await?;
+            Some((message, state))
+        })
+    }
+}
+
+struct Reconnecting {
+    config: WsConfig,
+    socket: Option<Socket>,
+    failed_attempts: u32,
+    connected_before: bool,
+    done: bool,
+}
+
+impl Reconnecting {
+    async fn next_message(&mut self) -> Option<Result<WsMessage, WooError>> {
+        loop {
+            if self.done {
+                return None;
+            }
+
+            let Some(socket) = &mut self.socket else {
+                match connect(&self.config).await {
+                    Ok(socket) => {
+                        self.socket = Some(socket);
+                        self.failed_attempts = 0;
+                        if std::mem::replace(&mut self.connected_before, true) {
+                            return Some(Ok(WsMessage::Reconnected));
+                        }
+                    }
+                    Err(err) => {
+                        self.failed_attempts += 1;
+                        tracing::warn!(attempt = self.failed_attempts, %err, "websocket connect failed");
+                        if self
+                            .config
+                            .max_reconnect_attempts
+                            .is_some_and(|max| self.failed_attempts >= max)
+                        {
+                            self.done = true;
+                            return Some(Err(err));
+                        }
+                        tokio::time::sleep(self.config.backoff_after(self.failed_attempts)).await;
+                    }
+                }
+                continue;
+            };
+
+            match socket.next().await {
+                Some(Ok(Message::Text(text))) => {
+                    let Ok(value) = serde_json::from_str::<serde_json::Value>(&text) else {
+                        continue;
+                    };
+                    if value["event"] == "ping" {
+                        let pong = serde_json::json!({ "event": "pong", "ts": value["ts"] });
+                        if socket.send(Message::text(pong.to_string())).await.is_err() {
+                            self.socket = None;
+                        }
+                        continue;
+                    }
+                    return Some(Ok(match serde_json::from_value::<Push>(value.clone()) {
+                        Ok(Push { topic, ts, data }) => WsMessage::Data { topic, ts, data },
+                        Err(_) => WsMessage::Event(value),
+                    }));
+                }
+                // tungstenite answers protocol pings itself
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => {
+                    tracing::info!("websocket disconnected, reconnecting");
+                    self.socket = None;
+                }
+                Some(Ok(_)) => {}
+            }
+        }
+    }
+}
+
+async fn connect(config: &WsConfig) -> Result<Socket, WooError> {
+    let (mut socket, _) = tokio_tungstenite::connect_async(config.url.as_str()).await?;
+
+    for topic in &config.topics {
+        let subscribe = serde_json::json!({ "id": topic, "topic": topic, "event": "subscribe" });
+        socket.send(Message::text(subscribe.to_string())).await?;
+    }
+
+    Ok(socket)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    async fn expect_subscribe(socket: &mut WebSocketStream<TcpStream>) -> String {
+        match socket.next().await {
+            Some(Ok(Message::Text(text))) => {
+                let value: serde_json::Value = serde_json::from_str(&text).unwrap();
+                assert_eq!(value["event"], "subscribe");
+                value["topic"].as_str().unwrap().to_string()
+            }
+            other => panic!("expected a subscribe, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn reconnects_and_resubscribes_after_the_server_drops() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("ws://{}", listener.local_addr().unwrap())).unwrap();
+
+        let server = tokio::spawn(async move {
+            let mut subscriptions = Vec::new();
+            for ts in [1, 2] {
+                let (tcp, _) = listener.accept().await.unwrap();
+                let mut socket = tokio_tungstenite::accept_async(tcp).await.unwrap();
+                subscriptions.push(expect_subscribe(&mut socket).await);
+
+                let push = serde_json::json!({
+                    "topic": "SPOT_BTC_USDT@bbo", "ts": ts, "data": { "bid": 9000 },
+                });
+                socket.send(Message::text(push.to_string())).await.unwrap();
+                // dropping the socket kills the connection without a close frame
+            }
+            subscriptions
+        });
+
+        let config = WsConfig::with_url(url)
+            .subscribe("SPOT_BTC_USDT@bbo")
+            .backoff(Duration::from_millis(10), Duration::from_millis(10));
+        let messages: Vec<_> = WooWsPublic::reconnecting(config)
+            .take(3)
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        let data = |ts| WsMessage::Data {
+            topic: "SPOT_BTC_USDT@bbo".to_string(),
+            ts,
+            data: serde_json::json!({ "bid": 9000 }),
+        };
+        assert_eq!(messages, [data(1), WsMessage::Reconnected, data(2)]);
+        assert_eq!(
+            server.await.unwrap(),
+            ["SPOT_BTC_USDT@bbo", "SPOT_BTC_USDT@bbo"]
+        );
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_reconnect_attempts() {
+        // bind and release a port so nothing listens on it
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("ws://{}", listener.local_addr().unwrap())).unwrap();
+        drop(listener);
+
+        let config = WsConfig::with_url(url)
+            .max_reconnect_attempts(2)
+            .backoff(Duration::from_millis(1), Duration::from_millis(1));
+        let messages: Vec<_> = WooWsPublic::reconnecting(config).collect().await;
+
+        assert_eq!(messages.len(), 1);
+        assert!(matches!(messages[0], Err(WooError::WebSocket(_))));
+    }
+}